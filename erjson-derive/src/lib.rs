@@ -0,0 +1,495 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as Tokens};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, Generics, Ident,
+    LitStr, Result,
+};
+
+#[proc_macro_derive(ToJSON, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromJSON, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+    tag: Option<String>,
+    content: Option<String>,
+    untagged: bool,
+}
+
+fn json_attrs(attrs: &[Attribute]) -> Result<Attrs> {
+    let mut out = Attrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("json")) {
+        attr.parse_nested_meta(|meta| {
+            let string = || -> Result<String> { Ok(meta.value()?.parse::<LitStr>()?.value()) };
+            if meta.path.is_ident("rename") {
+                out.rename = Some(string()?);
+            } else if meta.path.is_ident("tag") {
+                out.tag = Some(string()?);
+            } else if meta.path.is_ident("content") {
+                out.content = Some(string()?);
+            } else if meta.path.is_ident("default") {
+                out.default = true;
+            } else if meta.path.is_ident("skip") {
+                out.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                out.flatten = true;
+            } else if meta.path.is_ident("untagged") {
+                out.untagged = true;
+            } else {
+                return Err(meta.error("unknown json attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+enum Tagging {
+    External,
+    Internal(String),
+    Adjacent(String, String),
+    Untagged,
+}
+
+fn tagging(attrs: &Attrs) -> Result<Tagging> {
+    match (&attrs.tag, &attrs.content, attrs.untagged) {
+        (None, None, false) => Ok(Tagging::External),
+        (Some(tag), None, false) => Ok(Tagging::Internal(tag.clone())),
+        (Some(tag), Some(content), false) => Ok(Tagging::Adjacent(tag.clone(), content.clone())),
+        (None, None, true) => Ok(Tagging::Untagged),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "use either `tag`, `tag` with `content`, or `untagged`",
+        )),
+    }
+}
+
+fn with_bound(generics: &Generics, bound: Tokens) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+struct Field {
+    member: Tokens,
+    binding: Ident,
+    key: String,
+    ty: syn::Type,
+    attrs: Attrs,
+}
+
+fn fields(fields: &Fields) -> Result<Vec<Field>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let attrs = json_attrs(&f.attrs)?;
+            let (member, binding, name) = match &f.ident {
+                Some(ident) => (quote!(#ident), ident.clone(), ident.to_string()),
+                None => {
+                    let index = syn::Index::from(i);
+                    (quote!(#index), format_ident!("f{}", i), i.to_string())
+                }
+            };
+            Ok(Field {
+                member,
+                binding,
+                key: attrs.rename.clone().unwrap_or(name),
+                ty: f.ty.clone(),
+                attrs,
+            })
+        })
+        .collect()
+}
+
+// object built from named fields already bound to local variables
+fn object_to_json(fields: &[Field]) -> Tokens {
+    let inserts = fields.iter().filter(|f| !f.attrs.skip).map(|f| {
+        let (binding, key) = (&f.binding, &f.key);
+        if f.attrs.flatten {
+            quote! {
+                if let ::erjson::JSONValue::Object(inner) = ::erjson::ToJSON::to_json(#binding) {
+                    map.extend(inner);
+                }
+            }
+        } else {
+            quote! {
+                map.insert(#key.to_string(), ::erjson::ToJSON::to_json(#binding));
+            }
+        }
+    });
+    quote! {{
+        let mut map = ::std::collections::HashMap::new();
+        #(#inserts)*
+        ::erjson::JSONValue::Object(map)
+    }}
+}
+
+fn array_to_json(fields: &[Field]) -> Tokens {
+    let bindings = fields.iter().filter(|f| !f.attrs.skip).map(|f| &f.binding);
+    quote! {
+        ::erjson::JSONValue::Array(vec![#(::erjson::ToJSON::to_json(#bindings)),*])
+    }
+}
+
+// destructuring pattern binding every field, e.g. `{ a, b }` or `(f0, f1)`
+fn pattern(kind: &Fields, fields: &[Field]) -> Tokens {
+    let bindings = fields.iter().map(|f| &f.binding);
+    match kind {
+        Fields::Named(_) => quote!({ #(#bindings),* }),
+        Fields::Unnamed(_) => quote!((#(#bindings),*)),
+        Fields::Unit => quote!(),
+    }
+}
+
+fn expand_to_json(input: &DeriveInput) -> Result<Tokens> {
+    let name = &input.ident;
+    let generics = with_bound(&input.generics, quote!(::erjson::ToJSON));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let list = fields(&data.fields)?;
+            let pat = pattern(&data.fields, &list);
+            let value = match &data.fields {
+                Fields::Named(_) => object_to_json(&list),
+                Fields::Unnamed(_) if list.len() == 1 => {
+                    let binding = &list[0].binding;
+                    quote!(::erjson::ToJSON::to_json(#binding))
+                }
+                Fields::Unnamed(_) => array_to_json(&list),
+                Fields::Unit => quote!(::erjson::JSONValue::Null),
+            };
+            quote! {
+                #[allow(unused_variables)]
+                let #name #pat = self;
+                #value
+            }
+        }
+        Data::Enum(data) => {
+            let tagging = tagging(&json_attrs(&input.attrs)?)?;
+            let mut arms = vec![];
+            for variant in &data.variants {
+                let attrs = json_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                let key = attrs.rename.unwrap_or_else(|| ident.to_string());
+                let list = fields(&variant.fields)?;
+                let pat = pattern(&variant.fields, &list);
+                let content = match &variant.fields {
+                    Fields::Named(_) => Some(object_to_json(&list)),
+                    Fields::Unnamed(_) if list.len() == 1 => {
+                        let binding = &list[0].binding;
+                        Some(quote!(::erjson::ToJSON::to_json(#binding)))
+                    }
+                    Fields::Unnamed(_) => Some(array_to_json(&list)),
+                    Fields::Unit => None,
+                };
+                let value = tagged_to_json(&tagging, &key, content, &variant.fields)?;
+                arms.push(quote! {
+                    #[allow(unused_variables)]
+                    #name::#ident #pat => #value,
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::erjson::ToJSON for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::erjson::JSONValue {
+                #body
+            }
+        }
+    })
+}
+
+fn tagged_to_json(
+    tagging: &Tagging,
+    key: &str,
+    content: Option<Tokens>,
+    kind: &Fields,
+) -> Result<Tokens> {
+    let name = quote!(::erjson::JSONValue::String(#key.to_string()));
+    Ok(match (tagging, content) {
+        (Tagging::External, None) => name,
+        (Tagging::External, Some(content)) => quote! {{
+            let mut map = ::std::collections::HashMap::new();
+            map.insert(#key.to_string(), #content);
+            ::erjson::JSONValue::Object(map)
+        }},
+        (Tagging::Internal(tag), content) => {
+            if let Fields::Unnamed(f) = kind {
+                if f.unnamed.len() > 1 {
+                    return Err(Error::new(
+                        Span::call_site(),
+                        "tuple variants cannot be internally tagged",
+                    ));
+                }
+            }
+            let content = content.unwrap_or_else(|| quote!(::erjson::JSONValue::Null));
+            // a newtype holding a non-object value keeps only the tag
+            quote! {{
+                let mut map = match #content {
+                    ::erjson::JSONValue::Object(map) => map,
+                    _ => ::std::collections::HashMap::new(),
+                };
+                map.insert(#tag.to_string(), #name);
+                ::erjson::JSONValue::Object(map)
+            }}
+        }
+        (Tagging::Adjacent(tag, content_key), content) => {
+            let content = content.map(|c| quote!(map.insert(#content_key.to_string(), #c);));
+            quote! {{
+                let mut map = ::std::collections::HashMap::new();
+                map.insert(#tag.to_string(), #name);
+                #content
+                ::erjson::JSONValue::Object(map)
+            }}
+        }
+        (Tagging::Untagged, None) => quote!(::erjson::JSONValue::Null),
+        (Tagging::Untagged, Some(content)) => content,
+    })
+}
+
+// an expression of type `Result<Self, ConversionError>` reading `kind`
+// fields out of the `value: &JSONValue` in scope
+fn fields_from_json(ctor: Tokens, kind: &Fields, list: &[Field]) -> Tokens {
+    match kind {
+        Fields::Named(_) => {
+            let inits = list.iter().map(|f| {
+                let (member, key, ty) = (&f.member, &f.key, &f.ty);
+                let value = if f.attrs.skip {
+                    quote!(::std::default::Default::default())
+                } else if f.attrs.flatten {
+                    quote!(<#ty as ::erjson::FromJSON>::from_json(value)?)
+                } else {
+                    let missing = if f.attrs.default {
+                        quote!(::std::default::Default::default())
+                    } else {
+                        quote! {
+                            <#ty as ::erjson::FromJSON>::from_missing()
+                                .ok_or_else(|| ::erjson::ConversionError::missing(#key))?
+                        }
+                    };
+                    quote! {
+                        match map.get(#key) {
+                            Some(v) => <#ty as ::erjson::FromJSON>::from_json(v).map_err(|e| e.at(#key))?,
+                            None => #missing,
+                        }
+                    }
+                };
+                quote!(#member: #value)
+            });
+            quote! {
+                (|| -> ::std::result::Result<_, ::erjson::ConversionError> {
+                    let map = match value {
+                        ::erjson::JSONValue::Object(map) => map,
+                        v => return Err(::erjson::ConversionError::expected("object", v)),
+                    };
+                    Ok(#ctor { #(#inits),* })
+                })()
+            }
+        }
+        Fields::Unnamed(_) if list.len() == 1 => {
+            let ty = &list[0].ty;
+            quote!(<#ty as ::erjson::FromJSON>::from_json(value).map(#ctor))
+        }
+        Fields::Unnamed(_) => {
+            // skipped fields take no position in the array
+            let len = list.iter().filter(|f| !f.attrs.skip).count();
+            let expected = format!("array of {} items", len);
+            let mut i = 0usize;
+            let items = list.iter().map(|f| {
+                if f.attrs.skip {
+                    return quote!(::std::default::Default::default());
+                }
+                let (ty, index) = (&f.ty, i.to_string());
+                let item = quote!(<#ty as ::erjson::FromJSON>::from_json(&vc[#i]).map_err(|e| e.at(#index))?);
+                i += 1;
+                item
+            });
+            quote! {
+                (|| -> ::std::result::Result<_, ::erjson::ConversionError> {
+                    match value {
+                    ::erjson::JSONValue::Array(vc) if vc.len() == #len => Ok(#ctor(#(#items),*)),
+                        v => Err(::erjson::ConversionError::expected(#expected, v)),
+                    }
+                })()
+            }
+        }
+        Fields::Unit => quote!(Ok::<Self, ::erjson::ConversionError>(#ctor)),
+    }
+}
+
+fn expand_from_json(input: &DeriveInput) -> Result<Tokens> {
+    let name = &input.ident;
+    let generics = with_bound(&input.generics, quote!(::erjson::FromJSON));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let list = fields(&data.fields)?;
+            match &data.fields {
+                Fields::Unit => quote! {
+                    match value {
+                        ::erjson::JSONValue::Null => Ok(#name),
+                        v => Err(::erjson::ConversionError::expected("null", v)),
+                    }
+                },
+                kind => fields_from_json(quote!(#name), kind, &list),
+            }
+        }
+        Data::Enum(data) => {
+            let tagging = tagging(&json_attrs(&input.attrs)?)?;
+            let mut variants = vec![];
+            for variant in &data.variants {
+                let attrs = json_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                let key = attrs.rename.unwrap_or_else(|| ident.to_string());
+                let list = fields(&variant.fields)?;
+                let build = fields_from_json(quote!(#name::#ident), &variant.fields, &list);
+                let unit = matches!(variant.fields, Fields::Unit);
+                variants.push((key, build, unit));
+            }
+            enum_from_json(name, &tagging, &variants)
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::erjson::FromJSON for #name #ty_generics #where_clause {
+            fn from_json(value: &::erjson::JSONValue) -> ::std::result::Result<Self, ::erjson::ConversionError> {
+                #body
+            }
+        }
+    })
+}
+
+fn enum_from_json(name: &Ident, tagging: &Tagging, variants: &[(String, Tokens, bool)]) -> Tokens {
+    let unknown = quote! {
+        other => Err(::erjson::ConversionError::new(format!(
+            "Unknown variant `{}` of {}", other, stringify!(#name)
+        ))),
+    };
+    match tagging {
+        Tagging::External => {
+            let units = variants
+                .iter()
+                .filter(|v| v.2)
+                .map(|(key, build, _)| quote!(#key => #build,));
+            let arms = variants
+                .iter()
+                .map(|(key, build, _)| quote!(#key => #build.map_err(|e| e.at(#key)),));
+            quote! {
+                match value {
+                    ::erjson::JSONValue::String(s) => match s.as_str() {
+                        #(#units)*
+                        #unknown
+                    },
+                    ::erjson::JSONValue::Object(map) if map.len() == 1 => {
+                        let (key, value) = map.iter().next().unwrap();
+                        match key.as_str() {
+                            #(#arms)*
+                            #unknown
+                        }
+                    }
+                    v => Err(::erjson::ConversionError::expected("string or single key object", v)),
+                }
+            }
+        }
+        Tagging::Internal(tag) => {
+            let arms = variants
+                .iter()
+                .map(|(key, build, _)| quote!(#key => #build,));
+            quote! {
+                let map = match value {
+                    ::erjson::JSONValue::Object(map) => map,
+                    v => return Err(::erjson::ConversionError::expected("object", v)),
+                };
+                match map.get(#tag) {
+                    Some(::erjson::JSONValue::String(s)) => match s.as_str() {
+                        #(#arms)*
+                        #unknown
+                    },
+                    Some(v) => Err(::erjson::ConversionError::expected("string", v).at(#tag)),
+                    None => Err(::erjson::ConversionError::missing(#tag)),
+                }
+            }
+        }
+        Tagging::Adjacent(tag, content) => {
+            let arms = variants.iter().map(|(key, build, unit)| {
+                if *unit {
+                    quote!(#key => #build,)
+                } else {
+                    quote! {
+                        #key => match map.get(#content) {
+                            Some(value) => #build.map_err(|e| e.at(#content)),
+                            None => Err(::erjson::ConversionError::missing(#content)),
+                        },
+                    }
+                }
+            });
+            quote! {
+                let map = match value {
+                    ::erjson::JSONValue::Object(map) => map,
+                    v => return Err(::erjson::ConversionError::expected("object", v)),
+                };
+                match map.get(#tag) {
+                    Some(::erjson::JSONValue::String(s)) => match s.as_str() {
+                        #(#arms)*
+                        #unknown
+                    },
+                    Some(v) => Err(::erjson::ConversionError::expected("string", v).at(#tag)),
+                    None => Err(::erjson::ConversionError::missing(#tag)),
+                }
+            }
+        }
+        Tagging::Untagged => {
+            let attempts = variants.iter().map(|(_, build, unit)| {
+                if *unit {
+                    quote! {
+                        if value.is_null() {
+                            return #build;
+                        }
+                    }
+                } else {
+                    quote! {
+                        if let Ok(v) = #build {
+                            return Ok(v);
+                        }
+                    }
+                }
+            });
+            quote! {
+                #(#attempts)*
+                Err(::erjson::ConversionError::new(format!(
+                    "Value does not match any variant of {}", stringify!(#name)
+                )))
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+use erjson::{FromJSON, JSONDocument, JSONValue, ToJSON};
+
+fn json(text: &str) -> JSONValue {
+    JSONDocument::new().parse_string(text.to_string()).unwrap()
+}
+
+#[derive(Debug, PartialEq, Default, ToJSON, FromJSON)]
+struct Audit {
+    created_by: String,
+    version: u32,
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+struct User {
+    #[json(rename = "userName")]
+    name: String,
+    age: Option<u8>,
+    #[json(default)]
+    tags: Vec<String>,
+    #[json(skip)]
+    session: Option<String>,
+    #[json(flatten)]
+    audit: Audit,
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+struct Id(u64);
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+struct Point(i32, i32);
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+struct Sample(String, #[json(skip)] Option<u32>, f64);
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<Box<Page<T>>>,
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+enum External {
+    Empty,
+    Id(Id),
+    Move(i32, i32),
+    #[json(rename = "resize")]
+    Resize {
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+#[json(tag = "type")]
+enum Internal {
+    Ping,
+    Message { text: String },
+    Nested(Audit),
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+#[json(tag = "t", content = "c")]
+enum Adjacent {
+    Stop,
+    Go(u8),
+    Turn { left: bool },
+}
+
+#[derive(Debug, PartialEq, ToJSON, FromJSON)]
+#[json(untagged)]
+enum Untagged {
+    Nothing,
+    Number(f64),
+    Pair(String, String),
+    Named { name: String },
+}
+
+fn roundtrip<T: ToJSON + FromJSON + PartialEq + std::fmt::Debug>(value: T, expected: &str) {
+    let encoded = value.to_json();
+    assert_eq!(encoded, json(expected));
+    assert_eq!(T::from_json(&encoded).unwrap(), value);
+}
+
+#[test]
+fn structs() {
+    let user = User {
+        name: "jo".to_string(),
+        age: None,
+        tags: vec!["a".to_string()],
+        session: None,
+        audit: Audit {
+            created_by: "admin".to_string(),
+            version: 2,
+        },
+    };
+    roundtrip(
+        user,
+        r#"{ "userName": "jo", "age": null, "tags": ["a"], "created_by": "admin", "version": 2 }"#,
+    );
+
+    let user = User::from_json(&json(
+        r#"{ "userName": "jo", "session": "x", "created_by": "me", "version": 1 }"#,
+    ))
+    .unwrap();
+    assert_eq!((user.age, user.tags.len(), user.session), (None, 0, None));
+
+    roundtrip(Id(7), "7");
+    roundtrip(Point(1, -2), "[1, -2]");
+    roundtrip(Sample("a".to_string(), None, 1.5), r#"["a", 1.5]"#);
+    let sample = Sample("a".to_string(), Some(3), 1.5).to_json();
+    assert_eq!(Sample::from_json(&sample).unwrap().1, None);
+    roundtrip(
+        Page {
+            items: vec![1u8, 2],
+            next: Some(Box::new(Page {
+                items: vec![3],
+                next: None,
+            })),
+        },
+        r#"{ "items": [1, 2], "next": { "items": [3], "next": null } }"#,
+    );
+}
+
+#[test]
+fn enums() {
+    roundtrip(External::Empty, r#""Empty""#);
+    roundtrip(External::Id(Id(3)), r#"{ "Id": 3 }"#);
+    roundtrip(External::Move(1, 2), r#"{ "Move": [1, 2] }"#);
+    roundtrip(
+        External::Resize {
+            width: 1,
+            height: 2,
+        },
+        r#"{ "resize": { "width": 1, "height": 2 } }"#,
+    );
+
+    roundtrip(Internal::Ping, r#"{ "type": "Ping" }"#);
+    roundtrip(
+        Internal::Message {
+            text: "hi".to_string(),
+        },
+        r#"{ "type": "Message", "text": "hi" }"#,
+    );
+    roundtrip(
+        Internal::Nested(Audit::default()),
+        r#"{ "type": "Nested", "created_by": "", "version": 0 }"#,
+    );
+
+    roundtrip(Adjacent::Stop, r#"{ "t": "Stop" }"#);
+    roundtrip(Adjacent::Go(4), r#"{ "t": "Go", "c": 4 }"#);
+    roundtrip(
+        Adjacent::Turn { left: true },
+        r#"{ "t": "Turn", "c": { "left": true } }"#,
+    );
+
+    roundtrip(Untagged::Nothing, "null");
+    roundtrip(Untagged::Number(1.5), "1.5");
+    roundtrip(
+        Untagged::Pair("a".to_string(), "b".to_string()),
+        r#"["a", "b"]"#,
+    );
+    roundtrip(
+        Untagged::Named {
+            name: "n".to_string(),
+        },
+        r#"{ "name": "n" }"#,
+    );
+}
+
+#[test]
+fn error_paths() {
+    let err = User::from_json(&json(r#"{ "userName": "jo", "created_by": "me" }"#)).unwrap_err();
+    assert_eq!(err.path(), "/version");
+    assert_eq!(err.message(), "Missing field `version`");
+
+    let pages = json(r#"{ "items": [1], "next": { "items": [2, "x"] } }"#);
+    let err = Page::<u8>::from_json(&pages).unwrap_err();
+    assert_eq!(err.path(), "/next/items/1");
+
+    let err = External::from_json(&json(r#"{ "resize": { "width": -1 } }"#)).unwrap_err();
+    assert_eq!(err.path(), "/resize/width");
+    let err = Adjacent::from_json(&json(r#"{ "t": "Go", "c": true }"#)).unwrap_err();
+    assert_eq!(err.path(), "/c");
+    assert!(Internal::from_json(&json(r#"{ "type": "Pong" }"#))
+        .unwrap_err()
+        .message()
+        .contains("Unknown variant `Pong`"));
+    assert!(Untagged::from_json(&json("true")).is_err());
+
+    let mut map = BTreeMap::new();
+    map.insert("p".to_string(), Point(0, 0));
+    assert_eq!(map.to_json(), json(r#"{ "p": [0, 0] }"#));
+}
@@ -13,15 +13,12 @@ fn main() {
     let mut doc = JSONDocument::new();
     match doc.parse_string(json) {
         Ok(ref mut v) => {
-            println!("name: {}", v.get("name").unwrap()); // John Doe
-            println!("age: {}", v.get("age").unwrap()); // 43
-            match v {
-                JSONValue::Object(hm) => {
-                    *hm.get_mut("age").unwrap() = JSONValue::Number(45f64);
-                }
-                _ => {}
-            };
-            println!("age: {}", v.get("age").unwrap()); // 45
+            println!("name: {}", v["name"]); // John Doe
+            println!("age: {}", v["age"]); // 43
+            v["age"] = JSONValue::from(45);
+            println!("age: {}", v["age"]); // 45
+            println!("weight: {}", v["props"]["weight"]); // 76
+            println!("prime: {}", v["primes"][2]); // 17
         }
         Err(err) => print!("err: {}", err),
     }
@@ -12,7 +12,7 @@ fn main() {
     let json = String::from(data);
     let mut doc = JSONDocument::new();
     match doc.parse_string(json) {
-        Ok(v) => println!("print: {}", v.to_string()),
+        Ok(v) => println!("print: {}", v),
         Err(err) => print!("err: {}", err),
     }
 }
@@ -10,7 +10,7 @@ pub enum LineBuffer<'a> {
 }
 
 impl<'a> LineBuffer<'a> {
-    pub fn from_string(text: &'a String) -> Self {
+    pub fn from_string(text: &'a str) -> Self {
         LineBuffer::Text(text.lines())
     }
 
@@ -23,26 +23,20 @@ impl<'a> Iterator for LineBuffer<'a> {
     type Item = String;
 
     fn next(&mut self) -> Option<String> {
-        return match self {
-            LineBuffer::Text(it) => match it.next() {
-                Some(ref s) => {
-                    let mut s = s.to_string();
+        match self {
+            LineBuffer::Text(it) => it.next().map(|s| {
+                let mut s = s.to_string();
+                s.push('\n');
+                s
+            }),
+            LineBuffer::Reader(it) => match it.next() {
+                Some(Ok(mut s)) => {
                     s.push('\n');
                     Some(s)
                 }
-                None => None,
-            },
-            LineBuffer::Reader(it) => match it.next() {
-                Some(r) => match r {
-                    Ok(mut s) => {
-                        s.push('\n');
-                        Some(s)
-                    }
-                    _ => None,
-                },
-                None => None,
+                _ => None,
             },
-        };
+        }
     }
 }
 
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+
+use crate::traits::{ConversionError, FromJSON};
+use crate::value::JSONValue;
+
+impl From<()> for JSONValue {
+    fn from(_: ()) -> Self {
+        JSONValue::Null
+    }
+}
+
+impl From<bool> for JSONValue {
+    fn from(b: bool) -> Self {
+        JSONValue::Boolean(b)
+    }
+}
+
+macro_rules! number {
+    ($($t:ty),*) => {$(
+        impl From<$t> for JSONValue {
+            fn from(n: $t) -> Self {
+                JSONValue::Number(n as f64)
+            }
+        }
+    )*};
+}
+
+number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl From<&str> for JSONValue {
+    fn from(s: &str) -> Self {
+        JSONValue::String(s.to_string())
+    }
+}
+
+impl From<String> for JSONValue {
+    fn from(s: String) -> Self {
+        JSONValue::String(s)
+    }
+}
+
+impl From<&String> for JSONValue {
+    fn from(s: &String) -> Self {
+        JSONValue::String(s.clone())
+    }
+}
+
+impl From<char> for JSONValue {
+    fn from(c: char) -> Self {
+        JSONValue::String(c.to_string())
+    }
+}
+
+impl<T: Into<JSONValue>> From<Vec<T>> for JSONValue {
+    fn from(vc: Vec<T>) -> Self {
+        JSONValue::Array(vc.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Clone + Into<JSONValue>> From<&[T]> for JSONValue {
+    fn from(items: &[T]) -> Self {
+        items.iter().cloned().collect()
+    }
+}
+
+impl<T: Into<JSONValue>> From<Option<T>> for JSONValue {
+    fn from(opt: Option<T>) -> Self {
+        match opt {
+            Some(v) => v.into(),
+            None => JSONValue::Null,
+        }
+    }
+}
+
+impl<T: Into<JSONValue>> From<HashMap<String, T>> for JSONValue {
+    fn from(hm: HashMap<String, T>) -> Self {
+        hm.into_iter().collect()
+    }
+}
+
+impl<T: Into<JSONValue>> From<BTreeMap<String, T>> for JSONValue {
+    fn from(map: BTreeMap<String, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<T: Into<JSONValue>> FromIterator<T> for JSONValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        JSONValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<JSONValue>> FromIterator<(K, V)> for JSONValue {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        JSONValue::Object(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+// conversions back go through `FromJSON`, so they fail with the path of the
+// offending value rather than falling back to a default. `Option<T>` is left
+// out as core's `TryFrom<T> for Option<T>` covers it; use `FromJSON` there.
+macro_rules! try_from {
+    ($($t:ty),*) => {$(
+        impl TryFrom<JSONValue> for $t {
+            type Error = ConversionError;
+
+            fn try_from(value: JSONValue) -> Result<Self, ConversionError> {
+                FromJSON::from_json(&value)
+            }
+        }
+    )*};
+}
+
+try_from!(bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, char, String);
+
+impl TryFrom<JSONValue> for () {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, ConversionError> {
+        FromJSON::from_json(&value)
+    }
+}
+
+impl<T: FromJSON> TryFrom<JSONValue> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, ConversionError> {
+        FromJSON::from_json(&value)
+    }
+}
+
+impl<T: FromJSON> TryFrom<JSONValue> for HashMap<String, T> {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, ConversionError> {
+        FromJSON::from_json(&value)
+    }
+}
+
+impl<T: FromJSON> TryFrom<JSONValue> for BTreeMap<String, T> {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, ConversionError> {
+        FromJSON::from_json(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn into_json() {
+        assert_eq!(JSONValue::from(45), JSONValue::Number(45f64));
+        assert_eq!(JSONValue::from(2.5f32), JSONValue::Number(2.5));
+        assert_eq!(JSONValue::from('x'), json!("x"));
+        assert_eq!(JSONValue::from(Some("a")), json!("a"));
+        assert_eq!(JSONValue::from(None::<bool>), JSONValue::Null);
+        assert_eq!(JSONValue::from(&[1u8, 2][..]), json!([1, 2]));
+
+        let mut hm = HashMap::new();
+        hm.insert("a".to_string(), vec![true]);
+        assert_eq!(JSONValue::from(hm), json!({ "a": [true] }));
+
+        let squares: JSONValue = (1..4).map(|i| i * i).collect();
+        assert_eq!(squares, json!([1, 4, 9]));
+        let object: JSONValue = vec![("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(object, json!({ "a": 1, "b": 2 }));
+    }
+
+    #[test]
+    fn try_from_json() {
+        assert_eq!(u8::try_from(json!(7)), Ok(7));
+        assert_eq!(String::try_from(json!("s")), Ok("s".to_string()));
+        assert_eq!(
+            Vec::<Option<i32>>::try_from(json!([null, 1])),
+            Ok(vec![None, Some(1)])
+        );
+        assert_eq!(Vec::<f64>::try_from(json!([1, 2.5])), Ok(vec![1f64, 2.5]));
+
+        let err = i32::try_from(json!("45")).unwrap_err();
+        assert_eq!(err.message(), "Expected number but found string");
+        let err = HashMap::<String, Vec<bool>>::try_from(json!({ "a": [true, 1] })).unwrap_err();
+        assert_eq!(err.path(), "/a/1");
+        assert!(u8::try_from(json!(-1)).is_err());
+    }
+}
@@ -0,0 +1,803 @@
+use std::fmt;
+
+use crate::parser::Parser;
+use crate::path::{parse_path, PathSegment};
+use crate::pointer::{escape_token, parse_index};
+use crate::scanner::Dialect;
+use crate::serializer::FormatOptions;
+use crate::source_map::{Position, SourceMap, Span};
+use crate::value::{JSONError, JSONValue};
+
+// A lossless concrete syntax tree. Every byte of the source is kept, either in
+// a token or in the trivia (whitespace and comments) around it, so a document
+// edited through `set` and `remove` writes back unchanged apart from the
+// edited lines. New values take their layout from their neighbours.
+#[derive(Debug, Clone)]
+pub struct CSTDocument {
+    before: String,
+    root: Node,
+    after: String,
+    dialect: Dialect,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    // raw text of a string, number or literal
+    Scalar(String),
+    Object(Container),
+    Array(Container),
+}
+
+#[derive(Debug, Clone)]
+struct Container {
+    items: Vec<Item>,
+    // trivia before the closing bracket
+    end: String,
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    // trivia before the member or element
+    before: String,
+    key: Option<Key>,
+    value: Node,
+    // trivia between the value and its comma; empty without a comma
+    after: String,
+    comma: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Key {
+    raw: String,
+    name: String,
+    // everything between the key and the value, colon included
+    sep: String,
+}
+
+impl CSTDocument {
+    pub fn parse(text: &str) -> Result<CSTDocument, JSONError> {
+        CSTDocument::parse_with_dialect(text, Dialect::Json)
+    }
+
+    pub fn parse_with_dialect(text: &str, dialect: Dialect) -> Result<CSTDocument, JSONError> {
+        let mut reader = Reader::new(text, dialect);
+        let before = reader.trivia()?;
+        if reader.pos == text.len() {
+            return Err(JSONError::new("Empty json stream".to_string(), 0, 0));
+        }
+        let root = reader.value()?;
+        let after = reader.trivia()?;
+        if reader.pos != text.len() {
+            return Err(reader.error("Unexpected token after value".to_string()));
+        }
+        Ok(CSTDocument {
+            before,
+            root,
+            after,
+            dialect,
+        })
+    }
+
+    pub fn value(&self) -> JSONValue {
+        self.root.value(self.dialect)
+    }
+
+    pub fn get(&self, path: &str) -> Option<JSONValue> {
+        let mut node = &self.root;
+        for segment in parse_path(path).ok()? {
+            let index = node.find(&segment)?;
+            node = &node.container()?.items[index].value;
+        }
+        Some(node.value(self.dialect))
+    }
+
+    // Sets the value at `path` like `JSONValue::set_path`, creating missing
+    // members. Only the edited value is rewritten; new members are indented
+    // like their siblings. On error the document is left untouched.
+    pub fn set(&mut self, path: &str, value: &JSONValue) -> Result<Option<JSONValue>, JSONError> {
+        let segments = parse_path(path)?;
+        let unit = self.indent_unit();
+        let nl = line_break(&self.to_string()).to_string();
+        let dialect = self.dialect;
+        let mut node = &mut self.root;
+        // indentation of the line holding `node` and whether it spans lines
+        let (mut indent, mut multiline) = (String::new(), unit.is_some());
+        for (i, segment) in segments.iter().enumerate() {
+            let err = |msg: String| {
+                let at: String = segments[..i].iter().map(|s| s.to_string()).collect();
+                JSONError::new(format!("{} at `{}`", msg, at), 0, 0)
+            };
+            if let Some(index) = node.find(segment) {
+                let c = node.container_mut().unwrap();
+                multiline = c.is_multiline();
+                indent = c.child_indent(&indent, unit.as_deref());
+                node = &mut c.items[index].value;
+                continue;
+            }
+            let key = match (&*node, segment) {
+                (Node::Object(_), PathSegment::Key(k)) => Some(k.clone()),
+                (Node::Array(c), segment) => {
+                    let index = match segment {
+                        PathSegment::Index(n) => Some(*n),
+                        PathSegment::Key(k) if k == "-" => Some(c.items.len()),
+                        PathSegment::Key(k) => parse_index(k),
+                    };
+                    match index {
+                        Some(n) if n == c.items.len() => None,
+                        Some(n) => {
+                            return Err(err(format!(
+                                "Index {} out of bounds for length {}",
+                                n,
+                                c.items.len()
+                            )))
+                        }
+                        None => return Err(err(format!("Invalid index `{}`", segment))),
+                    }
+                }
+                (node, segment) => {
+                    let found = node.value(dialect).type_name();
+                    return Err(err(format!("Cannot set `{}` in {}", segment, found)));
+                }
+            };
+            let missing = nest(&segments[i..], value.clone()).map_err(|(j, msg)| {
+                let at: String = segments[..i + j].iter().map(|s| s.to_string()).collect();
+                JSONError::new(format!("{} at `{}`", msg, at), 0, 0)
+            })?;
+            let c = node.container_mut().unwrap();
+            c.insert(key, &missing, &indent, &nl, unit.as_deref(), dialect);
+            return Ok(None);
+        }
+        let old = node.value(dialect);
+        let unit = unit.filter(|_| multiline);
+        *node = render(value, &indent, &nl, unit.as_deref(), dialect);
+        Ok(Some(old))
+    }
+
+    // Removes the member or element at `path` along with its comments and
+    // line, returning its value; `None` when there is nothing to remove.
+    pub fn remove(&mut self, path: &str) -> Result<Option<JSONValue>, JSONError> {
+        let mut segments = parse_path(path)?;
+        let last = match segments.pop() {
+            Some(last) => last,
+            None => return Err(JSONError::new("Cannot remove the root".to_string(), 0, 0)),
+        };
+        let mut node = &mut self.root;
+        for segment in segments {
+            let index = match node.find(&segment) {
+                Some(index) => index,
+                None => return Ok(None),
+            };
+            node = &mut node.container_mut().unwrap().items[index].value;
+        }
+        let index = match node.find(&last) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let item = node.container_mut().unwrap().remove(index);
+        Ok(Some(item.value.value(self.dialect)))
+    }
+
+    // where every value and member key is in the text
+    pub fn source_map(&self) -> SourceMap {
+        let mut map = SourceMap::default();
+        let mut at = Position::start();
+        at.advance(&self.before);
+        self.root.map(&mut map, &mut String::new(), &mut at);
+        map
+    }
+
+    // the indentation step of the document, from its first indented container
+    fn indent_unit(&self) -> Option<String> {
+        fn find(node: &Node, indent: &str) -> Option<String> {
+            let c = node.container()?;
+            let child = c.child_indent(indent, None);
+            if c.is_multiline() && child.len() > indent.len() && child.starts_with(indent) {
+                return Some(child[indent.len()..].to_string());
+            }
+            c.items.iter().find_map(|item| find(&item.value, &child))
+        }
+        find(&self.root, "")
+    }
+}
+
+impl fmt::Display for CSTDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.before)?;
+        self.root.write(f)?;
+        f.write_str(&self.after)
+    }
+}
+
+// builds the value for the missing segments of a path, failing with the
+// offending segment for indexes other than 0
+fn nest(segments: &[PathSegment], value: JSONValue) -> Result<JSONValue, (usize, String)> {
+    for (j, segment) in segments.iter().enumerate().skip(1) {
+        if let PathSegment::Index(n) = segment {
+            if *n != 0 {
+                return Err((j, format!("Index {} out of bounds for length 0", n)));
+            }
+        }
+    }
+    Ok(segments
+        .iter()
+        .skip(1)
+        .rev()
+        .fold(value, |inner, segment| match segment {
+            PathSegment::Index(_) => JSONValue::Array(vec![inner]),
+            PathSegment::Key(k) => {
+                let mut hm = std::collections::HashMap::new();
+                hm.insert(k.clone(), inner);
+                JSONValue::Object(hm)
+            }
+        }))
+}
+
+// writes `value` as a node at `indent`, pretty when given an indentation step,
+// breaking lines with `nl`
+fn render(value: &JSONValue, indent: &str, nl: &str, unit: Option<&str>, dialect: Dialect) -> Node {
+    let opts = FormatOptions {
+        indent: unit.map(String::from),
+        sort_keys: true,
+    };
+    let text = value
+        .to_string_with(&opts)
+        .replace('\n', &format!("{}{}", nl, indent));
+    Reader::new(&text, dialect)
+        .value()
+        .unwrap_or_else(|_| Node::Scalar("null".to_string()))
+}
+
+// splits trivia at its first line break: what trails the previous token on
+// its line, and the rest
+fn split_line(trivia: &str) -> (&str, &str) {
+    match trivia.find('\n') {
+        Some(i) => trivia.split_at(i),
+        None => (trivia, ""),
+    }
+}
+
+// the line break a document uses
+fn line_break(trivia: &str) -> &str {
+    if trivia.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+impl Node {
+    fn container(&self) -> Option<&Container> {
+        match self {
+            Node::Object(c) | Node::Array(c) => Some(c),
+            Node::Scalar(_) => None,
+        }
+    }
+
+    fn container_mut(&mut self) -> Option<&mut Container> {
+        match self {
+            Node::Object(c) | Node::Array(c) => Some(c),
+            Node::Scalar(_) => None,
+        }
+    }
+
+    // position of the item `segment` refers to; the last of duplicate keys
+    fn find(&self, segment: &PathSegment) -> Option<usize> {
+        match (self, segment) {
+            (Node::Object(c), PathSegment::Key(key)) => c
+                .items
+                .iter()
+                .rposition(|item| item.key.as_ref().is_some_and(|k| &k.name == key)),
+            (Node::Array(c), PathSegment::Index(n)) => Some(*n).filter(|n| *n < c.items.len()),
+            (Node::Array(c), PathSegment::Key(k)) => parse_index(k).filter(|n| *n < c.items.len()),
+            _ => None,
+        }
+    }
+
+    fn value(&self, dialect: Dialect) -> JSONValue {
+        match self {
+            // scalars are validated when read
+            Node::Scalar(raw) => Parser::from_string(raw)
+                .with_dialect(dialect)
+                .parse()
+                .unwrap_or(JSONValue::Null),
+            Node::Object(c) => JSONValue::Object(
+                c.items
+                    .iter()
+                    .filter_map(|item| {
+                        Some((item.key.as_ref()?.name.clone(), item.value.value(dialect)))
+                    })
+                    .collect(),
+            ),
+            Node::Array(c) => JSONValue::Array(
+                c.items
+                    .iter()
+                    .map(|item| item.value.value(dialect))
+                    .collect(),
+            ),
+        }
+    }
+
+    // records the spans of the node and its children, moving `at` past it
+    fn map(&self, map: &mut SourceMap, pointer: &mut String, at: &mut Position) {
+        let start = *at;
+        if let Node::Scalar(raw) = self {
+            at.advance(raw);
+        }
+        if let Some(c) = self.container() {
+            at.advance("{");
+            for (i, item) in c.items.iter().enumerate() {
+                at.advance(&item.before);
+                let len = pointer.len();
+                match &item.key {
+                    Some(key) => {
+                        pointer.push('/');
+                        pointer.push_str(&escape_token(&key.name));
+                        let key_start = *at;
+                        at.advance(&key.raw);
+                        map.insert_key(
+                            pointer,
+                            Span {
+                                start: key_start,
+                                end: *at,
+                            },
+                        );
+                        at.advance(&key.sep);
+                    }
+                    None => pointer.push_str(&format!("/{}", i)),
+                }
+                item.value.map(map, pointer, at);
+                pointer.truncate(len);
+                at.advance(&item.after);
+                if item.comma {
+                    at.advance(",");
+                }
+            }
+            at.advance(&c.end);
+            at.advance("}");
+        }
+        map.insert_value(pointer, Span { start, end: *at });
+    }
+
+    fn write(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (c, open, close) = match self {
+            Node::Scalar(raw) => return f.write_str(raw),
+            Node::Object(c) => (c, '{', '}'),
+            Node::Array(c) => (c, '[', ']'),
+        };
+        write!(f, "{}", open)?;
+        for item in &c.items {
+            f.write_str(&item.before)?;
+            if let Some(key) = &item.key {
+                f.write_str(&key.raw)?;
+                f.write_str(&key.sep)?;
+            }
+            item.value.write(f)?;
+            f.write_str(&item.after)?;
+            if item.comma {
+                f.write_str(",")?;
+            }
+        }
+        f.write_str(&c.end)?;
+        write!(f, "{}", close)
+    }
+}
+
+impl Container {
+    fn is_multiline(&self) -> bool {
+        self.items.iter().any(|item| item.before.contains('\n')) || self.end.contains('\n')
+    }
+
+    // indentation of the lines holding the items
+    fn child_indent(&self, indent: &str, unit: Option<&str>) -> String {
+        let found = self.items.iter().rev().find_map(|item| {
+            let i = item.before.rfind('\n')?;
+            let tail = &item.before[i + 1..];
+            Some(tail[..tail.len() - tail.trim_start().len()].to_string())
+        });
+        match (found, unit) {
+            (Some(found), _) => found,
+            (None, Some(unit)) => format!("{}{}", indent, unit),
+            (None, None) => indent.to_string(),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        key: Option<String>,
+        value: &JSONValue,
+        indent: &str,
+        nl: &str,
+        unit: Option<&str>,
+        dialect: Dialect,
+    ) {
+        let multiline = self.is_multiline() || (self.items.is_empty() && unit.is_some());
+        let child = self.child_indent(indent, unit);
+        let node = render(value, &child, nl, unit.filter(|_| multiline), dialect);
+        let sep = match self.items.last().and_then(|item| item.key.as_ref()) {
+            Some(last) if last.sep.trim() == ":" => last.sep.clone(),
+            _ if unit.is_some() => ": ".to_string(),
+            _ => ":".to_string(),
+        };
+        let spaced = unit.is_some() || sep.ends_with(' ');
+        let key = key.map(|name| Key {
+            raw: crate::serializer::escape(&name),
+            name,
+            sep,
+        });
+
+        let before = if multiline {
+            // comments trailing the last item stay on its line; the closing
+            // bracket keeps its own line break
+            let (head, tail) = match self.end.rfind('\n') {
+                Some(i) => {
+                    let i = if self.end[..i].ends_with('\r') {
+                        i - 1
+                    } else {
+                        i
+                    };
+                    let (head, tail) = self.end.split_at(i);
+                    (head.to_string(), tail.to_string())
+                }
+                None => (self.end.clone(), format!("{}{}", nl, indent)),
+            };
+            self.end = tail;
+            format!("{}{}{}", head.trim_end(), nl, child)
+        } else {
+            // the first item's spacing follows the bracket, not a comma, so
+            // there is nothing to copy: use `, ` unless the document is compact
+            match self.items.last() {
+                Some(last) if self.items.len() > 1 && last.before.trim().is_empty() => {
+                    last.before.clone()
+                }
+                Some(_) if spaced => " ".to_string(),
+                _ => String::new(),
+            }
+        };
+        let comma = match self.items.last_mut() {
+            // keep a trailing comma style
+            Some(last) if last.comma => true,
+            Some(last) => {
+                last.comma = true;
+                false
+            }
+            None => false,
+        };
+        self.items.push(Item {
+            before,
+            key,
+            value: node,
+            after: String::new(),
+            comma,
+        });
+    }
+
+    // drops the item and the comments before it, keeping comments that trail
+    // the previous item on its line
+    fn remove(&mut self, index: usize) -> Item {
+        let item = self.items.remove(index);
+        let keep = if item.before.contains('\n') {
+            split_line(&item.before).0.to_string()
+        } else {
+            String::new()
+        };
+        if let Some(next) = self.items.get_mut(index) {
+            if next.before.contains('\n') {
+                next.before = format!("{}{}", keep, split_line(&next.before).1);
+            } else if index == 0 {
+                next.before = item.before.clone();
+            }
+        } else if self.items.is_empty() {
+            self.end = if keep.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", keep, split_line(&self.end).1)
+            };
+        } else {
+            let prev = self.items.last_mut().unwrap();
+            prev.comma = item.comma;
+            if self.end.contains('\n') {
+                self.end = format!("{}{}", keep, split_line(&self.end).1);
+            }
+            if !prev.comma {
+                self.end = std::mem::take(&mut prev.after) + &self.end;
+            }
+        }
+        item
+    }
+}
+
+// reads the tree, keeping the raw text of every token and trivia
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+    dialect: Dialect,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str, dialect: Dialect) -> Self {
+        Reader {
+            text,
+            pos: 0,
+            dialect,
+        }
+    }
+
+    fn error(&self, msg: String) -> JSONError {
+        let done = &self.text[..self.pos];
+        let line = done.matches('\n').count() + 1;
+        let col = done[done.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        JSONError::new(msg, line, col)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    // whitespace and, outside plain JSON, comments
+    fn trivia(&mut self) -> Result<String, JSONError> {
+        let start = self.pos;
+        loop {
+            let rest = &self.text[self.pos..];
+            let comments = self.dialect != Dialect::Json;
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.pos += c.len_utf8(),
+                Some('\u{feff}') if self.dialect == Dialect::Json5 => self.pos += 3,
+                Some('/') if comments && rest.starts_with("//") => {
+                    self.pos += rest.find('\n').unwrap_or(rest.len())
+                }
+                Some('/') if comments && rest.starts_with("/*") => match rest[2..].find("*/") {
+                    Some(i) => self.pos += i + 4,
+                    None => return Err(self.error("Unterminated comment".to_string())),
+                },
+                _ => return Ok(self.text[start..self.pos].to_string()),
+            }
+        }
+    }
+
+    // a quoted string or a run of word characters, checked by the parser
+    fn scalar(&mut self) -> Result<String, JSONError> {
+        let start = self.pos;
+        let rest = &self.text[start..];
+        let len = match self.peek() {
+            Some(q) if q == '"' || (q == '\'' && self.dialect == Dialect::Json5) => {
+                let mut escaped = false;
+                let end = rest.char_indices().skip(1).find(|(_, c)| {
+                    let close = !escaped && *c == q;
+                    escaped = !escaped && *c == '\\';
+                    close
+                });
+                match end {
+                    Some((i, _)) => i + 1,
+                    None => return Err(self.error("Invalid or unterminated string".to_string())),
+                }
+            }
+            Some(_) => rest
+                .find(|c: char| !(c.is_alphanumeric() || "_$+-.".contains(c)))
+                .unwrap_or(rest.len()),
+            None => return Err(self.error("Unexpected end of input".to_string())),
+        };
+        if len == 0 {
+            let c = self.peek().unwrap_or_default();
+            return Err(self.error(format!("Unexpected token {}", c)));
+        }
+        let raw = &rest[..len];
+        let parsed = Parser::from_string(raw).with_dialect(self.dialect).parse();
+        if let Err(err) = parsed {
+            return Err(self.error(err.message().to_string()));
+        }
+        self.pos += len;
+        Ok(raw.to_string())
+    }
+
+    fn value(&mut self) -> Result<Node, JSONError> {
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                Ok(Node::Object(self.container('}', true)?))
+            }
+            Some('[') => {
+                self.pos += 1;
+                Ok(Node::Array(self.container(']', false)?))
+            }
+            _ => Ok(Node::Scalar(self.scalar()?)),
+        }
+    }
+
+    fn key(&mut self) -> Result<Key, JSONError> {
+        let start = self.pos;
+        let quoted = self.peek() == Some('"') || self.peek() == Some('\'');
+        let identifier = self.dialect == Dialect::Json5
+            && self
+                .peek()
+                .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$');
+        let (raw, name) = if quoted {
+            let raw = self.scalar()?;
+            match Parser::from_string(&raw).with_dialect(self.dialect).parse() {
+                Ok(JSONValue::String(name)) => (raw, name),
+                _ => return Err(self.error("Expecting key name".to_string())),
+            }
+        } else if identifier {
+            let rest = &self.text[start..];
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            self.pos += len;
+            (rest[..len].to_string(), rest[..len].to_string())
+        } else {
+            return Err(self.error("Expecting key name".to_string()));
+        };
+
+        let sep_start = self.pos;
+        self.trivia()?;
+        if self.peek() != Some(':') {
+            return Err(self.error("Expecting collon".to_string()));
+        }
+        self.pos += 1;
+        self.trivia()?;
+        let sep = self.text[sep_start..self.pos].to_string();
+        Ok(Key { raw, name, sep })
+    }
+
+    fn container(&mut self, close: char, object: bool) -> Result<Container, JSONError> {
+        let mut items: Vec<Item> = vec![];
+        loop {
+            let before = self.trivia()?;
+            if self.peek() == Some(close) {
+                if !items.is_empty() && self.dialect == Dialect::Json {
+                    return Err(self.error(format!("Trailing `{}`", close)));
+                }
+                self.pos += 1;
+                return Ok(Container { items, end: before });
+            }
+            let key = if object { Some(self.key()?) } else { None };
+            let value = self.value()?;
+            let after = self.trivia()?;
+            match self.peek() {
+                Some(',') => {
+                    self.pos += 1;
+                    items.push(Item {
+                        before,
+                        key,
+                        value,
+                        after,
+                        comma: true,
+                    });
+                }
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    items.push(Item {
+                        before,
+                        key,
+                        value,
+                        after: String::new(),
+                        comma: false,
+                    });
+                    return Ok(Container { items, end: after });
+                }
+                Some(c) => return Err(self.error(format!("Unexpected token {}", c))),
+                None => return Err(self.error("Unexpected end of input".to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn lossless() {
+        let text = std::fs::read_to_string("fixtures/sample.json").unwrap();
+        let doc = CSTDocument::parse(&text).unwrap();
+        assert_eq!(doc.to_string(), text);
+        assert_eq!(
+            doc.value(),
+            crate::JSONDocument::new().parse_string(text).unwrap()
+        );
+
+        let text = "\u{feff}// top\r\n{ a: [1, 2,], /* c */ 'b': 0x10 }\r\n";
+        let doc = CSTDocument::parse_with_dialect(text, Dialect::Json5).unwrap();
+        assert_eq!(doc.to_string(), text);
+        assert_eq!(doc.get("b"), Some(json!(16)));
+
+        for bad in ["", "{\"a\" 1}", "[1,]", "[1] 2", "{\"a\": tru}", "\"open"].iter() {
+            assert!(CSTDocument::parse(bad).is_err(), "{}", bad);
+        }
+        let err = CSTDocument::parse("{\n  \"a\": [1 2]\n}").unwrap_err();
+        assert_eq!((err.line(), err.col()), (2, 11));
+    }
+
+    #[test]
+    fn editing() {
+        let text = "{
+    \"name\": \"app\", // the name
+    \"zeta\": { \"x\": 1 },
+    \"list\": [
+        1
+    ],
+    \"empty\": {}
+}
+";
+        let mut doc = CSTDocument::parse_with_dialect(text, Dialect::Jsonc).unwrap();
+        assert_eq!(doc.set("/zeta/x", &json!(3)).unwrap(), Some(json!(1)));
+        assert_eq!(doc.set("name", &json!("web")).unwrap(), Some(json!("app")));
+        assert_eq!(doc.set("list[1]", &json!({ "b": 2 })).unwrap(), None);
+        assert_eq!(doc.set("empty.deep.key", &json!(true)).unwrap(), None);
+        assert_eq!(doc.set("zeta.y", &json!([])).unwrap(), None);
+        assert_eq!(doc.set("added", &json!(null)).unwrap(), None);
+        assert_eq!(
+            doc.to_string(),
+            "{
+    \"name\": \"web\", // the name
+    \"zeta\": { \"x\": 3, \"y\": [] },
+    \"list\": [
+        1,
+        {
+            \"b\": 2
+        }
+    ],
+    \"empty\": {
+        \"deep\": {
+            \"key\": true
+        }
+    },
+    \"added\": null
+}
+"
+        );
+
+        assert_eq!(doc.remove("/list/0").unwrap(), Some(json!(1)));
+        assert_eq!(
+            doc.remove("zeta").unwrap(),
+            Some(json!({ "x": 3, "y": [] }))
+        );
+        assert_eq!(doc.remove("added").unwrap(), Some(json!(null)));
+        assert_eq!(doc.remove("empty.deep.key").unwrap(), Some(json!(true)));
+        assert_eq!(doc.remove("missing").unwrap(), None);
+        assert_eq!(
+            doc.to_string(),
+            "{
+    \"name\": \"web\", // the name
+    \"list\": [
+        {
+            \"b\": 2
+        }
+    ],
+    \"empty\": {
+        \"deep\": {}
+    }
+}
+"
+        );
+
+        let mut doc = CSTDocument::parse("{\"a\":[1],\"b\":{}}").unwrap();
+        doc.set("/a/-", &json!(2)).unwrap();
+        doc.set("/b/c", &json!({ "d": 1 })).unwrap();
+        assert_eq!(doc.to_string(), "{\"a\":[1,2],\"b\":{\"c\":{\"d\":1}}}");
+
+        let mut crlf = CSTDocument::parse("{\r\n  \"a\": 1\r\n}").unwrap();
+        crlf.set("a", &json!(2)).unwrap();
+        crlf.set("/b/c", &json!(true)).unwrap();
+        crlf.set("/b/d", &json!([1])).unwrap();
+        assert_eq!(
+            crlf.to_string(),
+            "{\r\n  \"a\": 2,\r\n  \"b\": {\r\n    \"c\": true,\r\n    \"d\": [\r\n      1\r\n    ]\r\n  }\r\n}"
+        );
+
+        let mut inline = CSTDocument::parse("{\"b\": 1}").unwrap();
+        inline.set("c", &json!(3)).unwrap();
+        inline.set("d", &json!([])).unwrap();
+        assert_eq!(inline.to_string(), "{\"b\": 1, \"c\": 3, \"d\": []}");
+
+        let err = doc.set("/a/5", &json!(0)).unwrap_err();
+        assert_eq!(err.message(), "Index 5 out of bounds for length 2 at `/a`");
+        let err = doc.set("/a/0/x", &json!(0)).unwrap_err();
+        assert_eq!(err.message(), "Cannot set `/x` in number at `/a/0`");
+        assert!(doc.remove("").is_err());
+        assert_eq!(doc.to_string(), "{\"a\":[1,2],\"b\":{\"c\":{\"d\":1}}}");
+    }
+}
@@ -0,0 +1,314 @@
+use std::fmt::{self, Display};
+use std::io;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Unexpected, Visitor,
+};
+
+use crate::parser::Parser;
+use crate::value::{JSONError, JSONValue};
+
+// Syntax errors keep the parser's line and column; errors raised while
+// building `T` from the parsed value are reported at (0, 0).
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T, JSONError> {
+    let value = Parser::from_string(s).parse()?;
+    from_value(value)
+}
+
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(mut reader: R) -> Result<T, JSONError> {
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .map_err(|e| JSONError::new(e.to_string(), 0, 0))?;
+    from_str(&content)
+}
+
+pub fn from_value<T: DeserializeOwned>(value: JSONValue) -> Result<T, JSONError> {
+    T::deserialize(value)
+}
+
+impl de::Error for JSONError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JSONError::new(msg.to_string(), 0, 0)
+    }
+}
+
+impl<'de> Deserialize<'de> for JSONValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<JSONValue, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JSONValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<JSONValue, E> {
+        Ok(JSONValue::Boolean(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<JSONValue, E> {
+        Ok(JSONValue::Number(v as f64))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<JSONValue, E> {
+        Ok(JSONValue::Number(v as f64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<JSONValue, E> {
+        Ok(JSONValue::Number(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<JSONValue, E> {
+        Ok(JSONValue::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<JSONValue, E> {
+        Ok(JSONValue::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<JSONValue, E> {
+        Ok(JSONValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<JSONValue, E> {
+        Ok(JSONValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<JSONValue, D::Error> {
+        Deserialize::deserialize(d)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<JSONValue, A::Error> {
+        let mut vc = vec![];
+        while let Some(v) = seq.next_element()? {
+            vc.push(v);
+        }
+        Ok(JSONValue::Array(vc))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<JSONValue, A::Error> {
+        let mut hm = std::collections::HashMap::new();
+        while let Some((k, v)) = map.next_entry::<String, JSONValue>()? {
+            hm.insert(k, v);
+        }
+        Ok(JSONValue::Object(hm))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, JSONError> for JSONValue {
+    type Deserializer = JSONValue;
+
+    fn into_deserializer(self) -> JSONValue {
+        self
+    }
+}
+
+fn unexpected(v: &JSONValue) -> Unexpected<'_> {
+    match v {
+        JSONValue::Null => Unexpected::Unit,
+        JSONValue::Boolean(b) => Unexpected::Bool(*b),
+        JSONValue::Number(n) => Unexpected::Float(*n),
+        JSONValue::String(s) => Unexpected::Str(s),
+        JSONValue::Array(_) => Unexpected::Seq,
+        JSONValue::Object(_) => Unexpected::Map,
+    }
+}
+
+impl<'de> de::Deserializer<'de> for JSONValue {
+    type Error = JSONError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JSONError> {
+        match self {
+            JSONValue::Null => visitor.visit_unit(),
+            JSONValue::Boolean(b) => visitor.visit_bool(b),
+            // whole numbers are offered as integers so integer fields accept them
+            JSONValue::Number(n) if n.fract() == 0f64 && n >= 0f64 && n < u64::MAX as f64 => {
+                visitor.visit_u64(n as u64)
+            }
+            JSONValue::Number(n) if n.fract() == 0f64 && n >= i64::MIN as f64 && n < 0f64 => {
+                visitor.visit_i64(n as i64)
+            }
+            JSONValue::Number(n) => visitor.visit_f64(n),
+            JSONValue::String(s) => visitor.visit_string(s),
+            JSONValue::Array(vc) => {
+                let mut seq = SeqDeserializer::new(vc.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            JSONValue::Object(hm) => {
+                let mut map = MapDeserializer::new(hm.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JSONError> {
+        match self {
+            JSONValue::Null => visitor.visit_none(),
+            v => visitor.visit_some(v),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JSONError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // enums are externally tagged: `"Unit"` or `{ "Variant": content }`
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JSONError> {
+        match self {
+            JSONValue::String(s) => visitor.visit_enum(s.into_deserializer()),
+            JSONValue::Object(hm) if hm.len() == 1 => {
+                let (variant, value) = hm.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            v => Err(de::Error::invalid_type(
+                unexpected(&v),
+                &"string or single key object",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: JSONValue,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = JSONError;
+    type Variant = JSONValue;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, JSONValue), JSONError> {
+        let variant: StringDeserializer<JSONError> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for JSONValue {
+    type Error = JSONError;
+
+    fn unit_variant(self) -> Result<(), JSONError> {
+        match self {
+            JSONValue::Null => Ok(()),
+            v => Err(de::Error::invalid_type(unexpected(&v), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, JSONError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, JSONError> {
+        match self {
+            JSONValue::Array(_) => de::Deserializer::deserialize_any(self, visitor),
+            v => Err(de::Error::invalid_type(unexpected(&v), &"tuple variant")),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JSONError> {
+        match self {
+            JSONValue::Object(_) => de::Deserializer::deserialize_any(self, visitor),
+            v => Err(de::Error::invalid_type(unexpected(&v), &"struct variant")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Dot,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+        Pair(i8, i8),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Drawing {
+        name: String,
+        layers: Vec<u8>,
+        shapes: Vec<Shape>,
+        scale: Option<f32>,
+        meta: BTreeMap<String, JSONValue>,
+    }
+
+    #[test]
+    fn typed_roundtrip() {
+        let text = r#"{ "name": "plan", "layers": [1, 2], "scale": null,
+            "shapes": ["Dot", { "Circle": 1.5 }, { "Rect": { "w": 2, "h": 3 } }, { "Pair": [-1, 1] }],
+            "meta": { "author": "me", "tags": [true, null] } }"#;
+        let drawing: Drawing = from_str(text).unwrap();
+        assert_eq!(drawing.layers, vec![1, 2]);
+        assert_eq!(drawing.shapes[2], Shape::Rect { w: 2, h: 3 });
+        assert_eq!(drawing.scale, None);
+
+        let out = to_string(&drawing).unwrap();
+        assert_eq!(
+            out,
+            r#"{"name":"plan","layers":[1,2],"shapes":["Dot",{"Circle":1.5},{"Rect":{"w":2,"h":3}},{"Pair":[-1,1]}],"scale":null,"meta":{"author":"me","tags":[true,null]}}"#
+        );
+        assert_eq!(from_str::<Drawing>(&out).unwrap(), drawing);
+        assert_eq!(from_reader::<_, Drawing>(out.as_bytes()).unwrap(), drawing);
+    }
+
+    #[test]
+    fn values_and_errors() {
+        let v: JSONValue = from_str(r#"{ "a": [1, "x", null] }"#).unwrap();
+        assert_eq!(to_string(&v).unwrap(), r#"{"a":[1,"x",null]}"#);
+        assert_eq!(to_string("a\"b\n").unwrap(), r#""a\"b\n""#);
+
+        let mut keyed = BTreeMap::new();
+        keyed.insert(3, "three");
+        assert_eq!(to_string(&keyed).unwrap(), r#"{"3":"three"}"#);
+
+        let err = from_str::<Drawing>("{\n  \"name\": }").unwrap_err();
+        assert_eq!((err.line(), err.col()), (2, 12));
+        let err = from_str::<u8>("300").unwrap_err();
+        assert!(err.message().contains("300"));
+        assert!(from_str::<Drawing>(r#"{ "name": "x" }"#)
+            .unwrap_err()
+            .message()
+            .contains("missing field"));
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+
+use crate::pointer::escape_token;
+use crate::value::JSONValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+    TypeChanged,
+}
+
+// A single difference; `path` is a JSON Pointer into the old value for
+// removals and into the new value otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+    pub old: Option<JSONValue>,
+    pub new: Option<JSONValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArrayStrategy {
+    ByIndex,
+    Lcs,
+    ByKey(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub arrays: ArrayStrategy,
+    pub tolerance: f64,
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        DiffOptions {
+            arrays: ArrayStrategy::ByIndex,
+            tolerance: 0f64,
+        }
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions::new()
+    }
+}
+
+impl JSONValue {
+    pub fn diff(&self, other: &JSONValue) -> Vec<Change> {
+        self.diff_with(other, &DiffOptions::new())
+    }
+
+    pub fn diff_with(&self, other: &JSONValue, options: &DiffOptions) -> Vec<Change> {
+        let mut changes = vec![];
+        diff_values(self, other, "", options, &mut changes);
+        changes
+    }
+}
+
+fn diff_values(
+    a: &JSONValue,
+    b: &JSONValue,
+    path: &str,
+    opts: &DiffOptions,
+    out: &mut Vec<Change>,
+) {
+    match (a, b) {
+        (JSONValue::Object(x), JSONValue::Object(y)) => {
+            let mut keys: Vec<&String> = x.keys().chain(y.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                let child = format!("{}/{}", path, escape_token(k));
+                match (x.get(k), y.get(k)) {
+                    (Some(l), Some(r)) => diff_values(l, r, &child, opts, out),
+                    (Some(l), None) => out.push(removed(&child, l)),
+                    (None, Some(r)) => out.push(added(&child, r)),
+                    (None, None) => (),
+                }
+            }
+        }
+        (JSONValue::Array(x), JSONValue::Array(y)) => match &opts.arrays {
+            ArrayStrategy::ByIndex => diff_by_index(x, y, path, opts, out),
+            ArrayStrategy::Lcs => diff_by_lcs(x, y, path, opts, out),
+            ArrayStrategy::ByKey(key) => diff_by_key(x, y, key, path, opts, out),
+        },
+        (JSONValue::Number(l), JSONValue::Number(r)) if (l - r).abs() > opts.tolerance => {
+            out.push(changed(ChangeKind::Changed, path, a, b))
+        }
+        (JSONValue::Number(_), JSONValue::Number(_)) => (),
+        _ if a.type_name() != b.type_name() => {
+            out.push(changed(ChangeKind::TypeChanged, path, a, b))
+        }
+        _ if a != b => out.push(changed(ChangeKind::Changed, path, a, b)),
+        _ => (),
+    }
+}
+
+fn diff_by_index(
+    x: &[JSONValue],
+    y: &[JSONValue],
+    path: &str,
+    opts: &DiffOptions,
+    out: &mut Vec<Change>,
+) {
+    for (i, (l, r)) in x.iter().zip(y.iter()).enumerate() {
+        diff_values(l, r, &format!("{}/{}", path, i), opts, out);
+    }
+    for (i, l) in x.iter().enumerate().skip(y.len()) {
+        out.push(removed(&format!("{}/{}", path, i), l));
+    }
+    for (i, r) in y.iter().enumerate().skip(x.len()) {
+        out.push(added(&format!("{}/{}", path, i), r));
+    }
+}
+
+// aligns both arrays on their longest common subsequence; unmatched
+// elements sitting between the same anchors are diffed pairwise
+fn diff_by_lcs(
+    x: &[JSONValue],
+    y: &[JSONValue],
+    path: &str,
+    opts: &DiffOptions,
+    out: &mut Vec<Change>,
+) {
+    let (n, m) = (x.len(), y.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if equal(&x[i], &y[j], opts) {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_x, mut gap_y) = (vec![], vec![]);
+    while i < n || j < m {
+        if i < n && j < m && equal(&x[i], &y[j], opts) {
+            flush_gap(x, y, &gap_x, &gap_y, path, opts, out);
+            gap_x.clear();
+            gap_y.clear();
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || table[i][j + 1] >= table[i + 1][j]) {
+            gap_y.push(j);
+            j += 1;
+        } else {
+            gap_x.push(i);
+            i += 1;
+        }
+    }
+    flush_gap(x, y, &gap_x, &gap_y, path, opts, out);
+}
+
+fn flush_gap(
+    x: &[JSONValue],
+    y: &[JSONValue],
+    gap_x: &[usize],
+    gap_y: &[usize],
+    path: &str,
+    opts: &DiffOptions,
+    out: &mut Vec<Change>,
+) {
+    for (i, j) in gap_x.iter().zip(gap_y.iter()) {
+        diff_values(&x[*i], &y[*j], &format!("{}/{}", path, j), opts, out);
+    }
+    for i in gap_x.iter().skip(gap_y.len()) {
+        out.push(removed(&format!("{}/{}", path, i), &x[*i]));
+    }
+    for j in gap_y.iter().skip(gap_x.len()) {
+        out.push(added(&format!("{}/{}", path, j), &y[*j]));
+    }
+}
+
+// pairs elements holding the same value under `key`; elements without it
+// only match an identical element
+fn diff_by_key(
+    x: &[JSONValue],
+    y: &[JSONValue],
+    key: &str,
+    path: &str,
+    opts: &DiffOptions,
+    out: &mut Vec<Change>,
+) {
+    let id = |v: &JSONValue| match v {
+        JSONValue::Object(hm) => hm.get(key).cloned(),
+        _ => None,
+    };
+    let mut matched = vec![false; y.len()];
+    for (i, l) in x.iter().enumerate() {
+        let lid = id(l);
+        let found = (0..y.len()).find(|j| {
+            !matched[*j]
+                && match (&lid, id(&y[*j])) {
+                    (Some(a), Some(b)) => *a == b,
+                    (None, None) => equal(l, &y[*j], opts),
+                    _ => false,
+                }
+        });
+        match found {
+            Some(j) => {
+                matched[j] = true;
+                diff_values(l, &y[j], &format!("{}/{}", path, j), opts, out);
+            }
+            None => out.push(removed(&format!("{}/{}", path, i), l)),
+        }
+    }
+    for (j, r) in y.iter().enumerate() {
+        if !matched[j] {
+            out.push(added(&format!("{}/{}", path, j), r));
+        }
+    }
+}
+
+fn equal(a: &JSONValue, b: &JSONValue, opts: &DiffOptions) -> bool {
+    let mut changes = vec![];
+    diff_values(a, b, "", opts, &mut changes);
+    changes.is_empty()
+}
+
+fn added(path: &str, v: &JSONValue) -> Change {
+    Change {
+        kind: ChangeKind::Added,
+        path: path.to_string(),
+        old: None,
+        new: Some(v.clone()),
+    }
+}
+
+fn removed(path: &str, v: &JSONValue) -> Change {
+    Change {
+        kind: ChangeKind::Removed,
+        path: path.to_string(),
+        old: Some(v.clone()),
+        new: None,
+    }
+}
+
+fn changed(kind: ChangeKind, path: &str, a: &JSONValue, b: &JSONValue) -> Change {
+    Change {
+        kind,
+        path: path.to_string(),
+        old: Some(a.clone()),
+        new: Some(b.clone()),
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+fn line(sign: char, path: &str, v: &JSONValue, typed: bool, color: bool) -> String {
+    let path = if path.is_empty() { "/" } else { path };
+    let mut text = format!("{} {}: {}", sign, path, v);
+    if typed {
+        text = format!("{} ({})", text, v.type_name());
+    }
+    if color {
+        let code = if sign == '-' { RED } else { GREEN };
+        text = format!("{}{}{}", code, text, RESET);
+    }
+    text
+}
+
+// Unified-style rendering: `-` lines show old values, `+` lines new ones.
+pub fn render_diff(changes: &[Change], color: bool) -> String {
+    let mut lines = vec![];
+    for c in changes {
+        let typed = c.kind == ChangeKind::TypeChanged;
+        if let Some(old) = &c.old {
+            lines.push(line('-', &c.path, old, typed, color));
+        }
+        if let Some(new) = &c.new {
+            lines.push(line('+', &c.path, new, typed, color));
+        }
+    }
+    lines.join("\n")
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render_diff(std::slice::from_ref(self), false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    fn paths(changes: &[Change]) -> Vec<(ChangeKind, &str)> {
+        changes.iter().map(|c| (c.kind, c.path.as_str())).collect()
+    }
+
+    #[test]
+    fn object_changes() {
+        let a = json(r#"{ "name": "a", "age": 1, "id": "7", "gone": true, "x/y": 1 }"#);
+        let b = json(r#"{ "name": "b", "age": 1.0005, "id": 7, "new": null, "x/y": 1 }"#);
+        let changes = a.diff(&b);
+        assert_eq!(
+            paths(&changes),
+            vec![
+                (ChangeKind::Changed, "/age"),
+                (ChangeKind::Removed, "/gone"),
+                (ChangeKind::TypeChanged, "/id"),
+                (ChangeKind::Changed, "/name"),
+                (ChangeKind::Added, "/new"),
+            ]
+        );
+
+        let mut opts = DiffOptions::new();
+        opts.tolerance = 0.001;
+        assert_eq!(a.diff_with(&b, &opts).len(), 4);
+        assert!(a.diff(&a).is_empty());
+    }
+
+    #[test]
+    fn array_strategies() {
+        let a = json("[1, 2, 3, 4]");
+        let b = json("[0, 1, 2, 4]");
+        assert_eq!(a.diff(&b).len(), 3);
+
+        let mut opts = DiffOptions::new();
+        opts.arrays = ArrayStrategy::Lcs;
+        assert_eq!(
+            paths(&a.diff_with(&b, &opts)),
+            vec![(ChangeKind::Added, "/0"), (ChangeKind::Removed, "/2")]
+        );
+
+        let a = json(r#"[{ "id": 1, "v": "a" }, { "id": 2, "v": "b" }]"#);
+        let b = json(r#"[{ "id": 2, "v": "c" }, { "id": 3, "v": "d" }]"#);
+        opts.arrays = ArrayStrategy::ByKey("id".to_string());
+        assert_eq!(
+            paths(&a.diff_with(&b, &opts)),
+            vec![
+                (ChangeKind::Removed, "/0"),
+                (ChangeKind::Changed, "/0/v"),
+                (ChangeKind::Added, "/1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rendering() {
+        let a = json(r#"{ "a": 1, "b": "x" }"#);
+        let b = json(r#"{ "a": 2, "b": 3 }"#);
+        let changes = a.diff(&b);
+        assert_eq!(
+            render_diff(&changes, false),
+            "- /a: 1\n+ /a: 2\n- /b: \"x\" (string)\n+ /b: 3 (number)"
+        );
+        assert_eq!(
+            render_diff(&changes[..1], true),
+            "\x1b[31m- /a: 1\x1b[0m\n\x1b[32m+ /a: 2\x1b[0m"
+        );
+        assert_eq!(json("1").diff(&json("2"))[0].to_string(), "- /: 1\n+ /: 2");
+    }
+}
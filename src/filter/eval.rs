@@ -0,0 +1,1136 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::filter::parser::{BinOp, Expr, ObjKey, StrSegment};
+use crate::parser::Parser;
+use crate::value::{JSONError, JSONValue};
+
+type Outputs = Result<Vec<JSONValue>, JSONError>;
+
+// variables bound with `as` and `reduce`, innermost first
+#[derive(Debug)]
+pub struct Env<'a> {
+    parent: Option<&'a Env<'a>>,
+    name: &'a str,
+    value: JSONValue,
+}
+
+impl<'a> Env<'a> {
+    fn lookup(env: Option<&Env>, name: &str) -> Option<JSONValue> {
+        let mut env = env;
+        while let Some(e) = env {
+            if e.name == name {
+                return Some(e.value.clone());
+            }
+            env = e.parent;
+        }
+        None
+    }
+}
+
+// longest string, in bytes, that `string * number` may build
+const MAX_REPEAT_LEN: usize = 1 << 28;
+
+const BUILTINS: [(&str, usize); 53] = [
+    ("empty", 0),
+    ("error", 0),
+    ("error", 1),
+    ("not", 0),
+    ("length", 0),
+    ("keys", 0),
+    ("keys_unsorted", 0),
+    ("values", 0),
+    ("has", 1),
+    ("to_entries", 0),
+    ("from_entries", 0),
+    ("with_entries", 1),
+    ("select", 1),
+    ("map", 1),
+    ("map_values", 1),
+    ("add", 0),
+    ("any", 0),
+    ("any", 1),
+    ("all", 0),
+    ("all", 1),
+    ("type", 0),
+    ("tostring", 0),
+    ("tonumber", 0),
+    ("tojson", 0),
+    ("fromjson", 0),
+    ("sort", 0),
+    ("sort_by", 1),
+    ("group_by", 1),
+    ("unique", 0),
+    ("unique_by", 1),
+    ("min", 0),
+    ("max", 0),
+    ("min_by", 1),
+    ("max_by", 1),
+    ("reverse", 0),
+    ("flatten", 0),
+    ("flatten", 1),
+    ("range", 1),
+    ("range", 2),
+    ("floor", 0),
+    ("sqrt", 0),
+    ("join", 1),
+    ("split", 1),
+    ("ascii_downcase", 0),
+    ("ascii_upcase", 0),
+    ("startswith", 1),
+    ("endswith", 1),
+    ("recurse", 0),
+    ("recurse", 1),
+    ("first", 0),
+    ("first", 1),
+    ("last", 0),
+    ("last", 1),
+];
+
+fn error(msg: String) -> JSONError {
+    JSONError::new(msg, 0, 0)
+}
+
+// checks function names, arities and variable bindings once at compile time
+pub fn check(expr: &Expr, vars: &mut Vec<String>) -> Result<(), JSONError> {
+    match expr {
+        Expr::Identity | Expr::Recurse | Expr::Literal(_) => Ok(()),
+        Expr::Var(name) => {
+            if vars.contains(name) {
+                Ok(())
+            } else {
+                Err(error(format!("${} is not defined", name)))
+            }
+        }
+        Expr::Str(segments) => check_segments(segments, vars),
+        Expr::Field(e, _) | Expr::Iterate(e) | Expr::Optional(e) | Expr::Neg(e) => check(e, vars),
+        Expr::Index(a, b)
+        | Expr::Pipe(a, b)
+        | Expr::Comma(a, b)
+        | Expr::Binary(_, a, b)
+        | Expr::And(a, b)
+        | Expr::Or(a, b)
+        | Expr::Alt(a, b) => {
+            check(a, vars)?;
+            check(b, vars)
+        }
+        Expr::Slice(e, from, to) => {
+            check(e, vars)?;
+            for bound in from.iter().chain(to.iter()) {
+                check(bound, vars)?;
+            }
+            Ok(())
+        }
+        Expr::Array(e) => match e {
+            Some(e) => check(e, vars),
+            None => Ok(()),
+        },
+        Expr::Object(entries) => {
+            for (key, value) in entries {
+                match key {
+                    ObjKey::Var(name) if !vars.contains(name) => {
+                        return Err(error(format!("${} is not defined", name)))
+                    }
+                    ObjKey::Str(segments) => check_segments(segments, vars)?,
+                    ObjKey::Computed(e) => check(e, vars)?,
+                    _ => (),
+                }
+                if let Some(value) = value {
+                    check(value, vars)?;
+                }
+            }
+            Ok(())
+        }
+        Expr::If(branches, otherwise) => {
+            for (cond, then) in branches {
+                check(cond, vars)?;
+                check(then, vars)?;
+            }
+            match otherwise {
+                Some(e) => check(e, vars),
+                None => Ok(()),
+            }
+        }
+        Expr::Try(body, handler) => {
+            check(body, vars)?;
+            match handler {
+                Some(e) => check(e, vars),
+                None => Ok(()),
+            }
+        }
+        Expr::Reduce(source, name, init, update) => {
+            check(source, vars)?;
+            check(init, vars)?;
+            vars.push(name.clone());
+            let r = check(update, vars);
+            vars.pop();
+            r
+        }
+        Expr::Bind(source, name, body) => {
+            check(source, vars)?;
+            vars.push(name.clone());
+            let r = check(body, vars);
+            vars.pop();
+            r
+        }
+        Expr::Call(name, args) => {
+            if !BUILTINS.contains(&(name.as_str(), args.len())) {
+                return Err(error(format!("{}/{} is not defined", name, args.len())));
+            }
+            for arg in args {
+                check(arg, vars)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn check_segments(segments: &[StrSegment], vars: &mut Vec<String>) -> Result<(), JSONError> {
+    for segment in segments {
+        if let StrSegment::Interp(e) = segment {
+            check(e, vars)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn eval(expr: &Expr, input: &JSONValue, env: Option<&Env>) -> Outputs {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Recurse => {
+            let mut out = vec![];
+            recurse_values(input, &mut out);
+            Ok(out)
+        }
+        Expr::Literal(v) => Ok(vec![v.clone()]),
+        Expr::Var(name) => match Env::lookup(env, name) {
+            Some(v) => Ok(vec![v]),
+            None => Err(error(format!("${} is not defined", name))),
+        },
+        Expr::Str(segments) => Ok(interpolate(segments, input, env)?
+            .into_iter()
+            .map(JSONValue::String)
+            .collect()),
+        Expr::Field(target, name) => {
+            let key = JSONValue::String(name.clone());
+            let mut out = vec![];
+            for t in eval(target, input, env)? {
+                out.push(index(&t, &key)?);
+            }
+            Ok(out)
+        }
+        Expr::Index(target, idx) => {
+            let keys = eval(idx, input, env)?;
+            let mut out = vec![];
+            for t in eval(target, input, env)? {
+                for k in &keys {
+                    out.push(index(&t, k)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::Slice(target, from, to) => {
+            let froms = match from {
+                Some(e) => eval(e, input, env)?,
+                None => vec![JSONValue::Null],
+            };
+            let tos = match to {
+                Some(e) => eval(e, input, env)?,
+                None => vec![JSONValue::Null],
+            };
+            let mut out = vec![];
+            for t in eval(target, input, env)? {
+                for to in &tos {
+                    for from in &froms {
+                        out.push(slice(&t, from, to)?);
+                    }
+                }
+            }
+            Ok(out)
+        }
+        Expr::Iterate(target) => {
+            let mut out = vec![];
+            for t in eval(target, input, env)? {
+                out.extend(iterate(&t)?);
+            }
+            Ok(out)
+        }
+        Expr::Optional(e) => Ok(eval(e, input, env).unwrap_or_default()),
+        Expr::Array(e) => match e {
+            Some(e) => Ok(vec![JSONValue::Array(eval(e, input, env)?)]),
+            None => Ok(vec![JSONValue::Array(vec![])]),
+        },
+        Expr::Object(entries) => construct_object(entries, input, env),
+        Expr::Pipe(lhs, rhs) => {
+            let mut out = vec![];
+            for v in eval(lhs, input, env)? {
+                out.extend(eval(rhs, &v, env)?);
+            }
+            Ok(out)
+        }
+        Expr::Comma(lhs, rhs) => {
+            let mut out = eval(lhs, input, env)?;
+            out.extend(eval(rhs, input, env)?);
+            Ok(out)
+        }
+        Expr::Neg(e) => {
+            let mut out = vec![];
+            for v in eval(e, input, env)? {
+                match v {
+                    JSONValue::Number(n) => out.push(JSONValue::Number(-n)),
+                    v => return Err(error(format!("{} cannot be negated", describe(&v)))),
+                }
+            }
+            Ok(out)
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let rhs = eval(rhs, input, env)?;
+            let lhs = eval(lhs, input, env)?;
+            let mut out = vec![];
+            for r in &rhs {
+                for l in &lhs {
+                    out.push(binary(*op, l, r)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::And(lhs, rhs) => {
+            let mut out = vec![];
+            for l in eval(lhs, input, env)? {
+                if !truthy(&l) {
+                    out.push(JSONValue::Boolean(false));
+                    continue;
+                }
+                for r in eval(rhs, input, env)? {
+                    out.push(JSONValue::Boolean(truthy(&r)));
+                }
+            }
+            Ok(out)
+        }
+        Expr::Or(lhs, rhs) => {
+            let mut out = vec![];
+            for l in eval(lhs, input, env)? {
+                if truthy(&l) {
+                    out.push(JSONValue::Boolean(true));
+                    continue;
+                }
+                for r in eval(rhs, input, env)? {
+                    out.push(JSONValue::Boolean(truthy(&r)));
+                }
+            }
+            Ok(out)
+        }
+        Expr::Alt(lhs, rhs) => {
+            let out: Vec<JSONValue> = eval(lhs, input, env)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect();
+            if out.is_empty() {
+                return eval(rhs, input, env);
+            }
+            Ok(out)
+        }
+        Expr::If(branches, otherwise) => eval_if(branches, otherwise, input, env),
+        Expr::Try(body, handler) => match eval(body, input, env) {
+            Ok(out) => Ok(out),
+            Err(err) => match handler {
+                Some(h) => eval(h, &JSONValue::String(err.message().to_string()), env),
+                None => Ok(vec![]),
+            },
+        },
+        Expr::Reduce(source, name, init, update) => {
+            let items = eval(source, input, env)?;
+            let mut out = vec![];
+            for mut acc in eval(init, input, env)? {
+                for item in &items {
+                    let scope = Env {
+                        parent: env,
+                        name,
+                        value: item.clone(),
+                    };
+                    acc = eval(update, &acc, Some(&scope))?
+                        .pop()
+                        .unwrap_or(JSONValue::Null);
+                }
+                out.push(acc);
+            }
+            Ok(out)
+        }
+        Expr::Bind(source, name, body) => {
+            let mut out = vec![];
+            for v in eval(source, input, env)? {
+                let scope = Env {
+                    parent: env,
+                    name,
+                    value: v,
+                };
+                out.extend(eval(body, input, Some(&scope))?);
+            }
+            Ok(out)
+        }
+        Expr::Call(name, args) => call(name, args, input, env),
+    }
+}
+
+fn eval_if(
+    branches: &[(Expr, Expr)],
+    otherwise: &Option<Box<Expr>>,
+    input: &JSONValue,
+    env: Option<&Env>,
+) -> Outputs {
+    let (cond, then) = match branches.first() {
+        Some(branch) => branch,
+        None => {
+            return match otherwise {
+                Some(e) => eval(e, input, env),
+                None => Ok(vec![input.clone()]),
+            }
+        }
+    };
+    let mut out = vec![];
+    for c in eval(cond, input, env)? {
+        if truthy(&c) {
+            out.extend(eval(then, input, env)?);
+        } else {
+            out.extend(eval_if(&branches[1..], otherwise, input, env)?);
+        }
+    }
+    Ok(out)
+}
+
+fn interpolate(
+    segments: &[StrSegment],
+    input: &JSONValue,
+    env: Option<&Env>,
+) -> Result<Vec<String>, JSONError> {
+    let mut out = vec!["".to_string()];
+    for segment in segments {
+        match segment {
+            StrSegment::Literal(s) => out.iter_mut().for_each(|o| o.push_str(s)),
+            StrSegment::Interp(e) => {
+                let values = eval(e, input, env)?;
+                let mut next = vec![];
+                for v in &values {
+                    for o in &out {
+                        next.push(format!("{}{}", o, tostring(v)));
+                    }
+                }
+                out = next;
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn construct_object(
+    entries: &[(ObjKey, Option<Expr>)],
+    input: &JSONValue,
+    env: Option<&Env>,
+) -> Outputs {
+    let mut out = vec![HashMap::new()];
+    for (key, value) in entries {
+        let keys = match key {
+            ObjKey::Name(name) => vec![name.clone()],
+            ObjKey::Var(name) => vec![name.clone()],
+            ObjKey::Str(segments) => interpolate(segments, input, env)?,
+            ObjKey::Computed(e) => {
+                let mut keys = vec![];
+                for k in eval(e, input, env)? {
+                    match k {
+                        JSONValue::String(s) => keys.push(s),
+                        k => {
+                            return Err(error(format!(
+                                "Object keys must be strings, not {}",
+                                k.type_name()
+                            )))
+                        }
+                    }
+                }
+                keys
+            }
+        };
+
+        let mut pairs = vec![];
+        for k in keys {
+            let values = match (value, key) {
+                (Some(e), _) => eval(e, input, env)?,
+                (None, ObjKey::Var(name)) => eval(&Expr::Var(name.clone()), input, env)?,
+                (None, _) => vec![index(input, &JSONValue::String(k.clone()))?],
+            };
+            for v in values {
+                pairs.push((k.clone(), v));
+            }
+        }
+
+        let mut next = vec![];
+        for (k, v) in &pairs {
+            for o in &out {
+                let mut o = o.clone();
+                o.insert(k.clone(), v.clone());
+                next.push(o);
+            }
+        }
+        out = next;
+    }
+    Ok(out.into_iter().map(JSONValue::Object).collect())
+}
+
+fn call(name: &str, args: &[Expr], input: &JSONValue, env: Option<&Env>) -> Outputs {
+    let arg = |i: usize| eval(&args[i], input, env);
+    let one = |v: JSONValue| Ok(vec![v]);
+    match (name, args.len()) {
+        ("empty", 0) => Ok(vec![]),
+        ("error", 0) => Err(error(tostring(input))),
+        ("error", 1) => match arg(0)?.into_iter().next() {
+            Some(v) => Err(error(tostring(&v))),
+            None => Ok(vec![]),
+        },
+        ("not", 0) => one(JSONValue::Boolean(!truthy(input))),
+        ("length", 0) => one(JSONValue::Number(match input {
+            JSONValue::Null => 0f64,
+            JSONValue::Number(n) => n.abs(),
+            JSONValue::String(s) => s.chars().count() as f64,
+            JSONValue::Array(vc) => vc.len() as f64,
+            JSONValue::Object(hm) => hm.len() as f64,
+            v => return Err(error(format!("{} has no length", describe(v)))),
+        })),
+        ("keys", 0) | ("keys_unsorted", 0) => match input {
+            JSONValue::Object(hm) => one(JSONValue::Array(
+                sorted_keys(hm)
+                    .into_iter()
+                    .map(|k| JSONValue::String(k.clone()))
+                    .collect(),
+            )),
+            JSONValue::Array(vc) => one(JSONValue::Array(
+                (0..vc.len()).map(|i| JSONValue::Number(i as f64)).collect(),
+            )),
+            v => Err(error(format!("{} has no keys", describe(v)))),
+        },
+        ("values", 0) => Ok(if input.is_null() {
+            vec![]
+        } else {
+            vec![input.clone()]
+        }),
+        ("has", 1) => {
+            let mut out = vec![];
+            for k in arg(0)? {
+                let found = match (input, &k) {
+                    (JSONValue::Object(hm), JSONValue::String(s)) => hm.contains_key(s),
+                    (JSONValue::Array(vc), JSONValue::Number(n)) => {
+                        *n >= 0f64 && (*n as usize) < vc.len()
+                    }
+                    _ => {
+                        return Err(error(format!(
+                            "Cannot check whether {} has a {} key",
+                            input.type_name(),
+                            k.type_name()
+                        )))
+                    }
+                };
+                out.push(JSONValue::Boolean(found));
+            }
+            Ok(out)
+        }
+        ("to_entries", 0) => match input {
+            JSONValue::Object(hm) => one(to_entries(hm)),
+            v => Err(error(format!("{} has no keys", describe(v)))),
+        },
+        ("from_entries", 0) => one(from_entries(input)?),
+        ("with_entries", 1) => match input {
+            JSONValue::Object(hm) => {
+                let mut mapped = vec![];
+                if let JSONValue::Array(entries) = to_entries(hm) {
+                    for entry in entries {
+                        mapped.extend(eval(&args[0], &entry, env)?);
+                    }
+                }
+                one(from_entries(&JSONValue::Array(mapped))?)
+            }
+            v => Err(error(format!("{} has no keys", describe(v)))),
+        },
+        ("select", 1) => Ok(arg(0)?
+            .iter()
+            .filter(|v| truthy(v))
+            .map(|_| input.clone())
+            .collect()),
+        ("map", 1) => {
+            let mut out = vec![];
+            for v in iterate(input)? {
+                out.extend(eval(&args[0], &v, env)?);
+            }
+            one(JSONValue::Array(out))
+        }
+        ("map_values", 1) => match input {
+            JSONValue::Object(hm) => {
+                let mut out = HashMap::new();
+                for (k, v) in hm {
+                    if let Some(v) = eval(&args[0], v, env)?.into_iter().next() {
+                        out.insert(k.clone(), v);
+                    }
+                }
+                one(JSONValue::Object(out))
+            }
+            JSONValue::Array(vc) => {
+                let mut out = vec![];
+                for v in vc {
+                    if let Some(v) = eval(&args[0], v, env)?.into_iter().next() {
+                        out.push(v);
+                    }
+                }
+                one(JSONValue::Array(out))
+            }
+            v => Err(error(format!("Cannot iterate over {}", describe(v)))),
+        },
+        ("add", 0) => {
+            let mut acc = JSONValue::Null;
+            for v in iterate(input)? {
+                acc = binary(BinOp::Add, &acc, &v)?;
+            }
+            one(acc)
+        }
+        ("any", 0) => one(JSONValue::Boolean(iterate(input)?.iter().any(truthy))),
+        ("all", 0) => one(JSONValue::Boolean(iterate(input)?.iter().all(truthy))),
+        ("any", 1) | ("all", 1) => {
+            let mut results = vec![];
+            for v in iterate(input)? {
+                results.extend(eval(&args[0], &v, env)?);
+            }
+            one(JSONValue::Boolean(if name == "any" {
+                results.iter().any(truthy)
+            } else {
+                results.iter().all(truthy)
+            }))
+        }
+        ("type", 0) => one(JSONValue::String(input.type_name().to_string())),
+        ("tostring", 0) => one(JSONValue::String(tostring(input))),
+        ("tonumber", 0) => match input {
+            JSONValue::Number(_) => one(input.clone()),
+            JSONValue::String(s) => match s.trim().parse::<f64>() {
+                Ok(n) => one(JSONValue::Number(n)),
+                Err(_) => Err(error(format!("Cannot parse '{}' as JSON", s))),
+            },
+            v => Err(error(format!(
+                "{} cannot be parsed as a number",
+                describe(v)
+            ))),
+        },
+        ("tojson", 0) => one(JSONValue::String(input.to_string())),
+        ("fromjson", 0) => match input {
+            JSONValue::String(s) => one(Parser::from_string(s).parse()?),
+            v => Err(error(format!("{} cannot be parsed as JSON", describe(v)))),
+        },
+        ("sort", 0) => {
+            let mut vc = array(input, "sorted")?.clone();
+            vc.sort_by(compare);
+            one(JSONValue::Array(vc))
+        }
+        ("sort_by", 1) | ("group_by", 1) | ("unique_by", 1) | ("min_by", 1) | ("max_by", 1) => {
+            let vc = array(input, "sorted")?;
+            let mut keyed = vec![];
+            for v in vc {
+                keyed.push((JSONValue::Array(eval(&args[0], v, env)?), v.clone()));
+            }
+            keyed.sort_by(|a, b| compare(&a.0, &b.0));
+            one(by_key(name, keyed))
+        }
+        ("unique", 0) => {
+            let mut vc = array(input, "sorted")?.clone();
+            vc.sort_by(compare);
+            vc.dedup();
+            one(JSONValue::Array(vc))
+        }
+        ("min", 0) => one(array(input, "sorted")?
+            .iter()
+            .min_by(|a, b| compare(a, b))
+            .cloned()
+            .unwrap_or(JSONValue::Null)),
+        ("max", 0) => one(array(input, "sorted")?
+            .iter()
+            .max_by(|a, b| compare(a, b))
+            .cloned()
+            .unwrap_or(JSONValue::Null)),
+        ("reverse", 0) => match input {
+            JSONValue::Null => one(JSONValue::Array(vec![])),
+            JSONValue::String(s) => one(JSONValue::String(s.chars().rev().collect())),
+            v => {
+                let mut vc = array(v, "reversed")?.clone();
+                vc.reverse();
+                one(JSONValue::Array(vc))
+            }
+        },
+        ("flatten", 0) => one(JSONValue::Array(flatten(array(input, "flattened")?, None))),
+        ("flatten", 1) => {
+            let mut out = vec![];
+            for depth in arg(0)? {
+                match depth {
+                    JSONValue::Number(d) if d >= 0f64 => out.push(JSONValue::Array(flatten(
+                        array(input, "flattened")?,
+                        Some(d as usize),
+                    ))),
+                    _ => return Err(error("flatten depth must not be negative".to_string())),
+                }
+            }
+            Ok(out)
+        }
+        ("range", 1) => {
+            let mut out = vec![];
+            for n in arg(0)? {
+                out.extend(range(&JSONValue::Number(0f64), &n)?);
+            }
+            Ok(out)
+        }
+        ("range", 2) => {
+            let mut out = vec![];
+            let ends = arg(1)?;
+            for from in arg(0)? {
+                for to in &ends {
+                    out.extend(range(&from, to)?);
+                }
+            }
+            Ok(out)
+        }
+        ("floor", 0) | ("sqrt", 0) => match input {
+            JSONValue::Number(n) => one(JSONValue::Number(if name == "floor" {
+                n.floor()
+            } else {
+                n.sqrt()
+            })),
+            v => Err(error(format!("{} number required", describe(v)))),
+        },
+        ("join", 1) => {
+            let mut out = vec![];
+            for sep in arg(0)? {
+                let sep = match sep {
+                    JSONValue::String(s) => s,
+                    v => return Err(error(format!("Cannot join with {}", describe(&v)))),
+                };
+                let mut parts = vec![];
+                for v in iterate(input)? {
+                    parts.push(match v {
+                        JSONValue::Null => "".to_string(),
+                        JSONValue::String(s) => s,
+                        JSONValue::Number(_) | JSONValue::Boolean(_) => v.to_string(),
+                        v => return Err(error(format!("Cannot join {}", describe(&v)))),
+                    });
+                }
+                out.push(JSONValue::String(parts.join(&sep)));
+            }
+            Ok(out)
+        }
+        ("split", 1) => {
+            let mut out = vec![];
+            for sep in arg(0)? {
+                out.push(binary(BinOp::Div, input, &sep)?);
+            }
+            Ok(out)
+        }
+        ("ascii_downcase", 0) | ("ascii_upcase", 0) => match input {
+            JSONValue::String(s) => one(JSONValue::String(if name == "ascii_downcase" {
+                s.to_ascii_lowercase()
+            } else {
+                s.to_ascii_uppercase()
+            })),
+            v => Err(error(format!("{} cannot be case converted", describe(v)))),
+        },
+        ("startswith", 1) | ("endswith", 1) => {
+            let mut out = vec![];
+            for affix in arg(0)? {
+                let found = match (input, &affix) {
+                    (JSONValue::String(s), JSONValue::String(a)) => {
+                        if name == "startswith" {
+                            s.starts_with(a.as_str())
+                        } else {
+                            s.ends_with(a.as_str())
+                        }
+                    }
+                    _ => return Err(error(format!("{}() requires string inputs", name))),
+                };
+                out.push(JSONValue::Boolean(found));
+            }
+            Ok(out)
+        }
+        ("recurse", 0) => {
+            let mut out = vec![];
+            recurse_values(input, &mut out);
+            Ok(out)
+        }
+        ("recurse", 1) => {
+            let mut out = vec![];
+            recurse_with(&args[0], input, env, &mut out)?;
+            Ok(out)
+        }
+        ("first", 0) => index(input, &JSONValue::Number(0f64)).map(|v| vec![v]),
+        ("last", 0) => index(input, &JSONValue::Number(-1f64)).map(|v| vec![v]),
+        ("first", 1) => Ok(arg(0)?.into_iter().take(1).collect()),
+        ("last", 1) => Ok(arg(0)?.pop().into_iter().collect()),
+        _ => Err(error(format!("{}/{} is not defined", name, args.len()))),
+    }
+}
+
+fn by_key(name: &str, keyed: Vec<(JSONValue, JSONValue)>) -> JSONValue {
+    match name {
+        "sort_by" => JSONValue::Array(keyed.into_iter().map(|(_, v)| v).collect()),
+        "min_by" => keyed.into_iter().next().map_or(JSONValue::Null, |(_, v)| v),
+        "max_by" => keyed.into_iter().last().map_or(JSONValue::Null, |(_, v)| v),
+        _ => {
+            let mut groups: Vec<(JSONValue, Vec<JSONValue>)> = vec![];
+            for (k, v) in keyed {
+                match groups.last_mut() {
+                    Some((last, group)) if *last == k => group.push(v),
+                    _ => groups.push((k, vec![v])),
+                }
+            }
+            JSONValue::Array(
+                groups
+                    .into_iter()
+                    .map(|(_, mut group)| {
+                        if name == "unique_by" {
+                            group.swap_remove(0)
+                        } else {
+                            JSONValue::Array(group)
+                        }
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn recurse_values(v: &JSONValue, out: &mut Vec<JSONValue>) {
+    out.push(v.clone());
+    match v {
+        JSONValue::Array(vc) => vc.iter().for_each(|v| recurse_values(v, out)),
+        JSONValue::Object(hm) => sorted_keys(hm)
+            .into_iter()
+            .for_each(|k| recurse_values(&hm[k], out)),
+        _ => (),
+    }
+}
+
+fn recurse_with(
+    f: &Expr,
+    v: &JSONValue,
+    env: Option<&Env>,
+    out: &mut Vec<JSONValue>,
+) -> Result<(), JSONError> {
+    out.push(v.clone());
+    for child in eval(f, v, env)? {
+        recurse_with(f, &child, env, out)?;
+    }
+    Ok(())
+}
+
+fn range(from: &JSONValue, to: &JSONValue) -> Outputs {
+    match (from, to) {
+        (JSONValue::Number(from), JSONValue::Number(to)) => {
+            let mut out = vec![];
+            let mut n = *from;
+            while n < *to {
+                out.push(JSONValue::Number(n));
+                n += 1f64;
+            }
+            Ok(out)
+        }
+        _ => Err(error("Range bounds must be numeric".to_string())),
+    }
+}
+
+fn flatten(vc: &[JSONValue], depth: Option<usize>) -> Vec<JSONValue> {
+    let mut out = vec![];
+    for v in vc {
+        match v {
+            JSONValue::Array(inner) if depth != Some(0) => {
+                out.extend(flatten(inner, depth.map(|d| d - 1)))
+            }
+            v => out.push(v.clone()),
+        }
+    }
+    out
+}
+
+fn array<'a>(v: &'a JSONValue, what: &str) -> Result<&'a Vec<JSONValue>, JSONError> {
+    match v {
+        JSONValue::Array(vc) => Ok(vc),
+        v => Err(error(format!(
+            "{} cannot be {}, as it is not an array",
+            describe(v),
+            what
+        ))),
+    }
+}
+
+fn to_entries(hm: &HashMap<String, JSONValue>) -> JSONValue {
+    JSONValue::Array(
+        sorted_keys(hm)
+            .into_iter()
+            .map(|k| {
+                let mut entry = HashMap::new();
+                entry.insert("key".to_string(), JSONValue::String(k.clone()));
+                entry.insert("value".to_string(), hm[k].clone());
+                JSONValue::Object(entry)
+            })
+            .collect(),
+    )
+}
+
+fn from_entries(input: &JSONValue) -> Result<JSONValue, JSONError> {
+    let mut out = HashMap::new();
+    for entry in iterate(input)? {
+        let hm = match &entry {
+            JSONValue::Object(hm) => hm,
+            v => return Err(error(format!("Cannot use {} as an entry", describe(v)))),
+        };
+        let field = |names: &[&str]| names.iter().find_map(|n| hm.get(*n)).cloned();
+        let key = match field(&["key", "k", "name", "Name", "Key", "K"]) {
+            Some(JSONValue::String(s)) => s,
+            Some(v @ JSONValue::Number(_)) | Some(v @ JSONValue::Boolean(_)) => v.to_string(),
+            other => {
+                return Err(error(format!(
+                    "Cannot use {} as object key",
+                    other.map_or("null".to_string(), |v| describe(&v))
+                )))
+            }
+        };
+        let value = field(&["value", "v", "Value", "V"]).unwrap_or(JSONValue::Null);
+        out.insert(key, value);
+    }
+    Ok(JSONValue::Object(out))
+}
+
+fn sorted_keys(hm: &HashMap<String, JSONValue>) -> Vec<&String> {
+    let mut keys: Vec<&String> = hm.keys().collect();
+    keys.sort();
+    keys
+}
+
+fn iterate(v: &JSONValue) -> Outputs {
+    match v {
+        JSONValue::Array(vc) => Ok(vc.clone()),
+        JSONValue::Object(hm) => Ok(sorted_keys(hm).into_iter().map(|k| hm[k].clone()).collect()),
+        v => Err(error(format!("Cannot iterate over {}", describe(v)))),
+    }
+}
+
+fn index(v: &JSONValue, k: &JSONValue) -> Result<JSONValue, JSONError> {
+    match (v, k) {
+        (JSONValue::Null, JSONValue::String(_)) | (JSONValue::Null, JSONValue::Number(_)) => {
+            Ok(JSONValue::Null)
+        }
+        (JSONValue::Object(hm), JSONValue::String(s)) => {
+            Ok(hm.get(s).cloned().unwrap_or(JSONValue::Null))
+        }
+        (JSONValue::Array(vc), JSONValue::Number(n)) => {
+            let i = n.floor();
+            let i = if i < 0f64 { vc.len() as f64 + i } else { i };
+            if i < 0f64 {
+                return Ok(JSONValue::Null);
+            }
+            Ok(vc.get(i as usize).cloned().unwrap_or(JSONValue::Null))
+        }
+        _ => Err(error(format!(
+            "Cannot index {} with {}",
+            v.type_name(),
+            match k {
+                JSONValue::String(s) => format!("\"{}\"", s),
+                k => k.type_name().to_string(),
+            }
+        ))),
+    }
+}
+
+fn slice(v: &JSONValue, from: &JSONValue, to: &JSONValue) -> Result<JSONValue, JSONError> {
+    let len = match v {
+        JSONValue::Null => return Ok(JSONValue::Null),
+        JSONValue::Array(vc) => vc.len(),
+        JSONValue::String(s) => s.chars().count(),
+        v => return Err(error(format!("Cannot index {} with object", v.type_name()))),
+    };
+    let bound = |b: &JSONValue, default: usize| -> Result<usize, JSONError> {
+        match b {
+            JSONValue::Null => Ok(default),
+            JSONValue::Number(n) => {
+                let n = if *n < 0f64 { len as f64 + n } else { *n };
+                Ok(n.max(0f64).min(len as f64) as usize)
+            }
+            _ => Err(error(
+                "Start and end indices of a slice must be numbers".to_string(),
+            )),
+        }
+    };
+    let start = bound(from, 0)?;
+    let end = bound(to, len)?.max(start);
+    match v {
+        JSONValue::Array(vc) => Ok(JSONValue::Array(vc[start..end].to_vec())),
+        JSONValue::String(s) => Ok(JSONValue::String(
+            s.chars().skip(start).take(end - start).collect(),
+        )),
+        _ => unreachable!(),
+    }
+}
+
+fn binary(op: BinOp, l: &JSONValue, r: &JSONValue) -> Result<JSONValue, JSONError> {
+    use JSONValue::*;
+    let v = match (op, l, r) {
+        (BinOp::Eq, _, _) => Boolean(l == r),
+        (BinOp::Neq, _, _) => Boolean(l != r),
+        (BinOp::Lt, _, _) => Boolean(compare(l, r) == Ordering::Less),
+        (BinOp::Lte, _, _) => Boolean(compare(l, r) != Ordering::Greater),
+        (BinOp::Gt, _, _) => Boolean(compare(l, r) == Ordering::Greater),
+        (BinOp::Gte, _, _) => Boolean(compare(l, r) != Ordering::Less),
+        (BinOp::Add, Null, v) | (BinOp::Add, v, Null) => v.clone(),
+        (BinOp::Add, Number(a), Number(b)) => Number(a + b),
+        (BinOp::Add, String(a), String(b)) => String(format!("{}{}", a, b)),
+        (BinOp::Add, Array(a), Array(b)) => Array(a.iter().chain(b.iter()).cloned().collect()),
+        (BinOp::Add, Object(a), Object(b)) => {
+            let mut hm = a.clone();
+            hm.extend(b.iter().map(|(k, v)| (k.clone(), v.clone())));
+            Object(hm)
+        }
+        (BinOp::Sub, Number(a), Number(b)) => Number(a - b),
+        (BinOp::Sub, Array(a), Array(b)) => {
+            Array(a.iter().filter(|v| !b.contains(v)).cloned().collect())
+        }
+        (BinOp::Mul, Number(a), Number(b)) => Number(a * b),
+        (BinOp::Mul, String(s), Number(n)) | (BinOp::Mul, Number(n), String(s)) => {
+            if *n <= 0f64 {
+                Null
+            } else {
+                // `as` saturates, so huge counts fail the length check
+                match (n.ceil() as usize).checked_mul(s.len()) {
+                    Some(len) if len <= MAX_REPEAT_LEN => String(s.repeat(n.ceil() as usize)),
+                    _ => {
+                        return Err(error(format!(
+                            "{} cannot be repeated {} times",
+                            describe(if l.is_string() { l } else { r }),
+                            n
+                        )))
+                    }
+                }
+            }
+        }
+        (BinOp::Mul, Object(_), Object(_)) => deep_merge(l, r),
+        (BinOp::Div, Number(a), Number(b)) => {
+            if *b == 0f64 {
+                return Err(error(format!(
+                    "{} and {} cannot be divided because the divisor is zero",
+                    describe(l),
+                    describe(r)
+                )));
+            }
+            Number(a / b)
+        }
+        (BinOp::Div, String(a), String(b)) => Array(if a.is_empty() {
+            vec![]
+        } else {
+            a.split(b.as_str()).map(|s| String(s.to_string())).collect()
+        }),
+        (BinOp::Mod, Number(a), Number(b)) => {
+            let (a, b) = (*a as i64, *b as i64);
+            if b == 0 {
+                return Err(error(format!(
+                    "{} and {} cannot be divided because the divisor is zero",
+                    describe(l),
+                    describe(r)
+                )));
+            }
+            // i64::MIN % -1 overflows, its remainder is 0
+            Number(a.wrapping_rem(b) as f64)
+        }
+        _ => {
+            let verb = match op {
+                BinOp::Add => "added",
+                BinOp::Sub => "subtracted",
+                BinOp::Mul => "multiplied",
+                _ => "divided",
+            };
+            return Err(error(format!(
+                "{} and {} cannot be {}",
+                describe(l),
+                describe(r),
+                verb
+            )));
+        }
+    };
+    Ok(v)
+}
+
+fn deep_merge(l: &JSONValue, r: &JSONValue) -> JSONValue {
+    match (l, r) {
+        (JSONValue::Object(a), JSONValue::Object(b)) => {
+            let mut hm = a.clone();
+            for (k, v) in b {
+                let merged = match hm.get(k) {
+                    Some(existing) => deep_merge(existing, v),
+                    None => v.clone(),
+                };
+                hm.insert(k.clone(), merged);
+            }
+            JSONValue::Object(hm)
+        }
+        (_, r) => r.clone(),
+    }
+}
+
+fn rank(v: &JSONValue) -> u8 {
+    match v {
+        JSONValue::Null => 0,
+        JSONValue::Boolean(false) => 1,
+        JSONValue::Boolean(true) => 2,
+        JSONValue::Number(_) => 3,
+        JSONValue::String(_) => 4,
+        JSONValue::Array(_) => 5,
+        JSONValue::Object(_) => 6,
+    }
+}
+
+// jq ordering: null < false < true < numbers < strings < arrays < objects
+pub fn compare(a: &JSONValue, b: &JSONValue) -> Ordering {
+    match (a, b) {
+        (JSONValue::Number(x), JSONValue::Number(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (JSONValue::String(x), JSONValue::String(y)) => x.cmp(y),
+        (JSONValue::Array(x), JSONValue::Array(y)) => {
+            for (l, r) in x.iter().zip(y.iter()) {
+                match compare(l, r) {
+                    Ordering::Equal => continue,
+                    o => return o,
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        (JSONValue::Object(x), JSONValue::Object(y)) => {
+            let (kx, ky) = (sorted_keys(x), sorted_keys(y));
+            match kx.cmp(&ky) {
+                Ordering::Equal => (),
+                o => return o,
+            }
+            for k in kx {
+                match compare(&x[k], &y[k]) {
+                    Ordering::Equal => continue,
+                    o => return o,
+                }
+            }
+            Ordering::Equal
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn truthy(v: &JSONValue) -> bool {
+    !matches!(v, JSONValue::Null | JSONValue::Boolean(false))
+}
+
+fn tostring(v: &JSONValue) -> String {
+    match v {
+        JSONValue::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+fn describe(v: &JSONValue) -> String {
+    let mut text = v.to_string();
+    if text.chars().count() > 11 {
+        text = text.chars().take(10).collect::<String>() + "...";
+    }
+    format!("{} ({})", v.type_name(), text)
+}
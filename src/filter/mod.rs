@@ -0,0 +1,188 @@
+mod eval;
+mod parser;
+mod scanner;
+
+use crate::value::{JSONError, JSONValue};
+use eval::{check, eval};
+use parser::{Expr, Parser};
+use scanner::Scanner;
+
+// A compiled jq-style filter, e.g. `.items[] | select(.qty > 1) | {name}`.
+#[derive(Debug, Clone)]
+pub struct JSONFilter {
+    expr: Expr,
+}
+
+impl JSONFilter {
+    pub fn compile(text: &str) -> Result<JSONFilter, JSONError> {
+        let tokens = Scanner::new(text).tokens()?;
+        let expr = Parser::new(tokens).parse()?;
+        check(&expr, &mut vec![])?;
+        Ok(JSONFilter { expr })
+    }
+
+    pub fn run(&self, input: &JSONValue) -> Result<Vec<JSONValue>, JSONError> {
+        eval(&self.expr, input, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    fn run(filter: &str, input: &str) -> Vec<JSONValue> {
+        JSONFilter::compile(filter)
+            .unwrap()
+            .run(&json(input))
+            .unwrap()
+    }
+
+    fn run_one(filter: &str, input: &str) -> JSONValue {
+        let mut out = run(filter, input);
+        assert_eq!(out.len(), 1, "{} produced {:?}", filter, out);
+        out.remove(0)
+    }
+
+    #[test]
+    fn paths_and_iteration() {
+        let doc = r#"{ "a": { "b": [1, 2, 3] }, "c": "x" }"#;
+        assert_eq!(run_one(".a.b[1]", doc), JSONValue::Number(2f64));
+        assert_eq!(run_one(".a.b[-1]", doc), JSONValue::Number(3f64));
+        assert_eq!(run_one(r#".["c"]"#, doc), json(r#""x""#));
+        assert_eq!(run_one(".a.b[1:]", doc), json("[2, 3]"));
+        assert_eq!(run_one(".missing.deeper", doc), JSONValue::Null);
+        assert_eq!(run(".a.b[]", doc), vec![json("1"), json("2"), json("3")]);
+        assert_eq!(run(".c[]?", doc), vec![]);
+        assert!(JSONFilter::compile(".c[]")
+            .unwrap()
+            .run(&json(doc))
+            .is_err());
+    }
+
+    #[test]
+    fn pipes_and_builtins() {
+        let doc = r#"[ { "n": "a", "q": 1 }, { "n": "b", "q": 5 }, { "n": "c", "q": 3 } ]"#;
+        assert_eq!(
+            run_one("[.[] | select(.q > 2) | .n]", doc),
+            json(r#"["b", "c"]"#)
+        );
+        assert_eq!(run_one("map(.q * 2) | add", doc), json("18"));
+        assert_eq!(run_one("length", doc), json("3"));
+        assert_eq!(
+            run_one("sort_by(.q) | map(.n)", doc),
+            json(r#"["a", "c", "b"]"#)
+        );
+        assert_eq!(run_one(".[0] | keys", doc), json(r#"["n", "q"]"#));
+        assert_eq!(
+            run_one(".[0] | to_entries", doc),
+            json(r#"[{"key": "n", "value": "a"}, {"key": "q", "value": 1}]"#)
+        );
+        assert_eq!(
+            run_one(r#".[0] | with_entries(select(.key == "q"))"#, doc),
+            json(r#"{"q": 1}"#)
+        );
+    }
+
+    #[test]
+    fn construction() {
+        let doc = r#"{ "user": "jhon", "titles": ["a", "b"], "id": 7 }"#;
+        assert_eq!(
+            run("{user, title: .titles[]}", doc),
+            vec![
+                json(r#"{"user": "jhon", "title": "a"}"#),
+                json(r#"{"user": "jhon", "title": "b"}"#)
+            ]
+        );
+        assert_eq!(
+            run_one(r#"{(.user): .id, "k\(.id)": true}"#, doc),
+            json(r#"{"jhon": 7, "k7": true}"#)
+        );
+        assert_eq!(run_one("[.id, .id + 1]", doc), json("[7, 8]"));
+        assert_eq!(
+            run_one(r#""\(.user) has \(.titles | length)""#, doc),
+            json(r#""jhon has 2""#)
+        );
+    }
+
+    #[test]
+    fn arithmetic_and_control() {
+        assert_eq!(
+            run("(1, 2) + (10, 20)", "null"),
+            vec![json("11"), json("12"), json("21"), json("22")]
+        );
+        assert_eq!(run_one("10 % 3 - -1", "null"), json("2"));
+        assert_eq!(
+            run_one(r#"{"a": 1} * {"a": {"b": 2}}"#, "null"),
+            json(r#"{"a": {"b": 2}}"#)
+        );
+        assert_eq!(run_one(r#""a,b" / ",""#, "null"), json(r#"["a", "b"]"#));
+        assert_eq!(run_one(".x // 5", "{}"), json("5"));
+        assert_eq!(
+            run_one(
+                "if . > 2 then \"big\" elif . > 1 then \"mid\" else \"small\" end",
+                "2"
+            ),
+            json(r#""mid""#)
+        );
+        assert_eq!(
+            run_one("reduce .[] as $x (0; . + $x)", "[1, 2, 3]"),
+            json("6")
+        );
+        assert_eq!(run_one(". as $n | [range($n)]", "3"), json("[0, 1, 2]"));
+        assert_eq!(
+            run_one("try error(\"boom\") catch .", "null"),
+            json(r#""boom""#)
+        );
+        assert_eq!(
+            run_one(".a and (.b or false)", r#"{"a": 1, "b": null}"#),
+            json("false")
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        assert!(JSONFilter::compile("map(").is_err());
+        assert!(JSONFilter::compile("nosuchfn").is_err());
+        assert!(JSONFilter::compile("map(.a; .b)").is_err());
+        assert!(JSONFilter::compile(".a | $undefined").is_err());
+
+        let err = JSONFilter::compile(".a |\n  ]").unwrap_err();
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.col(), 3);
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let filter = JSONFilter::compile(".a + 1").unwrap();
+        let err = filter.run(&json(r#"{"a": "x"}"#)).unwrap_err();
+        assert_eq!(
+            err.message(),
+            r#"string ("x") and number (1) cannot be added"#
+        );
+        assert!(JSONFilter::compile("1 / 0")
+            .unwrap()
+            .run(&JSONValue::Null)
+            .is_err());
+        let err = JSONFilter::compile(r#""x" * 1e20"#)
+            .unwrap()
+            .run(&JSONValue::Null)
+            .unwrap_err();
+        assert_eq!(
+            err.message(),
+            r#"string ("x") cannot be repeated 100000000000000000000 times"#
+        );
+        assert_eq!(run_one(r#""ab" * 2.5"#, "null"), json(r#""ababab""#));
+        assert_eq!(run_one(r#""" * 1e20"#, "null"), json(r#""""#));
+        assert_eq!(run_one("-9223372036854775808 % -1", "null"), json("0"));
+        assert_eq!(run_one("-7 % 3", "null"), json("-1"));
+        assert!(JSONFilter::compile("5 % 0")
+            .unwrap()
+            .run(&JSONValue::Null)
+            .is_err());
+    }
+}
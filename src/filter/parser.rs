@@ -0,0 +1,608 @@
+use std::collections::HashMap;
+
+use crate::filter::scanner::{StrPart, Token, TokenKind};
+use crate::value::{JSONError, JSONValue};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrSegment {
+    Literal(String),
+    Interp(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjKey {
+    Name(String),
+    Var(String),
+    Str(Vec<StrSegment>),
+    Computed(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Identity,
+    Recurse,
+    Literal(JSONValue),
+    Str(Vec<StrSegment>),
+    Var(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(ObjKey, Option<Expr>)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Alt(Box<Expr>, Box<Expr>),
+    If(Vec<(Expr, Expr)>, Option<Box<Expr>>),
+    Try(Box<Expr>, Option<Box<Expr>>),
+    Reduce(Box<Expr>, String, Box<Expr>, Box<Expr>),
+    Bind(Box<Expr>, String, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+const KEYWORDS: [&str; 12] = [
+    "if", "then", "elif", "else", "end", "as", "and", "or", "try", "catch", "reduce", "def",
+];
+
+#[derive(Debug)]
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // postfix terms already parsed while looking ahead for `as`
+    memo: HashMap<usize, Result<(Expr, usize), JSONError>>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser {
+            tokens,
+            pos: 0,
+            memo: HashMap::new(),
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Expr, JSONError> {
+        let expr = self.parse_pipe()?;
+        if self.ct().kind != TokenKind::Eof {
+            return Err(self.unexpected());
+        }
+        Ok(expr)
+    }
+
+    fn ct(&self) -> &Token {
+        // the scanner always terminates the stream with an Eof token
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn consume(&mut self) -> Token {
+        let t = self.ct().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn is_keyword(&self, word: &str) -> bool {
+        self.ct().kind == TokenKind::Ident(word.to_string())
+    }
+
+    fn unexpected(&self) -> JSONError {
+        let ct = self.ct();
+        let what = match &ct.kind {
+            TokenKind::Eof => "end of filter".to_string(),
+            k => format!("{:?}", k),
+        };
+        JSONError::new(format!("Unexpected token {}", what), ct.line, ct.col)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, JSONError> {
+        if self.ct().kind != kind {
+            let ct = self.ct();
+            return Err(JSONError::new(
+                format!("Expecting {:?} but found {:?}", kind, ct.kind),
+                ct.line,
+                ct.col,
+            ));
+        }
+        Ok(self.consume())
+    }
+
+    fn expect_keyword(&mut self, word: &str) -> Result<(), JSONError> {
+        if !self.is_keyword(word) {
+            let ct = self.ct();
+            return Err(JSONError::new(
+                format!("Expecting `{}` but found {:?}", word, ct.kind),
+                ct.line,
+                ct.col,
+            ));
+        }
+        self.consume();
+        Ok(())
+    }
+
+    fn expect_var(&mut self) -> Result<String, JSONError> {
+        match self.consume() {
+            Token {
+                kind: TokenKind::Var(name),
+                ..
+            } => Ok(name),
+            t => Err(JSONError::new(
+                format!("Expecting variable but found {:?}", t.kind),
+                t.line,
+                t.col,
+            )),
+        }
+    }
+
+    // pipe: `term as $x | pipe` | comma ('|' pipe)?
+    fn parse_pipe(&mut self) -> Result<Expr, JSONError> {
+        let start = self.pos;
+        if let Ok(source) = self.parse_postfix() {
+            if self.is_keyword("as") {
+                self.consume();
+                let name = self.expect_var()?;
+                self.expect(TokenKind::Pipe)?;
+                let body = self.parse_pipe()?;
+                return Ok(Expr::Bind(Box::new(source), name, Box::new(body)));
+            }
+        }
+        self.pos = start;
+
+        let lhs = self.parse_comma()?;
+        if self.ct().kind == TokenKind::Pipe {
+            self.consume();
+            let rhs = self.parse_pipe()?;
+            return Ok(Expr::Pipe(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_comma(&mut self) -> Result<Expr, JSONError> {
+        let mut lhs = self.parse_alt()?;
+        while self.ct().kind == TokenKind::Comma {
+            self.consume();
+            let rhs = self.parse_alt()?;
+            lhs = Expr::Comma(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    // `//` is right associative
+    fn parse_alt(&mut self) -> Result<Expr, JSONError> {
+        let lhs = self.parse_or()?;
+        if self.ct().kind == TokenKind::Alt {
+            self.consume();
+            let rhs = self.parse_alt()?;
+            return Ok(Expr::Alt(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, JSONError> {
+        let mut lhs = self.parse_and()?;
+        while self.is_keyword("or") {
+            self.consume();
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, JSONError> {
+        let mut lhs = self.parse_comparison()?;
+        while self.is_keyword("and") {
+            self.consume();
+            let rhs = self.parse_comparison()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    // comparison operators are not associative
+    fn parse_comparison(&mut self) -> Result<Expr, JSONError> {
+        let lhs = self.parse_additive()?;
+        let op = match self.ct().kind {
+            TokenKind::Eq => BinOp::Eq,
+            TokenKind::Neq => BinOp::Neq,
+            TokenKind::Lt => BinOp::Lt,
+            TokenKind::Lte => BinOp::Lte,
+            TokenKind::Gt => BinOp::Gt,
+            TokenKind::Gte => BinOp::Gte,
+            _ => return Ok(lhs),
+        };
+        self.consume();
+        let rhs = self.parse_additive()?;
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, JSONError> {
+        let mut lhs = self.parse_multiplicative()?;
+        loop {
+            let op = match self.ct().kind {
+                TokenKind::Plus => BinOp::Add,
+                TokenKind::Minus => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.consume();
+            let rhs = self.parse_multiplicative()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, JSONError> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = match self.ct().kind {
+                TokenKind::Star => BinOp::Mul,
+                TokenKind::Slash => BinOp::Div,
+                TokenKind::Percent => BinOp::Mod,
+                _ => return Ok(lhs),
+            };
+            self.consume();
+            let rhs = self.parse_unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, JSONError> {
+        if self.ct().kind == TokenKind::Minus {
+            self.consume();
+            let e = self.parse_postfix()?;
+            return Ok(Expr::Neg(Box::new(e)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, JSONError> {
+        let start = self.pos;
+        if let Some(memo) = self.memo.get(&start) {
+            let (e, end) = memo.clone()?;
+            self.pos = end;
+            return Ok(e);
+        }
+        let r = self.parse_suffixes();
+        let memo = r.clone().map(|e| (e, self.pos));
+        self.memo.insert(start, memo);
+        r
+    }
+
+    fn parse_suffixes(&mut self) -> Result<Expr, JSONError> {
+        let mut e = self.parse_term()?;
+        loop {
+            match self.ct().kind.clone() {
+                TokenKind::Field(name) => {
+                    self.consume();
+                    e = Expr::Field(Box::new(e), name);
+                }
+                TokenKind::Dot => {
+                    self.consume();
+                    match self.ct().kind.clone() {
+                        TokenKind::Str(parts) => {
+                            self.consume();
+                            let key = Expr::Str(self.segments(parts)?);
+                            e = Expr::Index(Box::new(e), Box::new(key));
+                        }
+                        TokenKind::LeftBracket => e = self.parse_brackets(e)?,
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+                TokenKind::LeftBracket => e = self.parse_brackets(e)?,
+                TokenKind::Question => {
+                    self.consume();
+                    e = Expr::Optional(Box::new(e));
+                }
+                _ => return Ok(e),
+            }
+        }
+    }
+
+    // `[]`, `[e]`, `[e:]`, `[:e]` and `[e:e]` suffixes
+    fn parse_brackets(&mut self, target: Expr) -> Result<Expr, JSONError> {
+        let target = Box::new(target);
+        self.expect(TokenKind::LeftBracket)?;
+        if self.ct().kind == TokenKind::RightBracket {
+            self.consume();
+            return Ok(Expr::Iterate(target));
+        }
+
+        let from = if self.ct().kind == TokenKind::Collon {
+            None
+        } else {
+            Some(Box::new(self.parse_pipe()?))
+        };
+        if self.ct().kind == TokenKind::Collon {
+            self.consume();
+            let to = if self.ct().kind == TokenKind::RightBracket {
+                None
+            } else {
+                Some(Box::new(self.parse_pipe()?))
+            };
+            self.expect(TokenKind::RightBracket)?;
+            return Ok(Expr::Slice(target, from, to));
+        }
+        self.expect(TokenKind::RightBracket)?;
+        match from {
+            Some(index) => Ok(Expr::Index(target, index)),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, JSONError> {
+        let t = self.ct().clone();
+        match t.kind {
+            TokenKind::Dot => {
+                self.consume();
+                match self.ct().kind.clone() {
+                    TokenKind::Str(parts) => {
+                        self.consume();
+                        let key = Expr::Str(self.segments(parts)?);
+                        Ok(Expr::Index(Box::new(Expr::Identity), Box::new(key)))
+                    }
+                    TokenKind::LeftBracket => self.parse_brackets(Expr::Identity),
+                    _ => Ok(Expr::Identity),
+                }
+            }
+            TokenKind::DotDot => {
+                self.consume();
+                Ok(Expr::Recurse)
+            }
+            TokenKind::Field(name) => {
+                self.consume();
+                Ok(Expr::Field(Box::new(Expr::Identity), name))
+            }
+            TokenKind::Number(n) => {
+                self.consume();
+                Ok(Expr::Literal(JSONValue::Number(n)))
+            }
+            TokenKind::Str(parts) => {
+                self.consume();
+                let segments = self.segments(parts)?;
+                Ok(literal_or_str(segments))
+            }
+            TokenKind::Var(name) => {
+                self.consume();
+                Ok(Expr::Var(name))
+            }
+            TokenKind::LeftParen => {
+                self.consume();
+                let e = self.parse_pipe()?;
+                self.expect(TokenKind::RightParen)?;
+                Ok(e)
+            }
+            TokenKind::LeftBracket => {
+                self.consume();
+                if self.ct().kind == TokenKind::RightBracket {
+                    self.consume();
+                    return Ok(Expr::Array(None));
+                }
+                let e = self.parse_pipe()?;
+                self.expect(TokenKind::RightBracket)?;
+                Ok(Expr::Array(Some(Box::new(e))))
+            }
+            TokenKind::LeftBrace => self.parse_object(),
+            TokenKind::Ident(ref word) => match word.as_ref() {
+                "if" => self.parse_if(),
+                "try" => self.parse_try(),
+                "reduce" => self.parse_reduce(),
+                "null" => {
+                    self.consume();
+                    Ok(Expr::Literal(JSONValue::Null))
+                }
+                "true" => {
+                    self.consume();
+                    Ok(Expr::Literal(JSONValue::Boolean(true)))
+                }
+                "false" => {
+                    self.consume();
+                    Ok(Expr::Literal(JSONValue::Boolean(false)))
+                }
+                w if KEYWORDS.contains(&w) => Err(self.unexpected()),
+                _ => self.parse_call(),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_call(&mut self) -> Result<Expr, JSONError> {
+        let name = match self.consume().kind {
+            TokenKind::Ident(name) => name,
+            _ => unreachable!(),
+        };
+        let mut args = vec![];
+        if self.ct().kind == TokenKind::LeftParen {
+            self.consume();
+            loop {
+                args.push(self.parse_pipe()?);
+                match self.ct().kind {
+                    TokenKind::SemiCollon => {
+                        self.consume();
+                    }
+                    TokenKind::RightParen => {
+                        self.consume();
+                        break;
+                    }
+                    _ => return Err(self.unexpected()),
+                }
+            }
+        }
+        Ok(Expr::Call(name, args))
+    }
+
+    fn parse_if(&mut self) -> Result<Expr, JSONError> {
+        self.expect_keyword("if")?;
+        let mut branches = vec![];
+        loop {
+            let cond = self.parse_pipe()?;
+            self.expect_keyword("then")?;
+            let then = self.parse_pipe()?;
+            branches.push((cond, then));
+            if self.is_keyword("elif") {
+                self.consume();
+                continue;
+            }
+            break;
+        }
+        let otherwise = if self.is_keyword("else") {
+            self.consume();
+            Some(Box::new(self.parse_pipe()?))
+        } else {
+            None
+        };
+        self.expect_keyword("end")?;
+        Ok(Expr::If(branches, otherwise))
+    }
+
+    fn parse_try(&mut self) -> Result<Expr, JSONError> {
+        self.expect_keyword("try")?;
+        let body = self.parse_postfix()?;
+        let handler = if self.is_keyword("catch") {
+            self.consume();
+            Some(Box::new(self.parse_postfix()?))
+        } else {
+            None
+        };
+        Ok(Expr::Try(Box::new(body), handler))
+    }
+
+    // reduce SOURCE as $x (INIT; UPDATE)
+    fn parse_reduce(&mut self) -> Result<Expr, JSONError> {
+        self.expect_keyword("reduce")?;
+        let source = self.parse_postfix()?;
+        self.expect_keyword("as")?;
+        let name = self.expect_var()?;
+        self.expect(TokenKind::LeftParen)?;
+        let init = self.parse_pipe()?;
+        self.expect(TokenKind::SemiCollon)?;
+        let update = self.parse_pipe()?;
+        self.expect(TokenKind::RightParen)?;
+        Ok(Expr::Reduce(
+            Box::new(source),
+            name,
+            Box::new(init),
+            Box::new(update),
+        ))
+    }
+
+    fn parse_object(&mut self) -> Result<Expr, JSONError> {
+        self.expect(TokenKind::LeftBrace)?;
+        let mut entries = vec![];
+        while self.ct().kind != TokenKind::RightBrace {
+            let t = self.consume();
+            let key = match t.kind {
+                TokenKind::Ident(name) => ObjKey::Name(name),
+                TokenKind::Var(name) => ObjKey::Var(name),
+                TokenKind::Str(parts) => ObjKey::Str(self.segments(parts)?),
+                TokenKind::LeftParen => {
+                    let e = self.parse_pipe()?;
+                    self.expect(TokenKind::RightParen)?;
+                    ObjKey::Computed(e)
+                }
+                k => {
+                    return Err(JSONError::new(
+                        format!("Expecting key name but found {:?}", k),
+                        t.line,
+                        t.col,
+                    ))
+                }
+            };
+
+            let value = if self.ct().kind == TokenKind::Collon {
+                self.consume();
+                Some(self.parse_alt()?)
+            } else {
+                match key {
+                    ObjKey::Computed(_) => return Err(self.unexpected()),
+                    _ => None,
+                }
+            };
+            entries.push((key, value));
+
+            match self.ct().kind {
+                TokenKind::Comma => {
+                    self.consume();
+                }
+                TokenKind::RightBrace => (),
+                _ => return Err(self.unexpected()),
+            }
+        }
+        self.expect(TokenKind::RightBrace)?;
+        Ok(Expr::Object(entries))
+    }
+
+    fn segments(&self, parts: Vec<StrPart>) -> Result<Vec<StrSegment>, JSONError> {
+        let mut segments = vec![];
+        for part in parts {
+            match part {
+                StrPart::Literal(s) => segments.push(StrSegment::Literal(s)),
+                StrPart::Interp(tokens) => {
+                    let mut parser = Parser::new(tokens);
+                    segments.push(StrSegment::Interp(parser.parse()?));
+                }
+            }
+        }
+        Ok(segments)
+    }
+}
+
+fn literal_or_str(segments: Vec<StrSegment>) -> Expr {
+    match segments.as_slice() {
+        [StrSegment::Literal(s)] => Expr::Literal(JSONValue::String(s.clone())),
+        _ => Expr::Str(segments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::scanner::Scanner;
+
+    fn parse(text: &str) -> Result<Expr, JSONError> {
+        let tokens = Scanner::new(text).tokens()?;
+        Parser::new(tokens).parse()
+    }
+
+    #[test]
+    fn precedence() {
+        let e = parse(".a, .b | .c").unwrap();
+        match e {
+            Expr::Pipe(lhs, _) => assert!(matches!(*lhs, Expr::Comma(_, _))),
+            _ => panic!("expecting pipe"),
+        }
+
+        let e = parse("1 + 2 * 3").unwrap();
+        match e {
+            Expr::Binary(BinOp::Add, _, rhs) => {
+                assert!(matches!(*rhs, Expr::Binary(BinOp::Mul, _, _)))
+            }
+            _ => panic!("expecting addition"),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(".a |").is_err());
+        assert!(parse("{(.a)}").is_err());
+        assert!(parse("if . then 1").is_err());
+        assert!(parse("[.a").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use crate::value::JSONError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrPart {
+    Literal(String),
+    Interp(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Dot,
+    DotDot,
+    Field(String),
+    Ident(String),
+    Var(String),
+    Number(f64),
+    Str(Vec<StrPart>),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Pipe,
+    Comma,
+    Collon,
+    SemiCollon,
+    Question,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Alt,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Token {
+    pub fn new(k: TokenKind, l: usize, c: usize) -> Self {
+        Token {
+            kind: k,
+            line: l,
+            col: c,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    lin: usize,
+    col: usize,
+}
+
+impl Scanner {
+    pub fn new(text: &str) -> Self {
+        Scanner {
+            chars: text.chars().collect(),
+            pos: 0,
+            lin: 1,
+            col: 1,
+        }
+    }
+
+    pub fn tokens(&mut self) -> Result<Vec<Token>, JSONError> {
+        let tokens = self.scan(false)?;
+        Ok(tokens)
+    }
+
+    // scans tokens up to the end of input, or up to the `)` closing a
+    // string interpolation when `nested` is set
+    fn scan(&mut self, nested: bool) -> Result<Vec<Token>, JSONError> {
+        let mut tokens = vec![];
+        let mut depth = 0;
+        loop {
+            self.skip_whitespace();
+            let (lin, col) = (self.lin, self.col);
+            let c = match self.peek(0) {
+                Some(c) => c,
+                None => {
+                    if nested {
+                        return Err(JSONError::new(
+                            "Unterminated string interpolation".to_string(),
+                            lin,
+                            col,
+                        ));
+                    }
+                    tokens.push(Token::new(TokenKind::Eof, lin, col));
+                    return Ok(tokens);
+                }
+            };
+
+            let kind = match c {
+                '.' if self.peek(1) == Some('.') => {
+                    self.advance(2);
+                    TokenKind::DotDot
+                }
+                '.' if self.peek(1).is_some_and(is_ident_start) => {
+                    self.advance(1);
+                    TokenKind::Field(self.scan_ident())
+                }
+                '.' if self.peek(1).is_some_and(|p| p.is_ascii_digit()) => {
+                    TokenKind::Number(self.scan_num(lin, col)?)
+                }
+                '.' => {
+                    self.advance(1);
+                    TokenKind::Dot
+                }
+                '$' => {
+                    self.advance(1);
+                    if !self.peek(0).is_some_and(is_ident_start) {
+                        return Err(JSONError::new(
+                            "Expecting variable name after `$`".to_string(),
+                            lin,
+                            col,
+                        ));
+                    }
+                    TokenKind::Var(self.scan_ident())
+                }
+                '"' => TokenKind::Str(self.scan_str(lin, col)?),
+                '(' => {
+                    self.advance(1);
+                    depth += 1;
+                    TokenKind::LeftParen
+                }
+                ')' => {
+                    self.advance(1);
+                    if nested && depth == 0 {
+                        tokens.push(Token::new(TokenKind::Eof, lin, col));
+                        return Ok(tokens);
+                    }
+                    depth -= 1;
+                    TokenKind::RightParen
+                }
+                '[' => self.single(TokenKind::LeftBracket),
+                ']' => self.single(TokenKind::RightBracket),
+                '{' => self.single(TokenKind::LeftBrace),
+                '}' => self.single(TokenKind::RightBrace),
+                '|' => self.single(TokenKind::Pipe),
+                ',' => self.single(TokenKind::Comma),
+                ':' => self.single(TokenKind::Collon),
+                ';' => self.single(TokenKind::SemiCollon),
+                '?' => self.single(TokenKind::Question),
+                '+' => self.single(TokenKind::Plus),
+                '-' => self.single(TokenKind::Minus),
+                '*' => self.single(TokenKind::Star),
+                '%' => self.single(TokenKind::Percent),
+                '/' if self.peek(1) == Some('/') => {
+                    self.advance(2);
+                    TokenKind::Alt
+                }
+                '/' => self.single(TokenKind::Slash),
+                '=' if self.peek(1) == Some('=') => {
+                    self.advance(2);
+                    TokenKind::Eq
+                }
+                '!' if self.peek(1) == Some('=') => {
+                    self.advance(2);
+                    TokenKind::Neq
+                }
+                '<' if self.peek(1) == Some('=') => {
+                    self.advance(2);
+                    TokenKind::Lte
+                }
+                '<' => self.single(TokenKind::Lt),
+                '>' if self.peek(1) == Some('=') => {
+                    self.advance(2);
+                    TokenKind::Gte
+                }
+                '>' => self.single(TokenKind::Gt),
+                c if c.is_ascii_digit() => TokenKind::Number(self.scan_num(lin, col)?),
+                c if is_ident_start(c) => TokenKind::Ident(self.scan_ident()),
+                c => {
+                    return Err(JSONError::new(
+                        format!("Unexpected character `{}`", c),
+                        lin,
+                        col,
+                    ))
+                }
+            };
+            tokens.push(Token::new(kind, lin, col));
+        }
+    }
+
+    fn peek(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn advance(&mut self, n: usize) {
+        for _ in 0..n {
+            if let Some(c) = self.peek(0) {
+                if c == '\n' {
+                    self.lin += 1;
+                    self.col = 1;
+                } else {
+                    self.col += 1;
+                }
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn single(&mut self, kind: TokenKind) -> TokenKind {
+        self.advance(1);
+        kind
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() {
+                self.advance(1);
+            } else if c == '#' {
+                // comment until end of line
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.advance(1);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn scan_ident(&mut self) -> String {
+        let mut word = "".to_string();
+        while let Some(c) = self.peek(0) {
+            if is_ident_start(c) || c.is_ascii_digit() {
+                word.push(c);
+                self.advance(1);
+            } else {
+                break;
+            }
+        }
+        word
+    }
+
+    fn scan_num(&mut self, lin: usize, col: usize) -> Result<f64, JSONError> {
+        let mut num = "".to_string();
+        while let Some(c) = self.peek(0) {
+            let exponent_sign = (c == '-' || c == '+') && num.ends_with(['e', 'E']);
+            if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                num.push(c);
+                self.advance(1);
+            } else {
+                break;
+            }
+        }
+        num.parse::<f64>()
+            .map_err(|_| JSONError::new(format!("{} is NaN", num), lin, col))
+    }
+
+    fn scan_str(&mut self, lin: usize, col: usize) -> Result<Vec<StrPart>, JSONError> {
+        let mut parts = vec![];
+        let mut word = "".to_string();
+        self.advance(1); // consume opening "
+        loop {
+            let c = match self.peek(0) {
+                Some(c) => c,
+                None => return Err(JSONError::new("Unterminated string".to_string(), lin, col)),
+            };
+            self.advance(1);
+            match c {
+                '"' => break,
+                '\\' => {
+                    let e = self.peek(0);
+                    self.advance(1);
+                    match e {
+                        Some('(') => {
+                            if !word.is_empty() {
+                                parts.push(StrPart::Literal(word));
+                                word = "".to_string();
+                            }
+                            parts.push(StrPart::Interp(self.scan(true)?));
+                        }
+                        Some('"') => word.push('"'),
+                        Some('\\') => word.push('\\'),
+                        Some('/') => word.push('/'),
+                        Some('b') => word.push('\u{8}'),
+                        Some('f') => word.push('\u{c}'),
+                        Some('n') => word.push('\n'),
+                        Some('r') => word.push('\r'),
+                        Some('t') => word.push('\t'),
+                        Some('u') => {
+                            let hex: String = (0..4).filter_map(|i| self.peek(i)).collect();
+                            let ch = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(std::char::from_u32);
+                            match ch {
+                                Some(ch) if hex.len() == 4 => {
+                                    self.advance(4);
+                                    word.push(ch);
+                                }
+                                _ => {
+                                    return Err(JSONError::new(
+                                        format!("Invalid unicode escape \\u{}", hex),
+                                        self.lin,
+                                        self.col,
+                                    ))
+                                }
+                            }
+                        }
+                        other => {
+                            return Err(JSONError::new(
+                                format!("Invalid escape `\\{}`", other.unwrap_or(' ')),
+                                self.lin,
+                                self.col,
+                            ))
+                        }
+                    }
+                }
+                _ => word.push(c),
+            }
+        }
+        if !word.is_empty() || parts.is_empty() {
+            parts.push(StrPart::Literal(word));
+        }
+        Ok(parts)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<TokenKind> {
+        let mut scanner = Scanner::new(text);
+        scanner
+            .tokens()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn scanner() {
+        let tokens = kinds(".foo | .[] // 1.5e1 >= $x");
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Field("foo".to_string()),
+                TokenKind::Pipe,
+                TokenKind::Dot,
+                TokenKind::LeftBracket,
+                TokenKind::RightBracket,
+                TokenKind::Alt,
+                TokenKind::Number(15f64),
+                TokenKind::Gte,
+                TokenKind::Var("x".to_string()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn scanner_interpolation() {
+        let tokens = kinds(r#""a\(.b + (1))c""#);
+        match &tokens[0] {
+            TokenKind::Str(parts) => {
+                assert_eq!(parts.len(), 3);
+                assert_eq!(parts[0], StrPart::Literal("a".to_string()));
+                assert_eq!(parts[2], StrPart::Literal("c".to_string()));
+                match &parts[1] {
+                    StrPart::Interp(t) => assert_eq!(t.len(), 6),
+                    _ => panic!("expecting interpolation"),
+                }
+            }
+            _ => panic!("expecting string"),
+        }
+    }
+
+    #[test]
+    fn scanner_error() {
+        let mut scanner = Scanner::new(".a | @");
+        let err = scanner.tokens().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "JSONError: Unexpected character `@` - @ (1, 6)"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use super::{as_integer, child};
+use crate::value::{JSONError, JSONValue};
+
+// RFC 8949 encoding with definite lengths; whole numbers become integers,
+// anything else a float64, and map keys are written in sorted order.
+pub fn to_vec(value: &JSONValue) -> Result<Vec<u8>, JSONError> {
+    let mut out = vec![];
+    write_value(&mut out, value);
+    Ok(out)
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= 0xff {
+        out.extend_from_slice(&[major | 24, n as u8]);
+    } else if n <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &JSONValue) {
+    match value {
+        JSONValue::Null => out.push(0xf6),
+        JSONValue::Boolean(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        JSONValue::Number(n) => match as_integer(*n) {
+            Some(i) if i >= 0 => write_head(out, 0, i as u64),
+            Some(i) => write_head(out, 1, (-1 - i) as u64),
+            None => {
+                out.push(0xfb);
+                out.extend_from_slice(&n.to_be_bytes());
+            }
+        },
+        JSONValue::String(s) => {
+            write_head(out, 3, s.len() as u64);
+            out.extend_from_slice(s.as_bytes());
+        }
+        JSONValue::Array(vc) => {
+            write_head(out, 4, vc.len() as u64);
+            for v in vc {
+                write_value(out, v);
+            }
+        }
+        JSONValue::Object(hm) => {
+            write_head(out, 5, hm.len() as u64);
+            let mut keys: Vec<&String> = hm.keys().collect();
+            keys.sort();
+            for k in keys {
+                write_head(out, 3, k.len() as u64);
+                out.extend_from_slice(k.as_bytes());
+                write_value(out, &hm[k]);
+            }
+        }
+    }
+}
+
+pub fn from_slice(data: &[u8]) -> Result<JSONValue, JSONError> {
+    let mut reader = Reader { data, pos: 0 };
+    let value = match reader.item("")? {
+        Item::Value(v) => v,
+        Item::Break => return Err(reader.error("Unexpected break")),
+    };
+    if reader.pos != data.len() {
+        return Err(reader.error("Trailing bytes after value"));
+    }
+    Ok(value)
+}
+
+enum Item {
+    Value(JSONValue),
+    // ends an indefinite-length string, array or map
+    Break,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    // positions are reported as byte offsets in the column
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(msg.to_string(), 0, self.pos)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JSONError> {
+        if self.data.len() - self.pos < n {
+            return Err(self.error("Unexpected end of input"));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, JSONError> {
+        Ok(self.take(n)?.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+    }
+
+    // the argument of a head byte; None for indefinite lengths
+    fn argument(&mut self, info: u8) -> Result<Option<u64>, JSONError> {
+        match info {
+            0..=23 => Ok(Some(info as u64)),
+            24..=27 => Ok(Some(self.uint(1 << (info - 24))?)),
+            31 => Ok(None),
+            _ => Err(self.error("Invalid additional information")),
+        }
+    }
+
+    fn value(&mut self, path: &str) -> Result<JSONValue, JSONError> {
+        match self.item(path)? {
+            Item::Value(v) => Ok(v),
+            Item::Break => Err(self.error("Unexpected break")),
+        }
+    }
+
+    fn text(&mut self, len: Option<u64>) -> Result<String, JSONError> {
+        match len {
+            Some(len) => {
+                let bytes = self.take(len as usize)?;
+                String::from_utf8(bytes.to_vec()).map_err(|_| self.error("Invalid UTF-8 in string"))
+            }
+            None => {
+                let mut s = String::new();
+                loop {
+                    let head = self.take(1)?[0];
+                    if head == 0xff {
+                        return Ok(s);
+                    }
+                    if head >> 5 != 3 || head & 0x1f == 31 {
+                        return Err(self.error("Invalid chunk in indefinite-length string"));
+                    }
+                    let len = self.argument(head & 0x1f)?;
+                    s.push_str(&self.text(len)?);
+                }
+            }
+        }
+    }
+
+    fn item(&mut self, path: &str) -> Result<Item, JSONError> {
+        let head = self.take(1)?[0];
+        let (major, info) = (head >> 5, head & 0x1f);
+        if major == 7 {
+            return self
+                .simple(info)
+                .map(|v| v.map_or(Item::Break, Item::Value));
+        }
+        let arg = self.argument(info)?;
+        let value = match (major, arg) {
+            (0, Some(n)) if n <= 1 << 53 => JSONValue::Number(n as f64),
+            (1, Some(n)) if n < 1 << 53 => JSONValue::Number(-1f64 - n as f64),
+            (0, _) | (1, _) => {
+                return Err(self.error("Integer cannot be represented exactly as a JSON number"))
+            }
+            (2, _) => return Err(self.error("Byte strings cannot be represented in JSON")),
+            (3, len) => JSONValue::String(self.text(len)?),
+            (4, len) => {
+                let mut vc = vec![];
+                for i in 0.. {
+                    if len == Some(i) {
+                        break;
+                    }
+                    match self.item(&format!("{}/{}", path, i))? {
+                        Item::Value(v) => vc.push(v),
+                        Item::Break if len.is_none() => break,
+                        Item::Break => return Err(self.error("Unexpected break")),
+                    }
+                }
+                JSONValue::Array(vc)
+            }
+            (5, len) => {
+                let mut hm = HashMap::new();
+                for i in 0.. {
+                    if len == Some(i) {
+                        break;
+                    }
+                    let key = match self.item(path)? {
+                        Item::Value(JSONValue::String(s)) => s,
+                        Item::Break if len.is_none() => break,
+                        Item::Value(other) => {
+                            return Err(self.error(&format!(
+                                "Map key of type {} cannot be represented in JSON",
+                                other.type_name()
+                            )))
+                        }
+                        Item::Break => return Err(self.error("Unexpected break")),
+                    };
+                    let value = self.value(&child(path, &key))?;
+                    hm.insert(key, value);
+                }
+                JSONValue::Object(hm)
+            }
+            _ => return Err(self.error("Tagged values cannot be represented in JSON")),
+        };
+        Ok(Item::Value(value))
+    }
+
+    // major type 7: simple values and floats; None is a break
+    fn simple(&mut self, info: u8) -> Result<Option<JSONValue>, JSONError> {
+        let value = match info {
+            20 => JSONValue::Boolean(false),
+            21 => JSONValue::Boolean(true),
+            22 => JSONValue::Null,
+            25 => JSONValue::Number(half(self.uint(2)? as u16)),
+            26 => JSONValue::Number(f32::from_bits(self.uint(4)? as u32) as f64),
+            27 => JSONValue::Number(f64::from_bits(self.uint(8)?)),
+            31 => return Ok(None),
+            23 => return Err(self.error("Undefined cannot be represented in JSON")),
+            _ => return Err(self.error("Simple values cannot be represented in JSON")),
+        };
+        Ok(Some(value))
+    }
+}
+
+// IEEE 754 half precision
+fn half(bits: u16) -> f64 {
+    let exp = (bits >> 10) & 0x1f;
+    let mant = (bits & 0x3ff) as f64;
+    let value = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mant / 1024.0) * 2f64.powi(exp as i32 - 15),
+    };
+    if bits & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn rfc8949_examples() {
+        let hex = |s: &str| -> Vec<u8> {
+            (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+                .collect()
+        };
+        let cases = [
+            ("00", json!(0)),
+            ("1864", json!(100)),
+            ("3903e7", json!(-1000)),
+            ("1b001fffffffffffff", json!(9007199254740991u64)),
+            ("f93c00", json!(1.0)),
+            ("f9c400", json!(-4.0)),
+            ("fa47c35000", json!(100000.0)),
+            ("fb3ff199999999999a", json!(1.1)),
+            ("f6", json!(null)),
+            ("6449455446", json!("IETF")),
+            ("8301820203820405", json!([1, [2, 3], [4, 5]])),
+            ("a26161016162820203", json!({ "a": 1, "b": [2, 3] })),
+            ("7f657374726561646d696e67ff", json!("streaming")),
+            ("9f018202039f0405ffff", json!([1, [2, 3], [4, 5]])),
+            ("bf61610161629f0203ffff", json!({ "a": 1, "b": [2, 3] })),
+        ];
+        for (data, value) in cases.iter() {
+            assert_eq!(&from_slice(&hex(data)).unwrap(), value, "{}", data);
+        }
+        for data in ["1864", "3903e7", "8301820203820405", "a26161016162820203"].iter() {
+            let value = from_slice(&hex(data)).unwrap();
+            assert_eq!(to_vec(&value).unwrap(), hex(data));
+        }
+
+        for bad in [
+            "4401020304",
+            "c074",
+            "f7",
+            "a10102",
+            "1bffffffffffffffff",
+            "82ff",
+            "8201",
+        ]
+        .iter()
+        {
+            assert!(from_slice(&hex(bad)).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use super::unsupported;
+use crate::serializer::format_number;
+use crate::value::{JSONError, JSONValue};
+
+// Writes an array of flat records as RFC 4180 CSV. The header is the sorted
+// union of the record keys; missing members and `null` become empty fields.
+// Strings that would read back as another type are quoted.
+pub fn to_string(value: &JSONValue) -> Result<String, JSONError> {
+    let records = match value {
+        JSONValue::Array(vc) => vc,
+        _ => return Err(unsupported("CSV needs an array of records", "")),
+    };
+    let mut header: Vec<&String> = vec![];
+    for (i, record) in records.iter().enumerate() {
+        let fields = match record {
+            JSONValue::Object(hm) => hm,
+            _ => {
+                return Err(unsupported(
+                    "CSV records must be objects",
+                    &format!("/{}", i),
+                ))
+            }
+        };
+        for (k, v) in fields {
+            if v.is_object() || v.is_array() {
+                let path = super::child(&format!("/{}", i), k);
+                return Err(unsupported("CSV fields must be scalars", &path));
+            }
+            if !header.contains(&k) {
+                header.push(k);
+            }
+        }
+    }
+    header.sort();
+
+    let mut out = String::new();
+    let row: Vec<String> = header.iter().map(|k| quote(k, false)).collect();
+    out.push_str(&row.join(","));
+    out.push_str("\r\n");
+    for record in records {
+        let row: Vec<String> = header
+            .iter()
+            .map(|k| match record.get(k) {
+                None | Some(JSONValue::Null) => "".to_string(),
+                Some(JSONValue::Boolean(b)) => b.to_string(),
+                Some(JSONValue::Number(n)) => format_number(*n),
+                Some(JSONValue::String(s)) => quote(s, true),
+                Some(_) => unreachable!(),
+            })
+            .collect();
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    Ok(out)
+}
+
+fn quote(s: &str, typed: bool) -> String {
+    let special = s.is_empty()
+        || s.contains([',', '"', '\n', '\r'])
+        || s.trim() != s
+        || (typed && !matches!(infer(s), JSONValue::String(_)));
+    if special {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// unquoted fields read as numbers, booleans or null (when empty)
+fn infer(field: &str) -> JSONValue {
+    match field {
+        "" => JSONValue::Null,
+        "true" => JSONValue::Boolean(true),
+        "false" => JSONValue::Boolean(false),
+        _ => match field.parse::<f64>() {
+            Ok(n)
+                if n.is_finite() && field.starts_with(|c: char| c == '-' || c.is_ascii_digit()) =>
+            {
+                JSONValue::Number(n)
+            }
+            _ => JSONValue::String(field.to_string()),
+        },
+    }
+}
+
+// Reads CSV with a header row into an array of objects. Quoted fields are
+// always strings.
+pub fn from_str(text: &str) -> Result<JSONValue, JSONError> {
+    let rows = parse_rows(text)?;
+    let mut rows = rows.into_iter();
+    let header: Vec<String> = match rows.next() {
+        Some(row) => row.into_iter().map(|(field, _)| field).collect(),
+        None => return Ok(JSONValue::Array(vec![])),
+    };
+    let mut records = vec![];
+    for (line, row) in rows.enumerate() {
+        if row.len() != header.len() {
+            return Err(JSONError::new(
+                format!("Expected {} fields but found {}", header.len(), row.len()),
+                line + 2,
+                0,
+            ));
+        }
+        let mut hm = HashMap::new();
+        for (k, (field, quoted)) in header.iter().zip(row) {
+            let value = if quoted {
+                JSONValue::String(field)
+            } else {
+                infer(&field)
+            };
+            hm.insert(k.clone(), value);
+        }
+        records.push(JSONValue::Object(hm));
+    }
+    Ok(JSONValue::Array(records))
+}
+
+// fields paired with whether they were quoted
+fn parse_rows(text: &str) -> Result<Vec<Vec<(String, bool)>>, JSONError> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let (mut line, mut col) = (1, 0);
+    let mut chars = text.chars().peekable();
+    loop {
+        let mut field = String::new();
+        let quoted = chars.peek() == Some(&'"');
+        if quoted {
+            chars.next();
+            col += 1;
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                        col += 2;
+                    }
+                    Some('"') => {
+                        col += 1;
+                        break;
+                    }
+                    Some(c) => {
+                        if c == '\n' {
+                            line += 1;
+                            col = 0;
+                        } else {
+                            col += 1;
+                        }
+                        field.push(c);
+                    }
+                    None => {
+                        return Err(JSONError::new(
+                            "Unterminated quoted field".to_string(),
+                            line,
+                            col,
+                        ))
+                    }
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == '\n' || c == '\r' {
+                    break;
+                }
+                if c == '"' {
+                    return Err(JSONError::new(
+                        "Unexpected `\"` in field".to_string(),
+                        line,
+                        col + 1,
+                    ));
+                }
+                field.push(c);
+                col += 1;
+                chars.next();
+            }
+        }
+        row.push((field, quoted));
+
+        match chars.next() {
+            Some(',') => col += 1,
+            Some('\r') | Some('\n') | None => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                // blank lines carry no record
+                if !(row.len() == 1 && row[0].0.is_empty() && !row[0].1) {
+                    rows.push(row);
+                }
+                row = vec![];
+                line += 1;
+                col = 0;
+                if chars.peek().is_none() {
+                    return Ok(rows);
+                }
+            }
+            Some(c) => {
+                return Err(JSONError::new(
+                    format!("Unexpected `{}` after quoted field", c),
+                    line,
+                    col + 1,
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn records() {
+        let v = json!([
+            { "name": "a, b", "qty": 2, "ok": true, "note": "say \"hi\"" },
+            { "name": "12", "qty": 1.5, "note": "" },
+            { "name": "multi\nline", "qty": null, "ok": false }
+        ]);
+        let text = to_string(&v).unwrap();
+        assert_eq!(
+            text,
+            "name,note,ok,qty\r\n\"a, b\",\"say \"\"hi\"\"\",true,2\r\n\"12\",\"\",,1.5\r\n\"multi\nline\",,false,\r\n"
+        );
+        let back = from_str(&text).unwrap();
+        assert_eq!(back[0], v[0]);
+        assert_eq!(back[1]["name"], json!("12"));
+        assert!(back[1]["ok"].is_null() && back[2]["note"].is_null());
+
+        assert_eq!(
+            from_str("a,b\n1,x\n\n-2.5,\n").unwrap(),
+            json!([{ "a": 1, "b": "x" }, { "a": -2.5, "b": null }])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = to_string(&json!([{ "a": 1 }, { "a": { "b": 1 } }])).unwrap_err();
+        assert_eq!(err.message(), "CSV fields must be scalars at `/1/a`");
+        assert!(to_string(&json!({ "a": 1 })).is_err());
+        assert!(to_string(&json!([1])).is_err());
+
+        let err = from_str("a,b\n1\n").unwrap_err();
+        assert_eq!(
+            (err.message(), err.line()),
+            ("Expected 2 fields but found 1", 2)
+        );
+        assert!(from_str("a\n\"open\n").is_err());
+        assert!(from_str("a\nx\"y\n").is_err());
+    }
+}
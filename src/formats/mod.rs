@@ -0,0 +1,199 @@
+pub mod cbor;
+pub mod csv;
+pub mod msgpack;
+pub mod toml;
+pub mod yaml;
+
+use crate::parser::Parser;
+use crate::pointer::escape_token;
+use crate::scanner::Dialect;
+use crate::serializer::FormatOptions;
+use crate::value::{JSONError, JSONValue};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Jsonc,
+    Json5,
+    Yaml,
+    Toml,
+    Csv,
+    MsgPack,
+    Cbor,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        let format = match name.to_ascii_lowercase().as_str() {
+            "json" => Format::Json,
+            "jsonc" => Format::Jsonc,
+            "json5" => Format::Json5,
+            "yaml" | "yml" => Format::Yaml,
+            "toml" => Format::Toml,
+            "csv" => Format::Csv,
+            "msgpack" | "mpk" => Format::MsgPack,
+            "cbor" => Format::Cbor,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    // guesses the format from a file extension
+    pub fn from_path(path: &str) -> Option<Format> {
+        Format::from_name(path.rsplit('.').next()?)
+    }
+
+    pub fn is_binary(self) -> bool {
+        self == Format::MsgPack || self == Format::Cbor
+    }
+
+    pub fn decode(self, data: &[u8]) -> Result<JSONValue, JSONError> {
+        if self.is_binary() {
+            return match self {
+                Format::MsgPack => msgpack::from_slice(data),
+                _ => cbor::from_slice(data),
+            };
+        }
+        let text = std::str::from_utf8(data)
+            .map_err(|e| JSONError::new(format!("Input is not valid UTF-8: {}", e), 0, 0))?;
+        match self {
+            Format::Json => Parser::from_string(text).parse(),
+            Format::Jsonc => Parser::from_string(text)
+                .with_dialect(Dialect::Jsonc)
+                .parse(),
+            Format::Json5 => Parser::from_string(text)
+                .with_dialect(Dialect::Json5)
+                .parse(),
+            Format::Yaml => yaml::from_str(text),
+            Format::Toml => toml::from_str(text),
+            _ => csv::from_str(text),
+        }
+    }
+
+    // fails rather than silently dropping what the format cannot hold
+    pub fn encode(self, value: &JSONValue) -> Result<Vec<u8>, JSONError> {
+        // keys are sorted like the other encoders do
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::new()
+        };
+        let text = match self {
+            Format::Json | Format::Jsonc => {
+                check_finite(value, "")?;
+                value.to_string_with(&opts) + "\n"
+            }
+            // JSON5 can write NaN and the infinities as they are
+            Format::Json5 => value.to_json5_string(&opts) + "\n",
+            Format::Yaml => yaml::to_string(value)?,
+            Format::Toml => toml::to_string(value)?,
+            Format::Csv => csv::to_string(value)?,
+            Format::MsgPack => return msgpack::to_vec(value),
+            Format::Cbor => return cbor::to_vec(value),
+        };
+        Ok(text.into_bytes())
+    }
+}
+
+// error for values a format has no representation for
+fn unsupported(what: &str, path: &str) -> JSONError {
+    let path = if path.is_empty() { "/" } else { path };
+    JSONError::new(format!("{} at `{}`", what, path), 0, 0)
+}
+
+fn child(path: &str, key: &str) -> String {
+    format!("{}/{}", path, escape_token(key))
+}
+
+// JSON would write NaN and the infinities as null
+fn check_finite(value: &JSONValue, path: &str) -> Result<(), JSONError> {
+    match value {
+        JSONValue::Number(n) if !n.is_finite() => Err(unsupported("Non-finite number", path)),
+        JSONValue::Array(vc) => vc
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, v)| check_finite(v, &format!("{}/{}", path, i))),
+        JSONValue::Object(hm) => hm
+            .iter()
+            .try_for_each(|(k, v)| check_finite(v, &child(path, k))),
+        _ => Ok(()),
+    }
+}
+
+// whole numbers within the range where f64 is exact
+fn as_integer(n: f64) -> Option<i64> {
+    if n.fract() == 0.0 && n.abs() <= 9_007_199_254_740_992f64 {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn roundtrips() {
+        let doc = json!({
+            "name": "erjson",
+            "version": 1.5,
+            "count": -3,
+            "ok": true,
+            "tags": ["a", "b c", ""],
+            "nested": { "deep": { "x": [1, [2, 3]] }, "empty": {} },
+            "items": [{ "id": 1, "q": "with \"quotes\"\nand lines" }, { "id": 2, "q": "é 😀" }]
+        });
+        for format in [
+            Format::Json,
+            Format::Json5,
+            Format::Yaml,
+            Format::Toml,
+            Format::MsgPack,
+            Format::Cbor,
+        ]
+        .iter()
+        {
+            let data = format.encode(&doc).unwrap();
+            assert_eq!(format.decode(&data).unwrap(), doc, "{:?}", format);
+        }
+
+        let mut with_null = doc.clone();
+        with_null["nested"]["none"] = JSONValue::Null;
+        for format in [
+            Format::Json,
+            Format::Json5,
+            Format::Yaml,
+            Format::MsgPack,
+            Format::Cbor,
+        ]
+        .iter()
+        {
+            let data = format.encode(&with_null).unwrap();
+            assert_eq!(format.decode(&data).unwrap(), with_null, "{:?}", format);
+        }
+        let err = Format::Toml.encode(&with_null).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "TOML cannot represent null at `/nested/none`"
+        );
+
+        let infinite = Format::Yaml.decode(b"limits:\n  - 1\n  - .inf\n").unwrap();
+        for format in [Format::Json, Format::Jsonc].iter() {
+            let err = format.encode(&infinite).unwrap_err();
+            assert_eq!(err.message(), "Non-finite number at `/limits/1`");
+        }
+        let data = Format::Json5.encode(&infinite).unwrap();
+        assert_eq!(Format::Json5.decode(&data).unwrap(), infinite);
+
+        let data = Format::Json.encode(&json!({ "b": 1, "a": { "d": 2, "c": 3 } }));
+        assert_eq!(
+            String::from_utf8(data.unwrap()).unwrap(),
+            "{\n  \"a\": {\n    \"c\": 3,\n    \"d\": 2\n  },\n  \"b\": 1\n}\n"
+        );
+
+        assert_eq!(Format::from_path("a/b.yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_name("CBOR"), Some(Format::Cbor));
+        assert_eq!(Format::from_name("xml"), None);
+    }
+}
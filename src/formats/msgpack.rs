@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use super::{as_integer, child};
+use crate::value::{JSONError, JSONValue};
+
+// Whole numbers are written as the smallest integer type, anything else as a
+// float64; map keys are written in sorted order.
+pub fn to_vec(value: &JSONValue) -> Result<Vec<u8>, JSONError> {
+    let mut out = vec![];
+    write_value(&mut out, value);
+    Ok(out)
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize, fix: (u8, usize), codes: [u8; 3]) {
+    if len < fix.1 {
+        out.push(fix.0 | len as u8);
+    } else if len <= 0xff && codes[0] != 0 {
+        out.extend_from_slice(&[codes[0], len as u8]);
+    } else if len <= 0xffff {
+        out.push(codes[1]);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(codes[2]);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, n: i64) {
+    match n {
+        0..=0x7f => out.push(n as u8),
+        -32..=-1 => out.push(n as i8 as u8),
+        0x80..=0xff => out.extend_from_slice(&[0xcc, n as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        -0x80..=-33 => out.extend_from_slice(&[0xd0, n as i8 as u8]),
+        -0x8000..=-0x81 => {
+            out.push(0xd1);
+            out.extend_from_slice(&(n as i16).to_be_bytes());
+        }
+        -0x8000_0000..=-0x8001 => {
+            out.push(0xd2);
+            out.extend_from_slice(&(n as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_len(out, s.len(), (0xa0, 32), [0xd9, 0xda, 0xdb]);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_value(out: &mut Vec<u8>, value: &JSONValue) {
+    match value {
+        JSONValue::Null => out.push(0xc0),
+        JSONValue::Boolean(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        JSONValue::Number(n) => match as_integer(*n) {
+            Some(i) => write_int(out, i),
+            None => {
+                out.push(0xcb);
+                out.extend_from_slice(&n.to_be_bytes());
+            }
+        },
+        JSONValue::String(s) => write_str(out, s),
+        JSONValue::Array(vc) => {
+            write_len(out, vc.len(), (0x90, 16), [0, 0xdc, 0xdd]);
+            for v in vc {
+                write_value(out, v);
+            }
+        }
+        JSONValue::Object(hm) => {
+            write_len(out, hm.len(), (0x80, 16), [0, 0xde, 0xdf]);
+            let mut keys: Vec<&String> = hm.keys().collect();
+            keys.sort();
+            for k in keys {
+                write_str(out, k);
+                write_value(out, &hm[k]);
+            }
+        }
+    }
+}
+
+pub fn from_slice(data: &[u8]) -> Result<JSONValue, JSONError> {
+    let mut reader = Reader { data, pos: 0 };
+    let value = reader.value("")?;
+    if reader.pos != data.len() {
+        return Err(reader.error("Trailing bytes after value"));
+    }
+    Ok(value)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    // positions are reported as byte offsets in the column
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(msg.to_string(), 0, self.pos)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JSONError> {
+        if self.data.len() - self.pos < n {
+            return Err(self.error("Unexpected end of input"));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, JSONError> {
+        Ok(self.take(n)?.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+    }
+
+    fn int(&mut self, n: usize) -> Result<i64, JSONError> {
+        let raw = self.uint(n)?;
+        let shift = 64 - 8 * n as u32;
+        Ok(((raw << shift) as i64) >> shift)
+    }
+
+    fn number(&self, n: f64, exact: bool) -> Result<JSONValue, JSONError> {
+        if !exact {
+            return Err(self.error("Integer cannot be represented exactly as a JSON number"));
+        }
+        Ok(JSONValue::Number(n))
+    }
+
+    fn str(&mut self, len: usize) -> Result<String, JSONError> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("Invalid UTF-8 in string"))
+    }
+
+    fn array(&mut self, len: usize, path: &str) -> Result<JSONValue, JSONError> {
+        let mut vc = Vec::with_capacity(len.min(1024));
+        for i in 0..len {
+            vc.push(self.value(&format!("{}/{}", path, i))?);
+        }
+        Ok(JSONValue::Array(vc))
+    }
+
+    fn map(&mut self, len: usize, path: &str) -> Result<JSONValue, JSONError> {
+        let mut hm = HashMap::new();
+        for _ in 0..len {
+            let key = match self.value(path)? {
+                JSONValue::String(s) => s,
+                other => {
+                    return Err(self.error(&format!(
+                        "Map key of type {} cannot be represented in JSON",
+                        other.type_name()
+                    )))
+                }
+            };
+            let value = self.value(&child(path, &key))?;
+            hm.insert(key, value);
+        }
+        Ok(JSONValue::Object(hm))
+    }
+
+    fn value(&mut self, path: &str) -> Result<JSONValue, JSONError> {
+        let b = self.take(1)?[0];
+        match b {
+            0x00..=0x7f => Ok(JSONValue::Number(b as f64)),
+            0x80..=0x8f => self.map((b & 0x0f) as usize, path),
+            0x90..=0x9f => self.array((b & 0x0f) as usize, path),
+            0xa0..=0xbf => Ok(JSONValue::String(self.str((b & 0x1f) as usize)?)),
+            0xc0 => Ok(JSONValue::Null),
+            0xc2 => Ok(JSONValue::Boolean(false)),
+            0xc3 => Ok(JSONValue::Boolean(true)),
+            0xca => {
+                let bits = self.uint(4)? as u32;
+                Ok(JSONValue::Number(f32::from_bits(bits) as f64))
+            }
+            0xcb => Ok(JSONValue::Number(f64::from_bits(self.uint(8)?))),
+            0xcc..=0xcf => {
+                let n = self.uint(1 << (b - 0xcc))?;
+                self.number(n as f64, n <= 1 << 53)
+            }
+            0xd0..=0xd3 => {
+                let n = self.int(1 << (b - 0xd0))?;
+                self.number(n as f64, n.unsigned_abs() <= 1 << 53)
+            }
+            0xd9..=0xdb => {
+                let len = self.uint(1 << (b - 0xd9))? as usize;
+                Ok(JSONValue::String(self.str(len)?))
+            }
+            0xdc | 0xdd => {
+                let len = self.uint(2 << (b - 0xdc))? as usize;
+                self.array(len, path)
+            }
+            0xde | 0xdf => {
+                let len = self.uint(2 << (b - 0xde))? as usize;
+                self.map(len, path)
+            }
+            0xe0..=0xff => Ok(JSONValue::Number(b as i8 as f64)),
+            0xc4..=0xc6 => Err(self.error("Binary data cannot be represented in JSON")),
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                Err(self.error("Extension types cannot be represented in JSON"))
+            }
+            _ => Err(self.error(&format!("Invalid type byte 0x{:02x}", b))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(
+            to_vec(&json!([1, -1, 200, -200, 1.5])).unwrap(),
+            vec![
+                0x95, 0x01, 0xff, 0xcc, 0xc8, 0xd1, 0xff, 0x38, 0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0
+            ]
+        );
+        assert_eq!(
+            to_vec(&json!({ "b": null, "a": true })).unwrap(),
+            vec![0x82, 0xa1, b'a', 0xc3, 0xa1, b'b', 0xc0]
+        );
+        let long = "x".repeat(40);
+        assert_eq!(&to_vec(&json!(long)).unwrap()[..2], &[0xd9, 40]);
+        for n in [65535f64, 65536f64, -32768f64, -2147483649f64, 4294967296f64].iter() {
+            let v = JSONValue::Number(*n);
+            assert_eq!(from_slice(&to_vec(&v).unwrap()).unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn decoding_errors() {
+        assert_eq!(
+            from_slice(&[0x81, 0x01, 0x02]).unwrap_err().message(),
+            "Map key of type number cannot be represented in JSON"
+        );
+        assert!(from_slice(&[0xc4, 0x01, 0x00]).is_err());
+        assert!(from_slice(&[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(from_slice(&[0x92, 0x01]).is_err());
+        assert!(from_slice(&[0x01, 0x02]).is_err());
+        assert_eq!(from_slice(&[0xca, 0x3f, 0xc0, 0, 0]).unwrap(), json!(1.5));
+    }
+}
@@ -0,0 +1,704 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{as_integer, child, unsupported};
+use crate::serializer::escape;
+use crate::value::{JSONError, JSONValue, Map};
+
+// Writes a TOML document. The root must be an object, and as TOML has no
+// null, any `null` is an error. Arrays made only of objects become arrays of
+// tables.
+pub fn to_string(value: &JSONValue) -> Result<String, JSONError> {
+    let hm = match value {
+        JSONValue::Object(hm) => hm,
+        _ => return Err(unsupported("TOML documents must be tables", "")),
+    };
+    let mut out = String::new();
+    write_table(&mut out, hm, &[], "", false)?;
+    Ok(out)
+}
+
+fn is_table_array(v: &JSONValue) -> bool {
+    match v {
+        JSONValue::Array(vc) => !vc.is_empty() && vc.iter().all(JSONValue::is_object),
+        _ => false,
+    }
+}
+
+fn key(k: &str) -> String {
+    let bare = !k.is_empty()
+        && k.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        k.to_string()
+    } else {
+        quote(k)
+    }
+}
+
+fn quote(s: &str) -> String {
+    escape(s).replace('\u{7f}', "\\u007f")
+}
+
+fn header(keys: &[&str]) -> String {
+    keys.iter().map(|k| key(k)).collect::<Vec<_>>().join(".")
+}
+
+// `item` marks an element of an array of tables
+fn write_table(
+    out: &mut String,
+    hm: &Map,
+    keys: &[&str],
+    path: &str,
+    item: bool,
+) -> Result<(), JSONError> {
+    let mut names: Vec<&String> = hm.keys().collect();
+    names.sort();
+    let (tables, plain): (Vec<&String>, Vec<&String>) = names
+        .into_iter()
+        .partition(|k| hm[*k].is_object() || is_table_array(&hm[*k]));
+
+    let (open, close) = if item { ("[[", "]]") } else { ("[", "]") };
+    if item || (!keys.is_empty() && (!plain.is_empty() || tables.is_empty())) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}{}{}\n", open, header(keys), close));
+    }
+    for k in plain.iter() {
+        let v = inline(&hm[*k], &child(path, k))?;
+        out.push_str(&format!("{} = {}\n", key(k), v));
+    }
+
+    for k in tables {
+        let mut sub: Vec<&str> = keys.to_vec();
+        sub.push(k);
+        let at = child(path, k);
+        match &hm[k] {
+            JSONValue::Object(members) => write_table(out, members, &sub, &at, false)?,
+            JSONValue::Array(vc) => {
+                for (i, item) in vc.iter().enumerate() {
+                    if let JSONValue::Object(members) = item {
+                        write_table(out, members, &sub, &format!("{}/{}", at, i), true)?;
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+fn inline(value: &JSONValue, path: &str) -> Result<String, JSONError> {
+    let text = match value {
+        JSONValue::Null => return Err(unsupported("TOML cannot represent null", path)),
+        JSONValue::Boolean(b) => b.to_string(),
+        JSONValue::Number(n) if n.is_nan() => "nan".to_string(),
+        JSONValue::Number(n) if n.is_infinite() => {
+            if *n > 0.0 { "inf" } else { "-inf" }.to_string()
+        }
+        JSONValue::Number(n) => match as_integer(*n) {
+            Some(i) => i.to_string(),
+            None => {
+                let s = n.to_string();
+                if s.contains('.') || s.contains('e') {
+                    s
+                } else {
+                    s + ".0"
+                }
+            }
+        },
+        JSONValue::String(s) => quote(s),
+        JSONValue::Array(vc) => {
+            let mut items = vec![];
+            for (i, v) in vc.iter().enumerate() {
+                items.push(inline(v, &format!("{}/{}", path, i))?);
+            }
+            format!("[{}]", items.join(", "))
+        }
+        JSONValue::Object(hm) if hm.is_empty() => "{}".to_string(),
+        JSONValue::Object(hm) => {
+            let mut names: Vec<&String> = hm.keys().collect();
+            names.sort();
+            let mut members = vec![];
+            for k in names {
+                members.push(format!("{} = {}", key(k), inline(&hm[k], &child(path, k))?));
+            }
+            format!("{{ {} }}", members.join(", "))
+        }
+    };
+    Ok(text)
+}
+
+// Reads a TOML document. Dates and times have no JSON counterpart and are
+// kept as strings; integers beyond 2^53 are an error.
+pub fn from_str(text: &str) -> Result<JSONValue, JSONError> {
+    Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+        col: 1,
+    }
+    .document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(msg.to_string(), self.line, self.col)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn looking_at(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JSONError> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("Expected `{}`", c)));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.next();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.next();
+            }
+        }
+    }
+
+    // whitespace, newlines and comments, as allowed inside arrays
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), JSONError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.next();
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            Some(_) => Err(self.error("Expected the end of the line")),
+        }
+    }
+
+    fn document(&mut self) -> Result<JSONValue, JSONError> {
+        let mut root = JSONValue::Object(HashMap::new());
+        let mut current: Vec<String> = vec![];
+        // explicit headers, a table may only be defined once
+        let mut defined: HashSet<Vec<String>> = HashSet::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') if self.peek_at(1) == Some('[') => {
+                    self.next();
+                    self.next();
+                    let keys = self.key()?;
+                    self.expect(']')?;
+                    self.expect(']')?;
+                    let (last, parents) = keys.split_last().unwrap();
+                    let table = self.table(&mut root, parents)?;
+                    let entry = table
+                        .entry(last.clone())
+                        .or_insert_with(|| JSONValue::Array(vec![]));
+                    match entry {
+                        JSONValue::Array(vc) if vc.iter().all(JSONValue::is_object) => {
+                            vc.push(JSONValue::Object(HashMap::new()))
+                        }
+                        _ => {
+                            return Err(
+                                self.error(&format!("Key `{}` is not an array of tables", last))
+                            )
+                        }
+                    }
+                    // the new element starts with none of its tables defined
+                    defined.retain(|d| !(d.len() > keys.len() && d.starts_with(&keys)));
+                    defined.insert(keys.clone());
+                    current = keys;
+                }
+                Some('[') => {
+                    self.next();
+                    let keys = self.key()?;
+                    self.expect(']')?;
+                    if !defined.insert(keys.clone()) {
+                        return Err(self.error(&format!("Duplicate table `{}`", keys.join("."))));
+                    }
+                    self.table(&mut root, &keys)?;
+                    current = keys;
+                }
+                Some(_) => {
+                    let keys = self.key()?;
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    let table = self.table(&mut root, &current)?;
+                    self.insert(table, &keys, value)?;
+                }
+            }
+            self.end_of_line()?;
+        }
+    }
+
+    // the table at `keys`, creating missing ones; arrays of tables resolve
+    // to their last element
+    fn table<'a>(
+        &self,
+        root: &'a mut JSONValue,
+        keys: &[String],
+    ) -> Result<&'a mut Map, JSONError> {
+        let mut target = root;
+        for k in keys {
+            target = match target {
+                JSONValue::Object(hm) => hm
+                    .entry(k.clone())
+                    .or_insert_with(|| JSONValue::Object(HashMap::new())),
+                _ => unreachable!(),
+            };
+            if let JSONValue::Array(vc) = target {
+                target = match vc.last_mut() {
+                    Some(last) if last.is_object() => last,
+                    _ => return Err(self.error(&format!("Key `{}` is not a table", k))),
+                };
+            }
+            if !target.is_object() {
+                return Err(self.error(&format!("Key `{}` is not a table", k)));
+            }
+        }
+        match target {
+            JSONValue::Object(hm) => Ok(hm),
+            _ => unreachable!(),
+        }
+    }
+
+    fn insert(&self, table: &mut Map, keys: &[String], value: JSONValue) -> Result<(), JSONError> {
+        let (last, parents) = keys.split_last().unwrap();
+        let mut parent = JSONValue::Object(std::mem::take(table));
+        let result = match self.table(&mut parent, parents) {
+            Ok(hm) if hm.contains_key(last) => {
+                Err(self.error(&format!("Duplicate key `{}`", last)))
+            }
+            Ok(hm) => {
+                hm.insert(last.clone(), value);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+        if let JSONValue::Object(hm) = parent {
+            *table = hm;
+        }
+        result
+    }
+
+    // a dotted key: `a."b.c".d`
+    fn key(&mut self) -> Result<Vec<String>, JSONError> {
+        let mut keys = vec![];
+        loop {
+            self.skip_spaces();
+            let k = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let mut k = String::new();
+                    while let Some(c) = self.peek() {
+                        if !(c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                            break;
+                        }
+                        k.push(c);
+                        self.next();
+                    }
+                    if k.is_empty() {
+                        return Err(self.error("Expected a key"));
+                    }
+                    k
+                }
+            };
+            keys.push(k);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(keys);
+            }
+            self.next();
+        }
+    }
+
+    fn value(&mut self) -> Result<JSONValue, JSONError> {
+        match self.peek() {
+            Some('"') => Ok(JSONValue::String(self.basic_string()?)),
+            Some('\'') => Ok(JSONValue::String(self.literal_string()?)),
+            Some('[') => {
+                self.next();
+                let mut vc = vec![];
+                loop {
+                    self.skip_blank();
+                    if self.peek() == Some(']') {
+                        self.next();
+                        return Ok(JSONValue::Array(vc));
+                    }
+                    vc.push(self.value()?);
+                    self.skip_blank();
+                    match self.peek() {
+                        Some(',') => {
+                            self.next();
+                        }
+                        Some(']') => (),
+                        _ => return Err(self.error("Expected `,` or `]`")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.next();
+                let mut table = JSONValue::Object(HashMap::new());
+                self.skip_spaces();
+                if self.peek() == Some('}') {
+                    self.next();
+                    return Ok(table);
+                }
+                loop {
+                    let keys = self.key()?;
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    if let JSONValue::Object(hm) = &mut table {
+                        self.insert(hm, &keys, value)?;
+                    }
+                    self.skip_spaces();
+                    match self.next() {
+                        Some(',') => (),
+                        Some('}') => return Ok(table),
+                        _ => return Err(self.error("Expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(_) => self.scalar(),
+            None => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn scalar(&mut self) -> Result<JSONValue, JSONError> {
+        let (line, col) = (self.line, self.col);
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            let date_time_space = c == ' '
+                && token.len() == 10
+                && token.as_bytes()[4] == b'-'
+                && self.peek_at(1).is_some_and(|c| c.is_ascii_digit());
+            if !(c.is_ascii_alphanumeric() || "+-_.:".contains(c) || date_time_space) {
+                break;
+            }
+            token.push(c);
+            self.next();
+        }
+        let err = |msg: String| JSONError::new(msg, line, col);
+
+        let unsigned = token.trim_start_matches(['+', '-']);
+        let value = match unsigned {
+            "true" if token == unsigned => JSONValue::Boolean(true),
+            "false" if token == unsigned => JSONValue::Boolean(false),
+            "inf" => JSONValue::Number(if token.starts_with('-') {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }),
+            "nan" => JSONValue::Number(f64::NAN),
+            _ if token.len() >= 8
+                && (token.as_bytes()[4] == b'-' || token.as_bytes()[2] == b':') =>
+            {
+                JSONValue::String(token.clone())
+            }
+            _ => {
+                let radix = match token.get(..2) {
+                    Some("0x") => 16,
+                    Some("0o") => 8,
+                    Some("0b") => 2,
+                    _ => 10,
+                };
+                let digits = if radix == 10 { &token[..] } else { &token[2..] };
+                if digits.is_empty()
+                    || digits.starts_with('_')
+                    || digits.ends_with('_')
+                    || digits.contains("__")
+                {
+                    return Err(err(format!("Invalid value `{}`", token)));
+                }
+                let digits = digits.replace('_', "");
+                let is_float = radix == 10 && digits.contains(['.', 'e', 'E']);
+                if is_float {
+                    match digits.parse::<f64>() {
+                        Ok(n) => JSONValue::Number(n),
+                        Err(_) => return Err(err(format!("Invalid value `{}`", token))),
+                    }
+                } else {
+                    match i64::from_str_radix(&digits, radix) {
+                        Ok(n) if n.unsigned_abs() <= 1 << 53 => JSONValue::Number(n as f64),
+                        Ok(_) => {
+                            return Err(err(format!(
+                                "Integer `{}` cannot be represented exactly as a JSON number",
+                                token
+                            )))
+                        }
+                        Err(_) => return Err(err(format!("Invalid value `{}`", token))),
+                    }
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    fn literal_string(&mut self) -> Result<String, JSONError> {
+        let multiline = self.looking_at("'''");
+        let quotes = if multiline { 3 } else { 1 };
+        for _ in 0..quotes {
+            self.next();
+        }
+        if multiline {
+            self.skip_newline();
+        }
+        let mut s = String::new();
+        loop {
+            if multiline && self.looking_at("'''") && !self.looking_at("''''") {
+                break;
+            }
+            match self.next() {
+                Some('\'') if !multiline => return Ok(s),
+                Some('\n') if !multiline => return Err(self.error("Unterminated string")),
+                Some(c) => s.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+        for _ in 0..3 {
+            self.next();
+        }
+        Ok(s)
+    }
+
+    fn skip_newline(&mut self) {
+        if self.looking_at("\r\n") {
+            self.next();
+        }
+        if self.peek() == Some('\n') {
+            self.next();
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, JSONError> {
+        let multiline = self.looking_at("\"\"\"");
+        let quotes = if multiline { 3 } else { 1 };
+        for _ in 0..quotes {
+            self.next();
+        }
+        if multiline {
+            self.skip_newline();
+        }
+        let mut s = String::new();
+        loop {
+            if multiline && self.looking_at("\"\"\"") && !self.looking_at("\"\"\"\"") {
+                break;
+            }
+            match self.next() {
+                Some('"') if !multiline => return Ok(s),
+                Some('\n') if !multiline => return Err(self.error("Unterminated string")),
+                Some('\\') => match self.next() {
+                    Some('b') => s.push('\u{8}'),
+                    Some('t') => s.push('\t'),
+                    Some('n') => s.push('\n'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('r') => s.push('\r'),
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some(u) if u == 'u' || u == 'U' => {
+                        let len = if u == 'u' { 4 } else { 8 };
+                        let hex: String = (0..len).filter_map(|_| self.next()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                            .ok_or_else(|| {
+                                self.error(&format!("Invalid escape `\\{}{}`", u, hex))
+                            })?;
+                        s.push(c);
+                    }
+                    // a line ending backslash trims the following whitespace
+                    Some(c) if multiline && c.is_whitespace() => {
+                        while self.peek().is_some_and(char::is_whitespace) {
+                            self.next();
+                        }
+                    }
+                    other => {
+                        return Err(
+                            self.error(&format!("Invalid escape `\\{}`", other.unwrap_or(' ')))
+                        )
+                    }
+                },
+                Some(c) => s.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+        for _ in 0..3 {
+            self.next();
+        }
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn writing() {
+        let v = json!({
+            "title": "TOML \"example\"",
+            "owner": { "name": "Tom", "dob": "1979-05-27" },
+            "database": { "ports": [8000, 8001], "limit": 1e20, "enabled": true, "nested": { "x": { "a": 1 } } },
+            "products": [{ "name": "Hammer" }, { "name": "Nail", "sizes": [{ "mm": 2 }, 3] }],
+            "odd key": 1.5
+        });
+        assert_eq!(
+            to_string(&v).unwrap(),
+            r#""odd key" = 1.5
+title = "TOML \"example\""
+
+[database]
+enabled = true
+limit = 100000000000000000000.0
+ports = [8000, 8001]
+
+[database.nested.x]
+a = 1
+
+[owner]
+dob = "1979-05-27"
+name = "Tom"
+
+[[products]]
+name = "Hammer"
+
+[[products]]
+name = "Nail"
+sizes = [{ mm = 2 }, 3]
+"#
+        );
+        assert!(to_string(&json!([1])).is_err());
+        let err = to_string(&json!({ "a": [1, null] })).unwrap_err();
+        assert_eq!(err.message(), "TOML cannot represent null at `/a/1`");
+    }
+
+    #[test]
+    fn reading() {
+        let text = r#"
+# a comment
+title = "TOML Example" # trailing
+"quoted key".dotted.key = 'C:\path'
+ints = [ 1_000, 0xff, 0o17, 0b101, -7, ]
+floats = [ 3.14, -1e3, +inf ]
+dates = [ 1979-05-27T07:32:00Z, 1979-05-27 07:32:00, 07:32:00 ]
+multi = """
+Roses \
+   are red\tand "blue" \u00e9"""
+raw = '''
+line 1
+line 2'''
+
+[servers.alpha]
+ip = "10.0.0.1"
+inline = { a = 1, b.c = [true, false] }
+
+[[fruit]]
+name = "apple"
+[fruit.physical]
+color = "red"
+[[fruit]]
+name = "banana"
+"#;
+        let v = from_str(text).unwrap();
+        assert_eq!(v["title"], json!("TOML Example"));
+        assert_eq!(v["quoted key"]["dotted"]["key"], json!("C:\\path"));
+        assert_eq!(v["ints"], json!([1000, 255, 15, 5, -7]));
+        assert_eq!(v["floats"][1], json!(-1000));
+        assert_eq!(v["floats"][2], JSONValue::Number(f64::INFINITY));
+        assert_eq!(
+            v["dates"],
+            json!(["1979-05-27T07:32:00Z", "1979-05-27 07:32:00", "07:32:00"])
+        );
+        assert_eq!(v["multi"], json!("Roses are red\tand \"blue\" é"));
+        assert_eq!(v["raw"], json!("line 1\nline 2"));
+        assert_eq!(
+            v["servers"]["alpha"]["inline"],
+            json!({ "a": 1, "b": { "c": [true, false] } })
+        );
+        assert_eq!(
+            v["fruit"],
+            json!([{ "name": "apple", "physical": { "color": "red" } }, { "name": "banana" }])
+        );
+
+        for bad in [
+            "a = ",
+            "a = 1\na = 2",
+            "a = 1 b = 2",
+            "a = \"open",
+            "a = 1\n[a]",
+            "x = 9007199254740993",
+            "a = 1__0",
+            "[a]\n[a]\n",
+            "[[a]]\n[a]\n",
+        ]
+        .iter()
+        {
+            assert!(from_str(bad).is_err(), "{}", bad);
+        }
+        let err = from_str("[a.b]\nx = 1\n[a]\ny = 2\n[a.b]\n").unwrap_err();
+        assert_eq!(err.message(), "Duplicate table `a.b`");
+        let err = from_str("a = 1\nb = [1 2]").unwrap_err();
+        assert_eq!((err.line(), err.col()), (2, 8));
+    }
+}
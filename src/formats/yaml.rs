@@ -0,0 +1,794 @@
+use std::collections::HashMap;
+
+use crate::serializer::escape;
+use crate::value::{JSONError, JSONValue};
+
+// Writes block-style YAML with sorted keys. Strings that would read back as
+// another type, or hold special characters, are double-quoted.
+pub fn to_string(value: &JSONValue) -> Result<String, JSONError> {
+    let mut out = String::new();
+    write_node(&mut out, value, 0);
+    Ok(out)
+}
+
+fn scalar(value: &JSONValue) -> String {
+    match value {
+        JSONValue::Null => "null".to_string(),
+        JSONValue::Boolean(b) => b.to_string(),
+        JSONValue::Number(n) if n.is_nan() => ".nan".to_string(),
+        JSONValue::Number(n) if n.is_infinite() => {
+            if *n > 0.0 { ".inf" } else { "-.inf" }.to_string()
+        }
+        JSONValue::Number(n) => n.to_string(),
+        JSONValue::String(s) => string(s),
+        JSONValue::Object(_) => "{}".to_string(),
+        JSONValue::Array(_) => "[]".to_string(),
+    }
+}
+
+fn string(s: &str) -> String {
+    let plain = !s.is_empty()
+        && s.trim() == s
+        && !s.starts_with(|c| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !s.ends_with(':')
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.contains(|c: char| c.is_control())
+        && matches!(resolve(s), JSONValue::String(_));
+    if plain {
+        s.to_string()
+    } else {
+        escape(s)
+    }
+}
+
+fn is_block(value: &JSONValue) -> bool {
+    match value {
+        JSONValue::Object(hm) => !hm.is_empty(),
+        JSONValue::Array(vc) => !vc.is_empty(),
+        _ => false,
+    }
+}
+
+fn write_node(out: &mut String, value: &JSONValue, indent: usize) {
+    let pad = " ".repeat(indent);
+    match value {
+        JSONValue::Object(hm) if !hm.is_empty() => {
+            let mut keys: Vec<&String> = hm.keys().collect();
+            keys.sort();
+            for k in keys {
+                let v = &hm[k];
+                if is_block(v) {
+                    out.push_str(&format!("{}{}:\n", pad, string(k)));
+                    write_node(out, v, indent + 2);
+                } else {
+                    out.push_str(&format!("{}{}: {}\n", pad, string(k), scalar(v)));
+                }
+            }
+        }
+        JSONValue::Array(vc) if !vc.is_empty() => {
+            for v in vc {
+                if is_block(v) {
+                    // the first line of the item follows the dash
+                    let mut item = String::new();
+                    write_node(&mut item, v, indent + 2);
+                    out.push_str(&format!("{}- {}", pad, &item[indent + 2..]));
+                } else {
+                    out.push_str(&format!("{}- {}\n", pad, scalar(v)));
+                }
+            }
+        }
+        v => out.push_str(&format!("{}{}\n", pad, scalar(v))),
+    }
+}
+
+// YAML 1.2 core schema resolution of plain scalars
+fn resolve(s: &str) -> JSONValue {
+    match s {
+        "" | "~" | "null" | "Null" | "NULL" => return JSONValue::Null,
+        "true" | "True" | "TRUE" => return JSONValue::Boolean(true),
+        "false" | "False" | "FALSE" => return JSONValue::Boolean(false),
+        ".nan" | ".NaN" | ".NAN" => return JSONValue::Number(f64::NAN),
+        _ => (),
+    }
+    let unsigned = s.trim_start_matches(['+', '-']);
+    let negative = s.starts_with('-');
+    if s.len() - unsigned.len() <= 1 {
+        if let ".inf" | ".Inf" | ".INF" = unsigned {
+            return JSONValue::Number(if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            });
+        }
+        if s.len() == unsigned.len() {
+            let radix = match s.get(..2) {
+                Some("0x") => 16,
+                Some("0o") => 8,
+                _ => 0,
+            };
+            if radix != 0 {
+                if let Ok(n) = i64::from_str_radix(&s[2..], radix) {
+                    return JSONValue::Number(n as f64);
+                }
+            }
+        }
+        let numeric = !unsigned.is_empty()
+            && unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && unsigned
+                .chars()
+                .all(|c| c.is_ascii_digit() || ".eE+-".contains(c))
+            && unsigned.chars().any(|c| c.is_ascii_digit());
+        if numeric {
+            if let Ok(n) = s.parse::<f64>() {
+                return JSONValue::Number(n);
+            }
+        }
+    }
+    JSONValue::String(s.to_string())
+}
+
+// Reads a single YAML document in block or flow style. Anchors, aliases,
+// tags and multi-line plain scalars are not supported.
+pub fn from_str(text: &str) -> Result<JSONValue, JSONError> {
+    let mut parser = Parser {
+        lines: text.lines().collect(),
+        i: 0,
+        pending: None,
+        started: false,
+    };
+    let value = parser.block(0)?;
+    if let Some(line) = parser.peek()? {
+        return Err(line.error("Unexpected content"));
+    }
+    Ok(value)
+}
+
+struct Line {
+    indent: usize,
+    text: String,
+    number: usize,
+}
+
+impl Line {
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(msg.to_string(), self.number, self.indent + 1)
+    }
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// strips a trailing ` # comment` from a plain scalar
+fn uncomment(text: &str) -> &str {
+    match text.find(" #") {
+        Some(i) => text[..i].trim_end(),
+        None if text.starts_with('#') => "",
+        None => text.trim_end(),
+    }
+}
+
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    i: usize,
+    // what follows the dash of a sequence item, read as its own line
+    pending: Option<Line>,
+    started: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Result<Option<Line>, JSONError> {
+        if let Some(line) = &self.pending {
+            return Ok(Some(Line {
+                indent: line.indent,
+                text: line.text.clone(),
+                number: line.number,
+            }));
+        }
+        while let Some(raw) = self.lines.get(self.i) {
+            let text = raw.trim_start_matches(' ');
+            let number = self.i + 1;
+            if text.trim().is_empty() || text.starts_with('#') || raw.starts_with("%") {
+                self.i += 1;
+                continue;
+            }
+            if raw.starts_with("---") && raw[3..].trim().is_empty() {
+                if self.started {
+                    return Err(JSONError::new(
+                        "Multiple documents are not supported".to_string(),
+                        number,
+                        1,
+                    ));
+                }
+                self.started = true;
+                self.i += 1;
+                continue;
+            }
+            if raw.starts_with("...") && raw[3..].trim().is_empty() {
+                self.i = self.lines.len();
+                break;
+            }
+            if text.starts_with('\t') {
+                return Err(JSONError::new(
+                    "Tabs are not allowed in indentation".to_string(),
+                    number,
+                    1,
+                ));
+            }
+            self.started = true;
+            return Ok(Some(Line {
+                indent: raw.len() - text.len(),
+                text: text.trim_end().to_string(),
+                number,
+            }));
+        }
+        Ok(None)
+    }
+
+    fn advance(&mut self) {
+        if self.pending.take().is_none() {
+            self.i += 1;
+        }
+    }
+
+    // a node indented by at least `min`; missing nodes are null
+    fn block(&mut self, min: usize) -> Result<JSONValue, JSONError> {
+        let line = match self.peek()? {
+            Some(line) if line.indent >= min => line,
+            _ => return Ok(JSONValue::Null),
+        };
+        if is_item(&line.text) {
+            return self.sequence(line.indent);
+        }
+        if split_key(&line.text, line.number)?.is_some() {
+            return self.mapping(line.indent);
+        }
+        self.advance();
+        self.inline(&line.text, &line)
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<JSONValue, JSONError> {
+        let mut vc = vec![];
+        while let Some(line) = self.peek()? {
+            if line.indent != indent || !is_item(&line.text) {
+                if line.indent > indent {
+                    return Err(line.error("Bad indentation of a sequence entry"));
+                }
+                break;
+            }
+            self.advance();
+            let rest = line.text[1..].trim_start();
+            if !uncomment(rest).is_empty() {
+                self.pending = Some(Line {
+                    indent: indent + line.text.len() - rest.len(),
+                    text: rest.to_string(),
+                    number: line.number,
+                });
+            }
+            vc.push(self.block(indent + 1)?);
+        }
+        Ok(JSONValue::Array(vc))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<JSONValue, JSONError> {
+        let mut hm = HashMap::new();
+        while let Some(line) = self.peek()? {
+            if line.indent != indent || is_item(&line.text) {
+                if line.indent > indent {
+                    return Err(line.error("Bad indentation of a mapping entry"));
+                }
+                break;
+            }
+            let (key, rest) = match split_key(&line.text, line.number)? {
+                Some(entry) => entry,
+                None => return Err(line.error("Expected a mapping entry")),
+            };
+            self.advance();
+            if hm.contains_key(&key) {
+                return Err(line.error(&format!("Duplicate key `{}`", key)));
+            }
+            let value = match uncomment(&rest) {
+                "" => match self.peek()? {
+                    // sequences may sit at the same indentation as their key
+                    Some(next) if next.indent == indent && is_item(&next.text) => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.block(indent + 1)?,
+                },
+                r if r.starts_with('|') || r.starts_with('>') => {
+                    self.block_scalar(r, indent, &line)?
+                }
+                _ => self.inline(&rest, &line)?,
+            };
+            hm.insert(key, value);
+        }
+        Ok(JSONValue::Object(hm))
+    }
+
+    // `|` keeps line breaks, `>` folds them; `-` strips the final newline
+    // and `+` keeps trailing blank lines
+    fn block_scalar(
+        &mut self,
+        header: &str,
+        indent: usize,
+        line: &Line,
+    ) -> Result<JSONValue, JSONError> {
+        let literal = header.starts_with('|');
+        let mut chomp = ' ';
+        let mut explicit = None;
+        for c in header[1..].chars() {
+            match c {
+                '+' | '-' => chomp = c,
+                '1'..='9' => explicit = c.to_digit(10).map(|d| indent + d as usize),
+                _ => return Err(line.error("Invalid block scalar header")),
+            }
+        }
+
+        let mut lines = vec![];
+        let mut content = explicit;
+        while let Some(raw) = self.lines.get(self.i) {
+            let text = raw.trim_start_matches(' ');
+            let spaces = raw.len() - text.len();
+            if text.is_empty() {
+                lines.push("");
+                self.i += 1;
+                continue;
+            }
+            let at = *content.get_or_insert(spaces);
+            if spaces < at || spaces <= indent {
+                break;
+            }
+            lines.push(&raw[at..]);
+            self.i += 1;
+        }
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        let body = &lines[..lines.len() - trailing];
+
+        let mut out = String::new();
+        let mut blanks = 0;
+        for (n, l) in body.iter().enumerate() {
+            if l.is_empty() {
+                blanks += 1;
+                continue;
+            }
+            if n > 0 {
+                let folds =
+                    !literal && !l.starts_with(' ') && !body[n - 1 - blanks].starts_with(' ');
+                if folds && blanks == 0 {
+                    out.push(' ');
+                } else {
+                    let breaks = if folds { blanks } else { blanks + 1 };
+                    out.push_str(&"\n".repeat(breaks));
+                }
+            }
+            blanks = 0;
+            out.push_str(l);
+        }
+        match chomp {
+            '-' => (),
+            '+' => out.push_str(&"\n".repeat(trailing + 1)),
+            _ if !out.is_empty() => out.push('\n'),
+            _ => (),
+        }
+        Ok(JSONValue::String(out))
+    }
+
+    // a scalar or flow collection; flow collections may span lines
+    fn inline(&mut self, text: &str, line: &Line) -> Result<JSONValue, JSONError> {
+        let mut text = text.trim().to_string();
+        if text.starts_with('[') || text.starts_with('{') {
+            while depth(&text) > 0 {
+                match self.lines.get(self.i) {
+                    Some(more) => {
+                        text.push(' ');
+                        text.push_str(more.trim());
+                        self.i += 1;
+                    }
+                    None => return Err(line.error("Unterminated flow collection")),
+                }
+            }
+        }
+        let mut flow = Flow {
+            chars: text.chars().collect(),
+            pos: 0,
+            line,
+        };
+        let value = flow.value(false)?;
+        flow.skip_spaces();
+        if !uncomment(&flow.rest()).is_empty() {
+            return Err(line.error("Unexpected content after value"));
+        }
+        Ok(value)
+    }
+}
+
+// open brackets outside of quotes
+fn depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') | (None, '{') => depth += 1,
+            (None, ']') | (None, '}') => depth -= 1,
+            (None, '#') => break,
+            _ => (),
+        }
+    }
+    depth
+}
+
+// splits `key: rest` when the line starts with a mapping key
+fn split_key(text: &str, number: usize) -> Result<Option<(String, String)>, JSONError> {
+    if text.starts_with('[') || text.starts_with('{') || text.starts_with('#') {
+        return Ok(None);
+    }
+    let line = Line {
+        indent: 0,
+        text: text.to_string(),
+        number,
+    };
+    let (key, rest) = if text.starts_with('"') || text.starts_with('\'') {
+        let mut flow = Flow {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: &line,
+        };
+        let key = match flow.value(false)? {
+            JSONValue::String(s) => s,
+            _ => unreachable!(),
+        };
+        flow.skip_spaces();
+        let rest = flow.rest();
+        match rest.strip_prefix(':') {
+            Some(r) if r.is_empty() || r.starts_with(' ') => (key, r.to_string()),
+            _ => return Ok(None),
+        }
+    } else {
+        let text = uncomment(text);
+        let end = text.find(": ").or_else(|| {
+            if text.ends_with(':') {
+                Some(text.len() - 1)
+            } else {
+                None
+            }
+        });
+        match end {
+            Some(i) => (text[..i].trim_end().to_string(), text[i + 1..].to_string()),
+            None => return Ok(None),
+        }
+    };
+    Ok(Some((key, rest.trim().to_string())))
+}
+
+struct Flow<'l> {
+    chars: Vec<char>,
+    pos: usize,
+    line: &'l Line,
+}
+
+impl<'l> Flow<'l> {
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(
+            msg.to_string(),
+            self.line.number,
+            self.line.indent + self.pos + 1,
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn rest(&self) -> String {
+        self.chars[self.pos..].iter().collect()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JSONError> {
+        self.skip_spaces();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("Expected `{}`", c)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // `in_flow` marks values inside brackets, where `,` ends plain scalars
+    fn value(&mut self, in_flow: bool) -> Result<JSONValue, JSONError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut vc = vec![];
+                loop {
+                    self.skip_spaces();
+                    if self.peek() == Some(']') {
+                        self.pos += 1;
+                        return Ok(JSONValue::Array(vc));
+                    }
+                    vc.push(self.value(true)?);
+                    self.skip_spaces();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => (),
+                        _ => return Err(self.error("Expected `,` or `]`")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut hm = HashMap::new();
+                loop {
+                    self.skip_spaces();
+                    if self.peek() == Some('}') {
+                        self.pos += 1;
+                        return Ok(JSONValue::Object(hm));
+                    }
+                    let key = match self.value(true)? {
+                        JSONValue::String(s) => s,
+                        JSONValue::Null => "".to_string(),
+                        other => other.to_string(),
+                    };
+                    self.expect(':')?;
+                    self.skip_spaces();
+                    let value = match self.peek() {
+                        Some(',') | Some('}') => JSONValue::Null,
+                        _ => self.value(true)?,
+                    };
+                    if hm.insert(key.clone(), value).is_some() {
+                        return Err(self.error(&format!("Duplicate key `{}`", key)));
+                    }
+                    self.skip_spaces();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some('}') => (),
+                        _ => return Err(self.error("Expected `,` or `}`")),
+                    }
+                }
+            }
+            Some('"') => self.double_quoted().map(JSONValue::String),
+            Some('\'') => self.single_quoted().map(JSONValue::String),
+            Some(c) if "&*!".contains(c) => {
+                Err(self.error("Anchors, aliases and tags are not supported"))
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    let next = self.chars.get(self.pos + 1).copied();
+                    let ends = match c {
+                        ',' | '[' | ']' | '{' | '}' => in_flow,
+                        ':' => {
+                            next.is_none() || next == Some(' ') || (in_flow && next == Some(','))
+                        }
+                        '#' => self.pos > start && self.chars[self.pos - 1] == ' ',
+                        _ => false,
+                    };
+                    if ends {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                Ok(resolve(text.trim()))
+            }
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<String, JSONError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') if self.chars.get(self.pos + 1) == Some(&'\'') => {
+                    s.push('\'');
+                    self.pos += 2;
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+    }
+
+    fn hex(&mut self, len: usize) -> Result<u32, JSONError> {
+        let end = (self.pos + len).min(self.chars.len());
+        let digits: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        match u32::from_str_radix(&digits, 16) {
+            Ok(n) if digits.len() == len => Ok(n),
+            _ => Err(self.error(&format!("Invalid escape `{}`", digits))),
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, JSONError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("Unterminated string")),
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let e = self
+                        .peek()
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    self.pos += 1;
+                    let decoded = match e {
+                        '0' => '\0',
+                        'a' => '\u{7}',
+                        'b' => '\u{8}',
+                        't' | '\t' => '\t',
+                        'n' => '\n',
+                        'v' => '\u{b}',
+                        'f' => '\u{c}',
+                        'r' => '\r',
+                        'e' => '\u{1b}',
+                        ' ' | '"' | '/' | '\\' => e,
+                        'N' => '\u{85}',
+                        '_' => '\u{a0}',
+                        'L' => '\u{2028}',
+                        'P' => '\u{2029}',
+                        'x' | 'u' | 'U' => {
+                            let len = match e {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let mut code = self.hex(len)?;
+                            // JSON-style surrogate pairs
+                            if (0xD800..0xDC00).contains(&code) && self.rest().starts_with("\\u") {
+                                self.pos += 2;
+                                let low = self.hex(4)?;
+                                code = 0x10000
+                                    + ((code - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3ff);
+                            }
+                            std::char::from_u32(code)
+                                .ok_or_else(|| self.error("Invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error(&format!("Invalid escape `\\{}`", e))),
+                    };
+                    s.push(decoded);
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn writing() {
+        let v = json!({
+            "name": "erjson",
+            "tags": ["a", "true", "", "x: y"],
+            "matrix": [[1, 2], [], { "k": null }],
+            "nested": { "list": [{ "a": 1, "b": 2.5 }], "empty": {} },
+            "-odd": "line\nbreak"
+        });
+        assert_eq!(
+            to_string(&v).unwrap(),
+            r#""-odd": "line\nbreak"
+matrix:
+  - - 1
+    - 2
+  - []
+  - k: null
+name: erjson
+nested:
+  empty: {}
+  list:
+    - a: 1
+      b: 2.5
+tags:
+  - a
+  - "true"
+  - ""
+  - "x: y"
+"#
+        );
+        assert_eq!(to_string(&json!("plain")).unwrap(), "plain\n");
+    }
+
+    #[test]
+    fn reading() {
+        let text = r#"
+%YAML 1.2
+---
+# config
+name: erjson   # trailing comment
+version: 1.5
+count: 0x1f
+enabled: yes
+missing: ~
+"quoted key": 'it''s'
+escaped: "tab\there \u00e9 \x41"
+url: http://example.com/a#frag
+servers:
+- host: alpha
+  ports: [80, 443]
+- host: beta
+  tags: { env: prod, "tier": 2 }
+matrix:
+  - - 1
+    - 2
+  -
+    - 3
+empty:
+literal: |
+  line 1
+   indented
+
+  line 3
+folded: >-
+  folded
+  text
+
+  next
+flow: [a, {b: c},
+  d]
+..."#;
+        let v = from_str(text).unwrap();
+        assert_eq!(v["name"], json!("erjson"));
+        assert_eq!(v["version"], json!(1.5));
+        assert_eq!(v["count"], json!(31));
+        assert_eq!(v["enabled"], json!("yes"));
+        assert!(v["missing"].is_null() && v["empty"].is_null() && v.has_key("empty"));
+        assert_eq!(v["quoted key"], json!("it's"));
+        assert_eq!(v["escaped"], json!("tab\there é A"));
+        assert_eq!(v["url"], json!("http://example.com/a#frag"));
+        assert_eq!(
+            v["servers"],
+            json!([
+                { "host": "alpha", "ports": [80, 443] },
+                { "host": "beta", "tags": { "env": "prod", "tier": 2 } }
+            ])
+        );
+        assert_eq!(v["matrix"], json!([[1, 2], [3]]));
+        assert_eq!(v["literal"], json!("line 1\n indented\n\nline 3\n"));
+        assert_eq!(v["folded"], json!("folded text\nnext"));
+        assert_eq!(v["flow"], json!(["a", { "b": "c" }, "d"]));
+
+        assert_eq!(
+            from_str("- 1\n- -2.5e3\n- .inf\n- '1'").unwrap()[1],
+            json!(-2500)
+        );
+        for bad in [
+            "a: 1\na: 2",
+            "a: *ref",
+            "a: [1, 2",
+            "a: 1\n  b: 2",
+            "- a\nb: 1",
+            "a: \"open",
+            "---\na: 1\n---\nb: 2",
+        ]
+        .iter()
+        {
+            assert!(from_str(bad).is_err(), "{}", bad);
+        }
+        let err = from_str("a:\n  b: 1\n   c: 2").unwrap_err();
+        assert_eq!(err.line(), 3);
+    }
+}
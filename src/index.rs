@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use crate::value::JSONValue;
+
+// returned by reads of missing keys and out of range indices
+static NULL: JSONValue = JSONValue::Null;
+
+impl Index<&str> for JSONValue {
+    type Output = JSONValue;
+
+    fn index(&self, key: &str) -> &JSONValue {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+impl Index<usize> for JSONValue {
+    type Output = JSONValue;
+
+    fn index(&self, i: usize) -> &JSONValue {
+        self.as_array().and_then(|vc| vc.get(i)).unwrap_or(&NULL)
+    }
+}
+
+// Writing to a key turns `null` into an object and inserts missing members
+// as `null`; any other type panics, as there is nothing sensible to return.
+impl IndexMut<&str> for JSONValue {
+    fn index_mut(&mut self, key: &str) -> &mut JSONValue {
+        if self.is_null() {
+            *self = JSONValue::Object(HashMap::new());
+        }
+        match self {
+            JSONValue::Object(hm) => hm.entry(key.to_string()).or_insert(JSONValue::Null),
+            v => panic!("cannot index into {} with key `{}`", v.type_name(), key),
+        }
+    }
+}
+
+impl IndexMut<usize> for JSONValue {
+    fn index_mut(&mut self, i: usize) -> &mut JSONValue {
+        match self {
+            JSONValue::Array(vc) => {
+                let len = vc.len();
+                vc.get_mut(i).unwrap_or_else(|| {
+                    panic!("index {} out of bounds for array of length {}", i, len)
+                })
+            }
+            v => panic!("cannot index into {} with {}", v.type_name(), i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json;
+
+    #[test]
+    fn read() {
+        let v = json!({ "props": { "weight": 76 }, "primes": [11, 13, 17] });
+        assert_eq!(v["props"]["weight"], json!(76));
+        assert_eq!(v["primes"][2], json!(17));
+        assert!(v["primes"][3].is_null());
+        assert!(v["missing"]["deeper"][0].is_null());
+        assert!(v[0].is_null());
+    }
+
+    #[test]
+    fn write() {
+        let mut v = json!({ "age": 43, "primes": [11, 13] });
+        v["age"] = json!(45);
+        v["primes"][1] = json!(19);
+        v["props"]["weight"] = json!(76);
+        assert_eq!(
+            v,
+            json!({ "age": 45, "primes": [11, 19], "props": { "weight": 76 } })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into number with key `x`")]
+    fn write_into_scalar() {
+        let mut v = json!({ "age": 43 });
+        v["age"]["x"] = json!(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds")]
+    fn write_out_of_bounds() {
+        let mut v = json!([1]);
+        v[2] = json!(1);
+    }
+}
@@ -1,27 +1,84 @@
 mod buffer;
+mod convert;
+mod cst;
+#[cfg(feature = "serde")]
+mod de;
+mod diff;
+mod filter;
+pub mod formats;
+mod index;
+mod macros;
+mod merge;
+mod merge_patch;
 mod parser;
+pub mod patch;
+mod path;
+mod pointer;
 mod scanner;
+mod schema;
+#[cfg(feature = "serde")]
+mod ser;
+mod serializer;
+mod source_map;
+mod traits;
 mod value;
-
+mod visit;
+
+pub use cst::CSTDocument;
+#[cfg(feature = "serde")]
+pub use de::{from_reader, from_str, from_value};
+pub use diff::{render_diff, ArrayStrategy, Change, ChangeKind, DiffOptions};
+pub use filter::JSONFilter;
+pub use merge::{deep_merge, MergeArrays, MergeConflicts, MergeError, MergeNulls, MergeStrategy};
+pub use merge_patch::{merge_patch, merge_patch_diff};
+pub use parser::Comments;
 use parser::Parser;
+pub use patch::{apply_patch, PatchError};
+pub use path::{parse_path, PathSegment};
+pub use scanner::Dialect;
+pub use schema::{infer_schema, InferOptions, JSONSchema, SchemaInferrer, ValidationError};
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_writer};
+pub use serializer::FormatOptions;
+pub use source_map::{Position, SourceMap, Span};
 use std::fs::File;
-pub use value::{JSONError, JSONValue};
+pub use traits::{ConversionError, FromJSON, ToJSON};
+pub use value::{JSONError, JSONValue, Map};
+pub use visit::{Visitor, VisitorMut};
+
+#[cfg(feature = "derive")]
+pub use erjson_derive::{FromJSON, ToJSON};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct JSONDocument {
     pub value: Option<JSONValue>,
+    // comments from JSONC or JSON5 input, see `JSONValue::to_jsonc_string`
+    pub comments: Comments,
+    dialect: Dialect,
 }
 
 impl JSONDocument {
     pub fn new() -> JSONDocument {
-        JSONDocument { value: None }
+        JSONDocument {
+            value: None,
+            comments: Comments::default(),
+            dialect: Dialect::Json,
+        }
+    }
+
+    // accept a relaxed dialect such as JSONC or JSON5 in `parse_string` and
+    // `parse_file`
+    pub fn with_dialect(mut self, dialect: Dialect) -> JSONDocument {
+        self.dialect = dialect;
+        self
     }
 
     pub fn parse_string(&mut self, content: String) -> Result<JSONValue, JSONError> {
-        let mut parser = Parser::from_string(&content);
-        match parser.parse() {
-            Ok(p) => {
+        let mut parser = Parser::from_string(&content).with_dialect(self.dialect);
+        match parser.parse_with_comments() {
+            Ok((p, comments)) => {
                 self.value = Some(p.clone());
+                self.comments = comments;
                 Ok(p)
             }
             Err(e) => Err(e),
@@ -29,16 +86,33 @@ impl JSONDocument {
     }
 
     pub fn parse_file(&mut self, file: File) -> Result<JSONValue, JSONError> {
-        let mut parser = Parser::from_file(file);
-        match parser.parse() {
-            Ok(p) => {
+        let mut parser = Parser::from_file(file).with_dialect(self.dialect);
+        match parser.parse_with_comments() {
+            Ok((p, comments)) => {
                 self.value = Some(p.clone());
+                self.comments = comments;
                 Ok(p)
             }
             Err(e) => Err(e),
         }
     }
 
+    // keeps going after errors, returning every error along with the value
+    // that could be read; see `Parser::parse_recovering`
+    pub fn parse_string_recovering(&mut self, content: String) -> (JSONValue, Vec<JSONError>) {
+        let mut parser = Parser::from_string(&content).with_dialect(self.dialect);
+        let (value, errors) = parser.parse_recovering();
+        self.value = Some(value.clone());
+        (value, errors)
+    }
+
+    pub fn parse_file_recovering(&mut self, file: File) -> (JSONValue, Vec<JSONError>) {
+        let mut parser = Parser::from_file(file).with_dialect(self.dialect);
+        let (value, errors) = parser.parse_recovering();
+        self.value = Some(value.clone());
+        (value, errors)
+    }
+
     pub fn to_string(&mut self) -> Result<String, JSONError> {
         match &self.value {
             Some(v) => Ok(v.to_string()),
@@ -47,17 +121,17 @@ impl JSONDocument {
     }
 
     pub fn pretify(&mut self) -> String {
-        "null".to_string()
-        // match &self.value {
-        //     Some(value) => value.to_pretty_string(),
-        //     None => String::from("null")
-        // }
+        match &self.value {
+            Some(value) => value.to_pretty_string(),
+            None => String::from("null"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::json;
 
     #[test]
     fn library_string_interface() {
@@ -98,4 +172,16 @@ mod tests {
         let v = doc.parse_string("\"jhon\"".to_string()).unwrap();
         assert_eq!(v, JSONValue::String("jhon".to_string()));
     }
+
+    #[test]
+    fn json5_document() {
+        let mut doc = JSONDocument::new().with_dialect(Dialect::Json5);
+        let v = doc
+            .parse_string("{ a: [1, 2,], /* c */ }".to_string())
+            .unwrap();
+        assert_eq!(v, json!({ "a": [1, 2] }));
+        assert!(JSONDocument::new()
+            .parse_string("{ a: 1 }".to_string())
+            .is_err());
+    }
 }
@@ -0,0 +1,197 @@
+// Builds a `JSONValue` from JSON-like syntax. Values may be any Rust
+// expression convertible with `JSONValue::from`, keys any expression
+// convertible into a `String`:
+//
+//     let v = json!({ "name": name, "tags": ["a", "b"], (key): 1 + 2 });
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! json_internal {
+    // arrays: elements are munched one at a time into `[$elems,]`
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(true)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(false)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($map:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($map)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+    (@array [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+        $crate::json_unexpected!($unexpected)
+    };
+
+    // objects: key tokens are munched up to the `:`, then the value up to
+    // the `,`; the last argument is a copy of the remaining tokens used to
+    // point errors at the offending token
+    (@object $object:ident () () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert(($($key)+).into(), $value);
+        $crate::json_internal!(@object $object () ($($rest)*) ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr) $unexpected:tt $($rest:tt)*) => {
+        $crate::json_unexpected!($unexpected);
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $object.insert(($($key)+).into(), $value);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: true $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(true)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: false $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(false)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)) , $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)));
+    };
+    // missing value for the last key
+    (@object $object:ident ($($key:tt)+) (:) $copy:tt) => {
+        $crate::json_internal!();
+    };
+    // missing colon and value for the last key
+    (@object $object:ident ($($key:tt)+) () $copy:tt) => {
+        $crate::json_internal!();
+    };
+    (@object $object:ident () (: $($rest:tt)*) ($colon:tt $($copy:tt)*)) => {
+        $crate::json_unexpected!($colon);
+    };
+    (@object $object:ident ($($key:tt)*) (, $($rest:tt)*) ($comma:tt $($copy:tt)*)) => {
+        $crate::json_unexpected!($comma);
+    };
+    // a parenthesized key is taken whole, so it may contain `:` or `,`
+    (@object $object:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($key) (: $($rest)*) (: $($rest)*));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*) ($($rest)*));
+    };
+
+    (null) => {
+        $crate::JSONValue::Null
+    };
+    (true) => {
+        $crate::JSONValue::Boolean(true)
+    };
+    (false) => {
+        $crate::JSONValue::Boolean(false)
+    };
+    ([]) => {
+        $crate::JSONValue::Array(vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::JSONValue::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::JSONValue::Object(::std::collections::HashMap::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::JSONValue::Object({
+            let mut object = ::std::collections::HashMap::new();
+            $crate::json_internal!(@object object () ($($tt)+) ($($tt)+));
+            object
+        })
+    };
+    ($other:expr) => {
+        $crate::JSONValue::from($other)
+    };
+}
+
+// has no rules, so calling it reports the token it was given
+#[doc(hidden)]
+#[macro_export]
+macro_rules! json_unexpected {
+    () => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{JSONDocument, JSONValue};
+
+    fn parse(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(json!(null), JSONValue::Null);
+        assert_eq!(json!(true), JSONValue::Boolean(true));
+        assert_eq!(json!(1.5), JSONValue::Number(1.5));
+        assert_eq!(json!("a"), JSONValue::String("a".to_string()));
+        assert_eq!(json!([]), JSONValue::Array(vec![]));
+        assert_eq!(json!({}), parse("{}"));
+        assert_eq!(
+            json!([1, [null, false], { "a": {} },]),
+            parse(r#"[1, [null, false], { "a": {} }]"#)
+        );
+    }
+
+    #[test]
+    fn interpolation() {
+        let name = "John Doe";
+        let key = String::from("computed");
+        let primes = vec![11, 13];
+        let weight: Option<f64> = None;
+        let v = json!({
+            "name": name,
+            "age": 40 + 3,
+            "props": { "weight": weight, "height": 2.3 },
+            "primes": primes,
+            "colors": ["red", "blue".to_string()],
+            key.clone(): true,
+            (format!("{}-{}", "a", 1)): -1,
+        });
+        assert_eq!(
+            v,
+            parse(
+                r#"{
+                    "name": "John Doe",
+                    "age": 43,
+                    "props": { "weight": null, "height": 2.3 },
+                    "primes": [11, 13],
+                    "colors": ["red", "blue"],
+                    "computed": true,
+                    "a-1": -1
+                }"#
+            )
+        );
+    }
+}
@@ -1,3 +1,475 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read, Write};
+use std::process;
+
+use erjson::formats::Format;
+use erjson::{
+    parse_path, render_diff, ArrayStrategy, CSTDocument, DiffOptions, FormatOptions, JSONDocument,
+    JSONError, JSONValue,
+};
+
+const USAGE: &str = "usage: erjson <command> [options] [FILE...]
+
+Reads standard input when no FILE (or `-`) is given.
+
+commands:
+  fmt        pretty-print JSON with object keys in sorted order
+               --indent N     indent with N spaces (default 2)
+               --tabs         indent with tabs
+               -i, --in-place rewrite the files instead of printing
+  minify     print JSON on a single line; accepts -i
+  validate   check that every input is valid JSON, reporting every error
+  get PATH   print the value at PATH, a JSON Pointer or dotted path (a.b[0])
+               -r, --raw      print strings without quotes
+  set PATH VALUE
+             set the value at PATH, creating missing members, and print the
+             document with only the edited lines changed
+               -r, --raw      take VALUE as a string instead of JSON
+               -i, --in-place rewrite the file instead of printing
+  diff A B   compare two documents, ignoring key order and whitespace; exits
+             with 1 when they differ and 2 when either is not valid JSON
+               --patch        print an RFC 6902 patch turning A into B
+               --color WHEN   always, never or auto (default)
+               --arrays HOW   match array elements by index (default), lcs
+                              or key:MEMBER
+  convert    translate between json, jsonc, json5, yaml, toml, csv, msgpack
+             and cbor; fails on values the target format cannot represent
+               --from FORMAT  input format (default: from the extension, or json)
+               --to FORMAT    output format (default: from the -o extension)
+               -o FILE        write to FILE instead of standard output
+
+exit codes: 0 success, 1 invalid JSON, 2 usage or I/O error,
+            3 PATH missing (or not settable), 4 `get` found null";
+
+// usage and I/O problems exit with 2, invalid documents with 1
+enum Failure {
+    Usage(String),
+    Io(String),
+    // already reported
+    Invalid,
+}
+
+struct Input {
+    name: String,
+    path: Option<String>,
+}
+
+fn inputs(files: &[String]) -> Vec<Input> {
+    if files.is_empty() {
+        return vec![Input {
+            name: "<stdin>".to_string(),
+            path: None,
+        }];
+    }
+    files
+        .iter()
+        .map(|f| Input {
+            name: if f == "-" { "<stdin>" } else { f }.to_string(),
+            path: if f == "-" { None } else { Some(f.clone()) },
+        })
+        .collect()
+}
+
+fn parse(input: &Input) -> Result<Result<JSONValue, JSONError>, Failure> {
+    let mut doc = JSONDocument::new();
+    match &input.path {
+        Some(path) => {
+            let file =
+                File::open(path).map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))?;
+            Ok(doc.parse_file(file))
+        }
+        None => {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))?;
+            Ok(doc.parse_string(content))
+        }
+    }
+}
+
+fn read(input: &Input) -> Result<String, Failure> {
+    String::from_utf8(read_bytes(input)?).map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))
+}
+
+fn read_bytes(input: &Input) -> Result<Vec<u8>, Failure> {
+    let mut content = vec![];
+    let read = match &input.path {
+        Some(path) => File::open(path).and_then(|mut f| f.read_to_end(&mut content)),
+        None => io::stdin().read_to_end(&mut content),
+    };
+    read.map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))?;
+    Ok(content)
+}
+
+fn report(input: &Input, err: &JSONError) {
+    eprintln!(
+        "{}:{}:{}: {}",
+        input.name,
+        err.line(),
+        err.col(),
+        err.message()
+    );
+}
+
+// objects are hash maps, so keys are always sorted to keep the output the
+// same from one run to the next
+fn reformat(value: &JSONValue, opts: &FormatOptions) -> String {
+    let opts = FormatOptions {
+        sort_keys: true,
+        ..opts.clone()
+    };
+    value.to_string_with(&opts) + "\n"
+}
+
+fn format(args: &[String], mut opts: FormatOptions) -> Result<i32, Failure> {
+    let mut in_place = false;
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--indent" if opts.indent.is_some() => {
+                let n: usize = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| Failure::Usage("--indent expects a number".to_string()))?;
+                opts.indent = Some(" ".repeat(n));
+            }
+            "--tabs" if opts.indent.is_some() => opts.indent = Some("\t".to_string()),
+            "-i" | "--in-place" => in_place = true,
+            a if a.starts_with('-') && a != "-" => {
+                return Err(Failure::Usage(format!("unknown option `{}`", a)))
+            }
+            _ => files.push(arg.clone()),
+        }
+    }
+
+    let inputs = inputs(&files);
+    if in_place && inputs.iter().any(|i| i.path.is_none()) {
+        return Err(Failure::Usage(
+            "--in-place needs files to rewrite".to_string(),
+        ));
+    }
+
+    let mut code = 0;
+    for input in inputs.iter() {
+        let value = match parse(input)? {
+            Ok(value) => value,
+            Err(err) => {
+                report(input, &err);
+                code = 1;
+                continue;
+            }
+        };
+        let out = reformat(&value, &opts);
+        match &input.path {
+            Some(path) if in_place => {
+                fs::write(path, out).map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))?
+            }
+            _ => print!("{}", out),
+        }
+    }
+    Ok(code)
+}
+
+fn validate(args: &[String]) -> Result<i32, Failure> {
+    if let Some(a) = args.iter().find(|a| a.starts_with('-') && *a != "-") {
+        return Err(Failure::Usage(format!("unknown option `{}`", a)));
+    }
+    let mut code = 0;
+    for input in inputs(args).iter() {
+        let text = read(input)?;
+        let (_, errors) = JSONDocument::new().parse_string_recovering(text);
+        for err in errors.iter() {
+            report(input, err);
+            code = 1;
+        }
+    }
+    Ok(code)
+}
+
+// splits options from positional arguments; `-` and negative numbers are
+// positional
+fn split_args<'a>(
+    args: &'a [String],
+    flags: &[&str],
+) -> Result<(Vec<&'a str>, Vec<&'a str>), Failure> {
+    let (mut set, mut positional) = (vec![], vec![]);
+    for arg in args.iter().map(String::as_str) {
+        if arg.starts_with('-') && arg != "-" && arg.parse::<f64>().is_err() {
+            if !flags.contains(&arg) {
+                return Err(Failure::Usage(format!("unknown option `{}`", arg)));
+            }
+            set.push(arg);
+        } else {
+            positional.push(arg);
+        }
+    }
+    Ok((set, positional))
+}
+
+fn document(file: Option<&str>) -> Result<(Input, String, JSONValue), Failure> {
+    let input = inputs(&file.map(String::from).into_iter().collect::<Vec<_>>()).remove(0);
+    let text = read(&input)?;
+    match JSONDocument::new().parse_string(text.clone()) {
+        Ok(value) => Ok((input, text, value)),
+        Err(err) => {
+            report(&input, &err);
+            Err(Failure::Invalid)
+        }
+    }
+}
+
+fn get(args: &[String]) -> Result<i32, Failure> {
+    let (flags, positional) = split_args(args, &["-r", "--raw"])?;
+    let (path, file) = match positional.as_slice() {
+        [path] => (*path, None),
+        [path, file] => (*path, Some(*file)),
+        _ => return Err(Failure::Usage("get expects PATH [FILE]".to_string())),
+    };
+    parse_path(path).map_err(|e| Failure::Usage(e.message().to_string()))?;
+    let (_, _, doc) = document(file)?;
+
+    match doc.get_path(path) {
+        None => Ok(3),
+        Some(JSONValue::Null) => {
+            println!("null");
+            Ok(4)
+        }
+        Some(JSONValue::String(s)) if !flags.is_empty() => {
+            println!("{}", s);
+            Ok(0)
+        }
+        Some(value) => {
+            print!("{}", reformat(value, &FormatOptions::new()));
+            Ok(0)
+        }
+    }
+}
+
+fn set(args: &[String]) -> Result<i32, Failure> {
+    let (flags, positional) = split_args(args, &["-r", "--raw", "-i", "--in-place"])?;
+    let (path, value, file) = match positional.as_slice() {
+        [path, value] => (*path, *value, None),
+        [path, value, file] => (*path, *value, Some(*file)),
+        _ => return Err(Failure::Usage("set expects PATH VALUE [FILE]".to_string())),
+    };
+    let raw = flags.iter().any(|f| *f == "-r" || *f == "--raw");
+    let in_place = flags.iter().any(|f| *f == "-i" || *f == "--in-place");
+    let value = if raw {
+        JSONValue::String(value.to_string())
+    } else {
+        JSONDocument::new()
+            .parse_string(value.to_string())
+            .map_err(|e| Failure::Usage(format!("invalid VALUE: {}", e.message())))?
+    };
+    parse_path(path).map_err(|e| Failure::Usage(e.message().to_string()))?;
+    let (input, text, _) = document(file)?;
+    if in_place && input.path.is_none() {
+        return Err(Failure::Usage(
+            "--in-place needs a file to rewrite".to_string(),
+        ));
+    }
+
+    let mut doc = CSTDocument::parse(&text).map_err(|err| {
+        report(&input, &err);
+        Failure::Invalid
+    })?;
+    if let Err(err) = doc.set(path, &value) {
+        eprintln!("{}: {}", input.name, err.message());
+        return Ok(3);
+    }
+    let out = doc.to_string();
+    match &input.path {
+        Some(path) if in_place => {
+            fs::write(path, out).map_err(|e| Failure::Io(format!("{}: {}", input.name, e)))?
+        }
+        _ => print!("{}", out),
+    }
+    Ok(0)
+}
+
+fn diff(args: &[String]) -> Result<i32, Failure> {
+    let mut opts = DiffOptions::new();
+    let (mut patch, mut color) = (false, io::stdout().is_terminal());
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| Failure::Usage(format!("{} expects a value", name)))
+        };
+        match arg.as_str() {
+            "--patch" => patch = true,
+            "--color" => {
+                color = match value("--color")? {
+                    "always" => true,
+                    "never" => false,
+                    "auto" => io::stdout().is_terminal(),
+                    other => return Err(Failure::Usage(format!("unknown --color `{}`", other))),
+                }
+            }
+            "--arrays" => {
+                opts.arrays = match value("--arrays")? {
+                    "index" => ArrayStrategy::ByIndex,
+                    "lcs" => ArrayStrategy::Lcs,
+                    other if other.starts_with("key:") => {
+                        ArrayStrategy::ByKey(other["key:".len()..].to_string())
+                    }
+                    other => return Err(Failure::Usage(format!("unknown --arrays `{}`", other))),
+                }
+            }
+            a if a.starts_with('-') && a != "-" => {
+                return Err(Failure::Usage(format!("unknown option `{}`", a)))
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let (a, b) = match files.as_slice() {
+        [a, b] => (*a, *b),
+        _ => return Err(Failure::Usage("diff expects two files".to_string())),
+    };
+
+    // like diff(1), trouble reading either side exits with 2
+    let (a, b) = match (document(Some(a)), document(Some(b))) {
+        (Ok((_, _, a)), Ok((_, _, b))) => (a, b),
+        (Err(Failure::Invalid), _) | (_, Err(Failure::Invalid)) => return Ok(2),
+        (Err(e), _) | (_, Err(e)) => return Err(e),
+    };
+    if patch {
+        let ops = erjson::patch::diff(&a, &b);
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::new()
+        };
+        println!("{}", ops.to_string_with(&opts));
+        return Ok(if ops.as_array().is_some_and(|ops| ops.is_empty()) {
+            0
+        } else {
+            1
+        });
+    }
+    let changes = a.diff_with(&b, &opts);
+    if changes.is_empty() {
+        return Ok(0);
+    }
+    println!("{}", render_diff(&changes, color));
+    Ok(1)
+}
+
+fn convert(args: &[String]) -> Result<i32, Failure> {
+    let (mut from, mut to, mut output) = (None, None, None);
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| Failure::Usage(format!("{} expects a value", name)))
+        };
+        let format = |name: &str| {
+            Format::from_name(name)
+                .ok_or_else(|| Failure::Usage(format!("unknown format `{}`", name)))
+        };
+        match arg.as_str() {
+            "--from" => from = Some(format(value("--from")?)?),
+            "--to" => to = Some(format(value("--to")?)?),
+            "-o" | "--output" => output = Some(value("-o")?),
+            a if a.starts_with('-') && a != "-" => {
+                return Err(Failure::Usage(format!("unknown option `{}`", a)))
+            }
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.len() > 1 {
+        return Err(Failure::Usage(
+            "convert expects at most one FILE".to_string(),
+        ));
+    }
+    let input = inputs(&files).remove(0);
+    let from = from
+        .or_else(|| input.path.as_deref().and_then(Format::from_path))
+        .unwrap_or(Format::Json);
+    let to = match to.or_else(|| output.and_then(Format::from_path)) {
+        Some(to) => to,
+        None => return Err(Failure::Usage("missing --to FORMAT".to_string())),
+    };
+
+    let data = read_bytes(&input)?;
+    let value = from.decode(&data).map_err(|err| {
+        report(&input, &err);
+        Failure::Invalid
+    })?;
+    let out = match to.encode(&value) {
+        Ok(out) => out,
+        Err(err) => {
+            eprintln!("erjson: {}", err.message());
+            return Ok(1);
+        }
+    };
+    match output {
+        Some(path) => fs::write(path, out).map_err(|e| Failure::Io(format!("{}: {}", path, e)))?,
+        None => io::stdout()
+            .write_all(&out)
+            .map_err(|e| Failure::Io(e.to_string()))?,
+    }
+    Ok(0)
+}
+
+fn run(args: &[String]) -> Result<i32, Failure> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(Failure::Usage("missing command".to_string())),
+    };
+    match command {
+        "fmt" => format(rest, FormatOptions::new()),
+        "minify" => format(rest, FormatOptions::compact()),
+        "validate" => validate(rest),
+        "get" => get(rest),
+        "set" => set(rest),
+        "diff" => diff(rest),
+        "convert" => convert(rest),
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        _ => Err(Failure::Usage(format!("unknown command `{}`", command))),
+    }
+}
+
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match run(&args) {
+        Ok(code) => code,
+        Err(Failure::Usage(msg)) => {
+            eprintln!("erjson: {}\n\n{}", msg, USAGE);
+            2
+        }
+        Err(Failure::Io(msg)) => {
+            eprintln!("erjson: {}", msg);
+            2
+        }
+        Err(Failure::Invalid) => 1,
+    };
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_is_deterministic() {
+        let text = r#"{"d": 1, "b": {"z": [], "a": null}, "c": 2, "a": 3, "e": 4, "f": 5}"#;
+        let parse = || JSONDocument::new().parse_string(text.to_string()).unwrap();
+        for opts in [FormatOptions::new(), FormatOptions::compact()].iter() {
+            assert_eq!(reformat(&parse(), opts), reformat(&parse(), opts));
+        }
+        assert_eq!(
+            reformat(&parse(), &FormatOptions::compact()),
+            "{\"a\":3,\"b\":{\"a\":null,\"z\":[]},\"c\":2,\"d\":1,\"e\":4,\"f\":5}\n"
+        );
+    }
 }
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use crate::pointer::escape_token;
+use crate::value::JSONValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeArrays {
+    Replace,
+    Append,
+    ByIndex,
+    // elements are objects matched on the value of this member
+    ByKey(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeNulls {
+    // a `null` in the overlay is merged like any other value
+    Overwrite,
+    Skip,
+    // removes the member from the base object; elsewhere acts as `Overwrite`
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeConflicts {
+    OverlayWins,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeStrategy {
+    pub arrays: MergeArrays,
+    pub nulls: MergeNulls,
+    pub conflicts: MergeConflicts,
+}
+
+impl MergeStrategy {
+    pub fn new() -> Self {
+        MergeStrategy {
+            arrays: MergeArrays::Replace,
+            nulls: MergeNulls::Overwrite,
+            conflicts: MergeConflicts::OverlayWins,
+        }
+    }
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        MergeStrategy::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeError(String, String);
+
+impl MergeError {
+    pub fn new(err: String, path: String) -> Self {
+        MergeError(err, path)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+
+    // JSON Pointer of the conflicting value
+    pub fn path(&self) -> &str {
+        &self.1
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.1.is_empty() { "/" } else { &self.1 };
+        write!(f, "MergeError: {} - @ {}", self.0, path)
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+// Merges `overlay` into `base`: objects are merged member by member and
+// arrays according to `strategy`, anything else is replaced. Returns the
+// pointers of base values that were replaced or removed, sorted. On error
+// `base` is left untouched.
+pub fn deep_merge(
+    base: &mut JSONValue,
+    overlay: JSONValue,
+    strategy: &MergeStrategy,
+) -> Result<Vec<String>, MergeError> {
+    let mut target = base.clone();
+    let mut overridden = vec![];
+    merge(&mut target, overlay, "", strategy, &mut overridden)?;
+    *base = target;
+    overridden.sort();
+    Ok(overridden)
+}
+
+fn merge(
+    base: &mut JSONValue,
+    overlay: JSONValue,
+    path: &str,
+    strategy: &MergeStrategy,
+    overridden: &mut Vec<String>,
+) -> Result<(), MergeError> {
+    match (base, overlay) {
+        (JSONValue::Object(hm), JSONValue::Object(members)) => {
+            for (k, v) in members {
+                let at = format!("{}/{}", path, escape_token(&k));
+                match hm.get_mut(&k) {
+                    Some(_) if v.is_null() && strategy.nulls == MergeNulls::Delete => {
+                        hm.remove(&k);
+                        overridden.push(at);
+                    }
+                    Some(old) => merge(old, v, &at, strategy, overridden)?,
+                    None if v.is_null() && strategy.nulls != MergeNulls::Overwrite => (),
+                    None => {
+                        hm.insert(k, v);
+                    }
+                }
+            }
+        }
+        (JSONValue::Array(vc), JSONValue::Array(items)) => match &strategy.arrays {
+            MergeArrays::Replace => {
+                if *vc != items {
+                    overridden.push(path.to_string());
+                }
+                *vc = items;
+            }
+            MergeArrays::Append => vc.extend(items),
+            MergeArrays::ByIndex => {
+                for (i, item) in items.into_iter().enumerate() {
+                    match vc.get_mut(i) {
+                        Some(old) => {
+                            merge(old, item, &format!("{}/{}", path, i), strategy, overridden)?
+                        }
+                        None => vc.push(item),
+                    }
+                }
+            }
+            MergeArrays::ByKey(key) => {
+                for item in items {
+                    let found = item
+                        .get(key)
+                        .and_then(|id| vc.iter().position(|old| old.get(key) == Some(id)));
+                    match found {
+                        Some(i) => merge(
+                            &mut vc[i],
+                            item,
+                            &format!("{}/{}", path, i),
+                            strategy,
+                            overridden,
+                        )?,
+                        None => vc.push(item),
+                    }
+                }
+            }
+        },
+        (_, v) if v.is_null() && strategy.nulls == MergeNulls::Skip => (),
+        (base, v) => {
+            let conflict = !base.is_null() && !v.is_null() && base.type_name() != v.type_name();
+            if conflict && strategy.conflicts == MergeConflicts::Error {
+                return Err(MergeError::new(
+                    format!("Cannot merge {} into {}", v.type_name(), base.type_name()),
+                    path.to_string(),
+                ));
+            }
+            if *base != v {
+                overridden.push(path.to_string());
+                *base = v;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn layering() {
+        let mut config = json!({
+            "server": { "host": "localhost", "port": 8080, "tls": null },
+            "features": ["a", "b"],
+            "debug": false
+        });
+        let env = json!({ "server": { "port": 9000 }, "features": ["c"] });
+        let user = json!({ "server": { "host": null, "tls": { "cert": "x" } }, "debug": true });
+
+        let strategy = MergeStrategy::new();
+        let overridden = deep_merge(&mut config, env, &strategy).unwrap();
+        assert_eq!(overridden, vec!["/features", "/server/port"]);
+
+        let strategy = MergeStrategy {
+            nulls: MergeNulls::Skip,
+            ..MergeStrategy::new()
+        };
+        let overridden = deep_merge(&mut config, user, &strategy).unwrap();
+        assert_eq!(overridden, vec!["/debug", "/server/tls"]);
+        assert_eq!(
+            config,
+            json!({
+                "server": { "host": "localhost", "port": 9000, "tls": { "cert": "x" } },
+                "features": ["c"],
+                "debug": true
+            })
+        );
+
+        let strategy = MergeStrategy {
+            nulls: MergeNulls::Delete,
+            ..MergeStrategy::new()
+        };
+        let overridden = deep_merge(
+            &mut config,
+            json!({ "server": { "tls": null, "x": null } }),
+            &strategy,
+        )
+        .unwrap();
+        assert_eq!(overridden, vec!["/server/tls"]);
+        assert!(!config["server"].has_key("tls") && !config["server"].has_key("x"));
+    }
+
+    #[test]
+    fn arrays() {
+        let merged = |arrays: MergeArrays, base: JSONValue, overlay: JSONValue| {
+            let mut base = base;
+            let strategy = MergeStrategy {
+                arrays,
+                ..MergeStrategy::new()
+            };
+            let overridden = deep_merge(&mut base, overlay, &strategy).unwrap();
+            (base, overridden)
+        };
+
+        let (v, _) = merged(MergeArrays::Append, json!([1, 2]), json!([2, 3]));
+        assert_eq!(v, json!([1, 2, 2, 3]));
+
+        let (v, overridden) = merged(
+            MergeArrays::ByIndex,
+            json!([{ "a": 1 }, 2]),
+            json!([{ "b": 1 }, 3, 4]),
+        );
+        assert_eq!(v, json!([{ "a": 1, "b": 1 }, 3, 4]));
+        assert_eq!(overridden, vec!["/1"]);
+
+        let (v, overridden) = merged(
+            MergeArrays::ByKey("id".to_string()),
+            json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "b" }]),
+            json!([{ "id": 2, "v": "c" }, { "id": 3 }, 5]),
+        );
+        assert_eq!(
+            v,
+            json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "c" }, { "id": 3 }, 5])
+        );
+        assert_eq!(overridden, vec!["/1/v"]);
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut base = json!({ "port": 80, "hosts": ["a"] });
+        let overlay = json!({ "port": "80", "hosts": { "a": 1 } });
+
+        let strategy = MergeStrategy {
+            conflicts: MergeConflicts::Error,
+            ..MergeStrategy::new()
+        };
+        let err = deep_merge(&mut base, overlay.clone(), &strategy).unwrap_err();
+        assert!(err.path() == "/port" || err.path() == "/hosts");
+        assert_eq!(base, json!({ "port": 80, "hosts": ["a"] }));
+        let err = deep_merge(&mut json!(1), json!(true), &strategy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "MergeError: Cannot merge boolean into number - @ /"
+        );
+
+        let overridden = deep_merge(&mut base, overlay.clone(), &MergeStrategy::new()).unwrap();
+        assert_eq!(overridden, vec!["/hosts", "/port"]);
+        assert_eq!(base, overlay);
+    }
+}
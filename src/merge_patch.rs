@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::value::JSONValue;
+
+// Applies an RFC 7396 merge patch: object members are merged recursively,
+// `null` members delete the key and any other patch value replaces the target.
+pub fn merge_patch(target: &mut JSONValue, patch: &JSONValue) {
+    let members = match patch {
+        JSONValue::Object(members) => members,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = JSONValue::Object(HashMap::new());
+    }
+    if let JSONValue::Object(hm) = target {
+        for (k, v) in members {
+            if v.is_null() {
+                hm.remove(k);
+            } else {
+                merge_patch(hm.entry(k.clone()).or_insert(JSONValue::Null), v);
+            }
+        }
+    }
+}
+
+// Produces a merge patch turning `a` into `b`. Merge patches cannot set a
+// member to `null` (it means delete), so such members are removed instead.
+pub fn merge_patch_diff(a: &JSONValue, b: &JSONValue) -> JSONValue {
+    match (a, b) {
+        (JSONValue::Object(x), JSONValue::Object(y)) => {
+            let mut patch = HashMap::new();
+            for k in x.keys() {
+                if !y.contains_key(k) {
+                    patch.insert(k.clone(), JSONValue::Null);
+                }
+            }
+            for (k, v) in y {
+                match x.get(k) {
+                    Some(old) if old == v => (),
+                    Some(old) if old.is_object() && v.is_object() => {
+                        patch.insert(k.clone(), merge_patch_diff(old, v));
+                    }
+                    _ => {
+                        patch.insert(k.clone(), strip_nulls(v));
+                    }
+                }
+            }
+            JSONValue::Object(patch)
+        }
+        _ => strip_nulls(b),
+    }
+}
+
+// nulls nested in a new object would be read as deletes, drop them up front
+fn strip_nulls(v: &JSONValue) -> JSONValue {
+    match v {
+        JSONValue::Object(hm) => JSONValue::Object(
+            hm.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), strip_nulls(v)))
+                .collect(),
+        ),
+        v => v.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn rfc7396_examples() {
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (
+                r#"{"a":{"b":"c"}}"#,
+                r#"{"a":{"b":"d","c":null}}"#,
+                r#"{"a":{"b":"d"}}"#,
+            ),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (
+                r#"{}"#,
+                r#"{"a":{"bb":{"ccc":null}}}"#,
+                r#"{"a":{"bb":{}}}"#,
+            ),
+        ];
+        for (target, patch, expected) in cases.iter() {
+            let mut target = json(target);
+            merge_patch(&mut target, &json(patch));
+            assert_eq!(target, json(expected), "patch {}", patch);
+        }
+    }
+
+    #[test]
+    fn diff_roundtrip() {
+        let cases = [
+            (
+                r#"{"a":1,"b":{"c":2,"d":3}}"#,
+                r#"{"b":{"c":4,"d":3},"e":[1]}"#,
+            ),
+            (r#"{"a":{"b":1}}"#, r#"{"a":"flat"}"#),
+            (r#"[1]"#, r#"{"a":{"b":1}}"#),
+            (r#"{"a":1}"#, r#"{"a":1}"#),
+        ];
+        for (a, b) in cases.iter() {
+            let (a, b) = (json(a), json(b));
+            let patch = merge_patch_diff(&a, &b);
+            let mut target = a.clone();
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, b);
+        }
+
+        let patch = merge_patch_diff(&json(r#"{"a":1,"b":2}"#), &json(r#"{"b":3}"#));
+        assert_eq!(patch, json(r#"{"a":null,"b":3}"#));
+    }
+}
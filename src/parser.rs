@@ -1,20 +1,44 @@
 use std::collections::HashMap;
 use std::fs::File;
 
-use crate::scanner::{Scanner, Token, TokenKind};
+use crate::pointer::escape_token;
+use crate::scanner::{Dialect, Scanner, Token, TokenKind};
 use crate::value::{JSONError, JSONValue};
 
+// Comments captured from JSONC or JSON5 input, keyed by JSON pointer. Each
+// comment keeps its delimiters so it can be written back as it was.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comments {
+    // comments before a member or element (or before the root for "")
+    pub leading: HashMap<String, Vec<String>>,
+    // comments before the closing bracket of a container
+    pub trailing: HashMap<String, Vec<String>>,
+    // comments after the root value
+    pub end: Vec<String>,
+}
+
 #[derive(Debug)]
 pub struct Parser<'a> {
     scanner: Scanner<'a>,
     ct: Token,
+    dialect: Dialect,
+    // pointer to the value being parsed and the comments seen so far, when
+    // capturing
+    pointer: String,
+    comments: Option<Comments>,
+    // errors recorded so far, when recovering
+    errors: Option<Vec<JSONError>>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn from_string(data: &'a String) -> Self {
+    pub fn from_string(data: &'a str) -> Self {
         Parser {
-            scanner: Scanner::from_string(&data),
+            scanner: Scanner::from_string(data),
             ct: Token::dummy(),
+            dialect: Dialect::Json,
+            pointer: String::new(),
+            comments: None,
+            errors: None,
         }
     }
 
@@ -22,19 +46,124 @@ impl<'a> Parser<'a> {
         Parser {
             scanner: Scanner::from_file(file),
             ct: Token::dummy(),
+            dialect: Dialect::Json,
+            pointer: String::new(),
+            comments: None,
+            errors: None,
+        }
+    }
+
+    pub fn with_dialect(self, dialect: Dialect) -> Self {
+        Parser {
+            scanner: self.scanner.with_dialect(dialect),
+            dialect,
+            ..self
         }
     }
 
     pub fn parse(&mut self) -> Result<JSONValue, JSONError> {
-        let mut value = Err(JSONError::new("Empty json stream".to_string(), 0, 0));
+        if self.consume().kind == TokenKind::Eof {
+            self.fail(JSONError::new("Empty json stream".to_string(), 0, 0))?;
+            return Ok(JSONValue::Null);
+        }
+        self.note(false);
+        let value = self.parse_value()?;
+        let end = self.scanner.take_comments();
+        if let Some(captured) = &mut self.comments {
+            captured.end = end;
+        }
+        if self.ct.kind != TokenKind::Eof {
+            let msg = format!("Unexpected token {} after value", self.ct.value);
+            self.fail(self.error(msg))?;
+        }
+        Ok(value)
+    }
+
+    // Like `parse`, but keeps going after errors: bad values read as `null`,
+    // missing delimiters are assumed and anything else is skipped up to the
+    // next `,` or closing bracket. Returns what could be read with every error.
+    pub fn parse_recovering(&mut self) -> (JSONValue, Vec<JSONError>) {
+        self.errors = Some(vec![]);
+        let value = self.parse().unwrap_or(JSONValue::Null);
+        (value, self.errors.take().unwrap_or_default())
+    }
+
+    // like `parse`, also returning the comments attached to the value that
+    // follows them
+    pub fn parse_with_comments(&mut self) -> Result<(JSONValue, Comments), JSONError> {
+        self.comments = Some(Comments::default());
+        let value = self.parse()?;
+        Ok((value, self.comments.take().unwrap_or_default()))
+    }
+
+    // attaches the comments before the current token to the current pointer
+    fn note(&mut self, trailing: bool) {
+        let comments = self.scanner.take_comments();
+        let captured = match &mut self.comments {
+            Some(captured) if !comments.is_empty() => captured,
+            _ => return,
+        };
+        let map = if trailing {
+            &mut captured.trailing
+        } else {
+            &mut captured.leading
+        };
+        map.entry(self.pointer.clone())
+            .or_insert_with(Vec::new)
+            .extend(comments);
+    }
+
+    // consumes a closing bracket
+    fn close(&mut self) {
+        self.note(true);
+        self.consume();
+    }
+
+    fn error(&self, msg: String) -> JSONError {
+        JSONError::new(msg, self.ct.line, self.ct.col)
+    }
+
+    // records `err` when recovering, otherwise fails with it
+    fn fail(&mut self, err: JSONError) -> Result<(), JSONError> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(err);
+                Ok(())
+            }
+            None => Err(err),
+        }
+    }
+
+    // skips to the next `,` or closing bracket of the current container
+    fn synchronize(&mut self) {
+        let mut depth = 0;
         loop {
-            let tk = self.consume();
-            value = match tk.kind {
-                TokenKind::Eof => break,
-                _ => self.parse_value(),
+            match self.ct.kind {
+                TokenKind::Eof => return,
+                TokenKind::Comma | TokenKind::RightBrace | TokenKind::RightBracket
+                    if depth == 0 =>
+                {
+                    return
+                }
+                TokenKind::LeftBrace | TokenKind::LeftBracket => depth += 1,
+                TokenKind::RightBrace | TokenKind::RightBracket => depth -= 1,
+                _ => (),
             }
+            self.consume();
         }
-        value
+    }
+
+    fn starts_value(&self) -> bool {
+        matches!(
+            self.ct.kind,
+            TokenKind::Null
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::String
+                | TokenKind::Number
+                | TokenKind::LeftBrace
+                | TokenKind::LeftBracket
+        )
     }
 
     fn consume(&mut self) -> Token {
@@ -42,68 +171,135 @@ impl<'a> Parser<'a> {
         self.ct.clone()
     }
 
+    // JSON5 also allows identifier keys, including reserved words
+    fn is_key(&self) -> bool {
+        match self.ct.kind {
+            TokenKind::String => true,
+            TokenKind::Identifier | TokenKind::Null | TokenKind::True | TokenKind::False => {
+                self.dialect == Dialect::Json5
+            }
+            TokenKind::Number => self.ct.value == "Infinity" || self.ct.value == "NaN",
+            _ => false,
+        }
+    }
+
+    fn parse_number(&self, text: &str) -> Option<f64> {
+        if self.dialect != Dialect::Json5 {
+            // no leading zeros, and a `.` must be followed by a digit: `01`,
+            // `-01`, `-.5`, `1.` and `1.e5` are invalid
+            let mantissa = text.split(['e', 'E']).next().unwrap_or(text);
+            let (int, fraction) = match mantissa.split_once('.') {
+                Some((int, fraction)) => (int, Some(fraction)),
+                None => (mantissa, None),
+            };
+            let int = int.strip_prefix('-').unwrap_or(int);
+            if int.is_empty() || (int.len() > 1 && int.starts_with('0')) || fraction == Some("") {
+                return None;
+            }
+            return text.parse::<f64>().ok();
+        }
+        let (sign, body) = match text.strip_prefix('-') {
+            Some(body) => (-1.0, body),
+            None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let n = match body {
+            "Infinity" => f64::INFINITY,
+            "NaN" => f64::NAN,
+            _ if body.starts_with("0x") || body.starts_with("0X") => {
+                u64::from_str_radix(&body[2..], 16).ok()? as f64
+            }
+            _ if body.starts_with(|c: char| c.is_ascii_digit() || c == '.') => {
+                // leading zeros are not allowed, as in JSON
+                let int = body.split(['.', 'e', 'E']).next().unwrap_or(body);
+                if int.len() > 1 && int.starts_with('0') {
+                    return None;
+                }
+                body.parse::<f64>().ok().filter(|n| n.is_finite())?
+            }
+            _ => return None,
+        };
+        Some(sign * n)
+    }
+
     fn parse_object(&mut self) -> Result<JSONValue, JSONError> {
         let mut values: HashMap<String, JSONValue> = HashMap::new();
 
         //consume LeftBrace
         self.consume();
         if self.ct.kind == TokenKind::RightBrace {
-            self.consume();
+            self.close();
             return Ok(JSONValue::Object(values));
         }
 
-        while self.ct.kind != TokenKind::RightBrace && self.ct.kind != TokenKind::Eof {
-            if self.ct.kind != TokenKind::String {
-                return Err(JSONError::new(
-                    format!("Expecting key name but found {:?}", self.ct.kind),
-                    self.ct.line,
-                    self.ct.col,
-                ));
-            }
-            let key = self.ct.value.clone();
-            self.consume(); //consume String
-
-            if self.ct.kind != TokenKind::Collon {
-                return Err(JSONError::new(
-                    format!("Expecting collon but found {:?}", self.ct.kind),
-                    self.ct.line,
-                    self.ct.col,
-                ));
-            }
-            self.consume(); //consume Collon
-
-            let r = self.parse_value();
-            match r {
-                Ok(v) => values.insert(key, v),
-                Err(_) => return r,
-            };
-
+        loop {
             match self.ct.kind {
-                TokenKind::Comma => {
-                    self.consume();
-                    if self.ct.kind == TokenKind::RightBrace {
-                        return Err(JSONError::new(
-                            format!("Trailing `{}`", self.ct.value),
-                            self.ct.line,
-                            self.ct.col,
-                        ));
-                    }
-                }
-                TokenKind::RightBrace => {
-                    self.consume();
+                TokenKind::RightBracket | TokenKind::Eof => {
+                    // the token is left to the enclosing container
+                    self.fail(self.error("Missing `}`".to_string()))?;
                     return Ok(JSONValue::Object(values));
                 }
+                _ if !self.is_key() => {
+                    let msg = format!("Expecting key name but found {:?}", self.ct.kind);
+                    self.fail(self.error(msg))?;
+                    self.synchronize();
+                }
                 _ => {
-                    return Err(JSONError::new(
-                        format!("Unexpected token {}", self.ct.value),
-                        self.ct.line,
-                        self.ct.col,
-                    ))
+                    let key = self.ct.value.clone();
+                    let len = self.pointer.len();
+                    self.pointer.push('/');
+                    self.pointer.push_str(&escape_token(&key));
+                    self.note(false);
+                    self.consume(); //consume String
+
+                    let collon = self.ct.kind == TokenKind::Collon;
+                    if collon {
+                        self.consume(); //consume Collon
+                        self.note(false);
+                    } else {
+                        let msg = format!("Expecting collon but found {:?}", self.ct.kind);
+                        self.fail(self.error(msg))?;
+                    }
+                    // without a collon only parse what looks like a value
+                    let value = if collon || self.starts_value() {
+                        self.parse_value()?
+                    } else {
+                        JSONValue::Null
+                    };
+                    values.insert(key, value);
+                    self.pointer.truncate(len);
                 }
-            };
-        }
+            }
 
-        Ok(JSONValue::Object(values))
+            loop {
+                match self.ct.kind {
+                    TokenKind::Comma => {
+                        self.consume();
+                        if self.ct.kind == TokenKind::RightBrace {
+                            if self.dialect == Dialect::Json {
+                                self.fail(self.error("Trailing `}`".to_string()))?;
+                            }
+                            self.close();
+                            return Ok(JSONValue::Object(values));
+                        }
+                        break;
+                    }
+                    TokenKind::RightBrace => {
+                        self.close();
+                        return Ok(JSONValue::Object(values));
+                    }
+                    TokenKind::RightBracket | TokenKind::Eof => break,
+                    _ if self.is_key() => {
+                        self.fail(self.error("Missing `,`".to_string()))?;
+                        break;
+                    }
+                    _ => {
+                        let msg = format!("Unexpected token {}", self.ct.value);
+                        self.fail(self.error(msg))?;
+                        self.synchronize();
+                    }
+                }
+            }
+        }
     }
 
     fn parse_array(&mut self) -> Result<JSONValue, JSONError> {
@@ -112,95 +308,104 @@ impl<'a> Parser<'a> {
         //consume LeftBracket
         self.consume();
         if self.ct.kind == TokenKind::RightBracket {
-            self.consume();
+            self.close();
             return Ok(JSONValue::Array(values));
         }
 
-        while self.ct.kind != TokenKind::RightBracket && self.ct.kind != TokenKind::Eof {
-            let rst = self.parse_value();
-            match rst {
-                Ok(v) => values.push(v),
-                Err(err) => return Err(err),
-            }
-
+        loop {
             match self.ct.kind {
-                TokenKind::Comma => {
-                    self.consume();
-                    if self.ct.kind == TokenKind::RightBracket {
-                        return Err(JSONError::new(
-                            format!("Trailing `{}`", self.ct.value),
-                            self.ct.line,
-                            self.ct.col,
-                        ));
-                    }
-                }
-                TokenKind::RightBracket => {
-                    self.consume();
+                TokenKind::RightBrace | TokenKind::Eof => {
+                    // the token is left to the enclosing container
+                    self.fail(self.error("Missing `]`".to_string()))?;
                     return Ok(JSONValue::Array(values));
                 }
+                TokenKind::Comma => {
+                    self.fail(self.error("Unexpected token ,".to_string()))?;
+                }
                 _ => {
-                    return Err(JSONError::new(
-                        format!("Unexpected token {}", self.ct.value),
-                        self.ct.line,
-                        self.ct.col,
-                    ))
+                    let len = self.pointer.len();
+                    self.pointer.push_str(&format!("/{}", values.len()));
+                    self.note(false);
+                    values.push(self.parse_value()?);
+                    self.pointer.truncate(len);
                 }
-            };
-        }
+            }
 
-        Ok(JSONValue::Array(values))
+            loop {
+                match self.ct.kind {
+                    TokenKind::Comma => {
+                        self.consume();
+                        if self.ct.kind == TokenKind::RightBracket {
+                            if self.dialect == Dialect::Json {
+                                self.fail(self.error("Trailing `]`".to_string()))?;
+                            }
+                            self.close();
+                            return Ok(JSONValue::Array(values));
+                        }
+                        break;
+                    }
+                    TokenKind::RightBracket => {
+                        self.close();
+                        return Ok(JSONValue::Array(values));
+                    }
+                    TokenKind::RightBrace | TokenKind::Eof => break,
+                    _ if self.starts_value() => {
+                        self.fail(self.error("Missing `,`".to_string()))?;
+                        break;
+                    }
+                    _ => {
+                        let msg = format!("Unexpected token {}", self.ct.value);
+                        self.fail(self.error(msg))?;
+                        self.synchronize();
+                    }
+                }
+            }
+        }
     }
 
+    // a bad value reads as `null` when recovering; delimiters are left for
+    // the enclosing container
     fn parse_value(&mut self) -> Result<JSONValue, JSONError> {
         let ct = &self.ct;
-        let value = match ct.kind {
+        let msg = match ct.kind {
             TokenKind::Null => {
                 self.consume();
-                Ok(JSONValue::Null)
+                return Ok(JSONValue::Null);
             }
             TokenKind::False => {
                 self.consume();
-                Ok(JSONValue::Boolean(false))
+                return Ok(JSONValue::Boolean(false));
             }
             TokenKind::True => {
                 self.consume();
-                Ok(JSONValue::Boolean(true))
+                return Ok(JSONValue::Boolean(true));
             }
             TokenKind::String => {
                 let v = ct.value.clone();
                 self.consume();
-                Ok(JSONValue::String(v))
+                return Ok(JSONValue::String(v));
             }
-            TokenKind::Number => {
-                // TODO: distinct int, float
-                match ct.value.parse::<f64>() {
-                    Ok(n) => {
-                        self.consume();
-                        Ok(JSONValue::Number(n))
-                    }
-                    Err(_) => Err(JSONError::new(
-                        format!("{} is NaN ", ct.value),
-                        ct.line,
-                        ct.col,
-                    )),
+            TokenKind::Number => match self.parse_number(&ct.value) {
+                Some(n) => {
+                    self.consume();
+                    return Ok(JSONValue::Number(n));
                 }
-            }
-            TokenKind::LeftBrace => self.parse_object(),
-            TokenKind::LeftBracket => self.parse_array(),
-            _ => {
-                return Err(JSONError::new(
-                    format!("Unexpected token {}", ct.value),
-                    ct.line,
-                    ct.col,
-                ))
-            }
+                None => format!("{} is NaN ", ct.value),
+            },
+            TokenKind::LeftBrace => return self.parse_object(),
+            TokenKind::LeftBracket => return self.parse_array(),
+            TokenKind::Error if ct.value.is_empty() => "Invalid or unterminated string".to_string(),
+            TokenKind::Eof => "Unexpected end of input".to_string(),
+            _ => format!("Unexpected token {}", ct.value),
         };
-
-        // if self.ct.kind != TokenKind::Eof {
-        //     println!("after value consume {:?}", self.ct);
-        //     self.consume();
-        // }
-        value
+        self.fail(self.error(msg))?;
+        if !matches!(
+            self.ct.kind,
+            TokenKind::Comma | TokenKind::RightBrace | TokenKind::RightBracket | TokenKind::Eof
+        ) {
+            self.consume();
+        }
+        Ok(JSONValue::Null)
     }
 }
 
@@ -216,4 +421,246 @@ mod tests {
     fn complex_parsing() {
         //TODO:
     }
+
+    #[test]
+    fn strings_and_trailing_content() {
+        use super::Parser;
+        use crate::value::JSONValue;
+
+        let parse = |s: &str| Parser::from_string(s).parse();
+        assert_eq!(
+            parse(r#""a\"b\\c\/\n\u00e9\ud83d\ude00""#).unwrap(),
+            JSONValue::String("a\"b\\c/\né😀".to_string())
+        );
+        assert_eq!(parse(r#"["\\"]"#).unwrap().to_string(), r#"["\\"]"#);
+        for bad in [
+            r#""\x""#,
+            r#""\ud83d""#,
+            "\"a\tb\"",
+            "\"open",
+            "1 2",
+            "{} x",
+        ]
+        .iter()
+        {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+        let err = parse("[1]\n  ]").unwrap_err();
+        assert_eq!((err.line(), err.col()), (2, 4));
+    }
+
+    #[test]
+    fn numbers() {
+        use super::Parser;
+        use crate::json;
+
+        let parse = |s: &str| Parser::from_string(s).parse();
+        assert_eq!(
+            parse("[1E30, 2e-3, 1e5, -0.5E+2, 0, -0, 10.05]").unwrap(),
+            json!([1e30, 0.002, 100000, -50, 0, 0, 10.05])
+        );
+        for bad in [
+            "1.", "1.e5", "[1.]", "-2.E3", "1e", "1e+", "01", "-01", "[00]", "-.5", "-",
+        ]
+        .iter()
+        {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn json5() {
+        use super::Parser;
+        use crate::json;
+        use crate::scanner::Dialect;
+
+        let parse = |s: &str| Parser::from_string(s).with_dialect(Dialect::Json5).parse();
+        let text = "\u{feff}// config
+{
+  unquoted: 'single \\'quoted\\'',
+  $key_1: \"tab\\x09 \\
+next\",
+  hex: 0xFF, neg: -0x10, lead: .5, trail: 5., plus: +1e2,
+  /* block
+     comment */
+  list: [null, true, Infinity, -Infinity,],
+  null: 'reserved',
+}";
+        let v = parse(text).unwrap();
+        assert_eq!(v["unquoted"], json!("single 'quoted'"));
+        assert_eq!(v["$key_1"], json!("tab\t next"));
+        assert_eq!(
+            [&v["hex"], &v["neg"], &v["lead"], &v["trail"], &v["plus"]],
+            [
+                &json!(255),
+                &json!(-16),
+                &json!(0.5),
+                &json!(5),
+                &json!(100)
+            ]
+        );
+        assert_eq!(
+            v["list"],
+            json!([null, true, f64::INFINITY, f64::NEG_INFINITY])
+        );
+        assert_eq!(v["null"], json!("reserved"));
+        assert!(parse("NaN").unwrap().as_f64().unwrap().is_nan());
+        assert_eq!(parse("[0, -0.5, 0e1, 0.]").unwrap(), json!([0, -0.5, 0, 0]));
+
+        for bad in [
+            "{a b: 1}",
+            "[1,,]",
+            "0x",
+            "1e",
+            "'\\1'",
+            "[01abc]",
+            "[01]",
+            "-00.5",
+            "+012e1",
+            "/* open",
+            "undefined",
+        ]
+        .iter()
+        {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+        for strict in ["{a: 1}", "[1,]", "'x'", "// c\n1", "+1", ".5", "Infinity"].iter() {
+            assert!(Parser::from_string(strict).parse().is_err(), "{}", strict);
+        }
+    }
+
+    #[test]
+    fn jsonc_comments() {
+        use super::Parser;
+        use crate::json;
+        use crate::scanner::Dialect;
+        use crate::serializer::FormatOptions;
+
+        let text = "// settings
+{
+  // editor
+  \"tab\": /* spaces */ 2,
+  \"list\": [
+    1,
+    // second
+    2, // after two
+  ],
+  \"empty\": { /* none yet */ },
+}
+// end";
+        let (v, comments) = Parser::from_string(text)
+            .with_dialect(Dialect::Jsonc)
+            .parse_with_comments()
+            .unwrap();
+        assert_eq!(v, json!({ "tab": 2, "list": [1, 2], "empty": {} }));
+        assert_eq!(comments.leading[""], vec!["// settings"]);
+        assert_eq!(comments.leading["/tab"], vec!["// editor", "/* spaces */"]);
+        assert_eq!(comments.leading["/list/1"], vec!["// second"]);
+        assert_eq!(comments.trailing["/list"], vec!["// after two"]);
+        assert_eq!(comments.trailing["/empty"], vec!["/* none yet */"]);
+        assert_eq!(comments.end, vec!["// end"]);
+
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::new()
+        };
+        let out = v.to_jsonc_string(&opts, &comments);
+        assert_eq!(
+            out,
+            "// settings
+{
+  \"empty\": {
+    /* none yet */
+  },
+  \"list\": [
+    1,
+    // second
+    2
+    // after two
+  ],
+  // editor
+  /* spaces */
+  \"tab\": 2
+}
+// end"
+        );
+        let again = Parser::from_string(&out)
+            .with_dialect(Dialect::Jsonc)
+            .parse_with_comments()
+            .unwrap();
+        assert_eq!(again, (v.clone(), comments.clone()));
+        assert_eq!(
+            v.to_jsonc_string(&FormatOptions::compact(), &comments)
+                .matches("/*")
+                .count(),
+            7
+        );
+        let text = "{\"a\": 1 // see a*/b\n}";
+        let (v, comments) = Parser::from_string(text)
+            .with_dialect(Dialect::Jsonc)
+            .parse_with_comments()
+            .unwrap();
+        let out = v.to_jsonc_string(&FormatOptions::compact(), &comments);
+        assert_eq!(out, "{\"a\":1// see a*/b\n}");
+        let again = Parser::from_string(&out)
+            .with_dialect(Dialect::Jsonc)
+            .parse_with_comments()
+            .unwrap();
+        assert_eq!(again, (v, comments));
+
+        for bad in ["{'a': 1}", "{a: 1}", "/* open", "[0x1]"].iter() {
+            let parsed = Parser::from_string(bad)
+                .with_dialect(Dialect::Jsonc)
+                .parse();
+            assert!(parsed.is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn recovering() {
+        use super::Parser;
+        use crate::json;
+
+        let recover = |s: &str| Parser::from_string(s).parse_recovering();
+        let (v, errors) = recover(
+            r#"{"a": 1 "b": [1 2,, 3], "c": tru, "d": {"x" 5, "y": }, "e": "\q", "f": 6,}"#,
+        );
+        assert_eq!(
+            v,
+            json!({ "a": 1, "b": [1, 2, 3], "c": null, "d": { "x": 5, "y": null }, "e": null, "f": 6 })
+        );
+        let messages: Vec<&str> = errors.iter().map(|e| e.message()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Missing `,`",
+                "Missing `,`",
+                "Unexpected token ,",
+                "Unexpected token tru",
+                "Expecting collon but found Number",
+                "Unexpected token }",
+                "Invalid or unterminated string",
+                "Trailing `}`",
+            ]
+        );
+
+        let (v, errors) = recover("[{\"a\": [1, 2}, 3");
+        assert_eq!(v, json!([{ "a": [1, 2] }, 3]));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(recover("").1[0].message(), "Empty json stream");
+        let (v, errors) = recover("{\"a\": 1} x");
+        assert_eq!((v, errors.len()), (json!({ "a": 1 }), 1));
+
+        // every prefix and some mangled variants parse without panicking
+        let text = r#"{"k": [1, -2.5e3, "s\u00e9", true, null, {"n": {}}], "o": {"p": false}}"#;
+        for i in 0..text.len() {
+            let (head, tail) = text.split_at(i);
+            assert!(Parser::from_string(head).parse().is_err());
+            assert!(!recover(head).1.is_empty());
+            recover(&format!("{}{}", tail, head));
+            recover(&format!("{}]}}{}", head, tail));
+            recover(&head.replace(['"', ','], ""));
+        }
+        assert!(recover(text).1.is_empty());
+    }
 }
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::pointer::{escape_token, parse_index, parse_pointer, to_pointer};
+use crate::value::JSONValue;
+
+#[derive(Debug, Clone)]
+pub struct PatchError(String, usize);
+
+impl PatchError {
+    pub fn new(err: String, op: usize) -> Self {
+        PatchError(err, op)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+
+    // index of the failing operation in the patch document
+    pub fn operation(&self) -> usize {
+        self.1
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PatchError: {} - @ operation {}", self.0, self.1)
+    }
+}
+
+// Applies an RFC 6902 patch. Operations run against a copy of the document
+// which only replaces `doc` once all of them succeeded.
+pub fn apply_patch(doc: &mut JSONValue, patch: &JSONValue) -> Result<(), PatchError> {
+    let ops = match patch {
+        JSONValue::Array(ops) => ops,
+        _ => {
+            return Err(PatchError::new(
+                "Patch must be an array of operations".to_string(),
+                0,
+            ))
+        }
+    };
+
+    let mut target = doc.clone();
+    for (i, op) in ops.iter().enumerate() {
+        apply_operation(&mut target, op).map_err(|e| PatchError::new(e, i))?;
+    }
+    *doc = target;
+    Ok(())
+}
+
+fn apply_operation(doc: &mut JSONValue, op: &JSONValue) -> Result<(), String> {
+    let member = |name: &str| match op {
+        JSONValue::Object(hm) => hm.get(name),
+        _ => None,
+    };
+    let string_member = |name: &str| match member(name) {
+        Some(JSONValue::String(s)) => Ok(s.as_str()),
+        _ => Err(format!("Operation is missing string member `{}`", name)),
+    };
+    let value_member = || match member("value") {
+        Some(v) => Ok(v.clone()),
+        None => Err("Operation is missing member `value`".to_string()),
+    };
+
+    let name = string_member("op")?;
+    let path = tokens(string_member("path")?)?;
+    match name {
+        "add" => add(doc, &path, value_member()?),
+        "remove" => remove(doc, &path).map(|_| ()),
+        "replace" => {
+            let value = value_member()?;
+            *lookup_mut(doc, &path)? = value;
+            Ok(())
+        }
+        "move" => {
+            let from = tokens(string_member("from")?)?;
+            if from == path {
+                return Ok(());
+            }
+            if path.starts_with(&from) {
+                return Err("Cannot move a value into one of its children".to_string());
+            }
+            let value = remove(doc, &from)?;
+            add(doc, &path, value)
+        }
+        "copy" => {
+            let from = tokens(string_member("from")?)?;
+            let value = lookup_mut(doc, &from)?.clone();
+            add(doc, &path, value)
+        }
+        "test" => {
+            let value = value_member()?;
+            if *lookup_mut(doc, &path)? != value {
+                return Err(format!("Test failed at `{}`", string_member("path")?));
+            }
+            Ok(())
+        }
+        other => Err(format!("Unknown operation `{}`", other)),
+    }
+}
+
+fn tokens(pointer: &str) -> Result<Vec<String>, String> {
+    parse_pointer(pointer).map_err(|e| e.message().to_string())
+}
+
+fn lookup_mut<'a>(doc: &'a mut JSONValue, path: &[String]) -> Result<&'a mut JSONValue, String> {
+    let mut target = doc;
+    for token in path {
+        target = match target {
+            JSONValue::Object(hm) => hm.get_mut(token),
+            JSONValue::Array(vc) => match parse_index(token) {
+                Some(i) => vc.get_mut(i),
+                None => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| format!("Path `{}` does not exist", to_pointer(path)))?;
+    }
+    Ok(target)
+}
+
+fn add(doc: &mut JSONValue, path: &[String], value: JSONValue) -> Result<(), String> {
+    let (last, parent) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            *doc = value;
+            return Ok(());
+        }
+    };
+    match lookup_mut(doc, parent)? {
+        JSONValue::Object(hm) => {
+            hm.insert(last.clone(), value);
+            Ok(())
+        }
+        JSONValue::Array(vc) => {
+            let i = if last == "-" {
+                vc.len()
+            } else {
+                match parse_index(last) {
+                    Some(i) if i <= vc.len() => i,
+                    _ => return Err(format!("Index `{}` is out of bounds", to_pointer(path))),
+                }
+            };
+            vc.insert(i, value);
+            Ok(())
+        }
+        _ => Err(format!("Cannot add a child to `{}`", to_pointer(parent))),
+    }
+}
+
+fn remove(doc: &mut JSONValue, path: &[String]) -> Result<JSONValue, String> {
+    let (last, parent) = match path.split_last() {
+        Some(split) => split,
+        None => return Ok(std::mem::replace(doc, JSONValue::Null)),
+    };
+    let removed = match lookup_mut(doc, parent)? {
+        JSONValue::Object(hm) => hm.remove(last),
+        JSONValue::Array(vc) => match parse_index(last) {
+            Some(i) if i < vc.len() => Some(vc.remove(i)),
+            _ => None,
+        },
+        _ => None,
+    };
+    removed.ok_or_else(|| format!("Path `{}` does not exist", to_pointer(path)))
+}
+
+// Generates a patch turning `a` into `b`. Objects are diffed per key and
+// arrays per index after trimming their common prefix and suffix.
+pub fn diff(a: &JSONValue, b: &JSONValue) -> JSONValue {
+    let mut ops = vec![];
+    diff_values(a, b, "", &mut ops);
+    JSONValue::Array(ops)
+}
+
+fn diff_values(a: &JSONValue, b: &JSONValue, path: &str, ops: &mut Vec<JSONValue>) {
+    if a == b {
+        return;
+    }
+    match (a, b) {
+        (JSONValue::Object(x), JSONValue::Object(y)) => {
+            let mut keys: Vec<&String> = x.keys().chain(y.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                let child = format!("{}/{}", path, escape_token(k));
+                match (x.get(k), y.get(k)) {
+                    (Some(l), Some(r)) => diff_values(l, r, &child, ops),
+                    (Some(_), None) => ops.push(operation("remove", &child, None)),
+                    (None, Some(r)) => ops.push(operation("add", &child, Some(r))),
+                    (None, None) => (),
+                }
+            }
+        }
+        (JSONValue::Array(x), JSONValue::Array(y)) => {
+            let prefix = x.iter().zip(y.iter()).take_while(|(l, r)| l == r).count();
+            let suffix = x[prefix..]
+                .iter()
+                .rev()
+                .zip(y[prefix..].iter().rev())
+                .take_while(|(l, r)| l == r)
+                .count();
+            let (xm, ym) = (&x[prefix..x.len() - suffix], &y[prefix..y.len() - suffix]);
+            let common = xm.len().min(ym.len());
+            for i in 0..common {
+                let child = format!("{}/{}", path, prefix + i);
+                diff_values(&xm[i], &ym[i], &child, ops);
+            }
+            for i in (common..xm.len()).rev() {
+                ops.push(operation(
+                    "remove",
+                    &format!("{}/{}", path, prefix + i),
+                    None,
+                ));
+            }
+            for (i, v) in ym.iter().enumerate().skip(common) {
+                ops.push(operation(
+                    "add",
+                    &format!("{}/{}", path, prefix + i),
+                    Some(v),
+                ));
+            }
+        }
+        _ => ops.push(operation("replace", path, Some(b))),
+    }
+}
+
+fn operation(name: &str, path: &str, value: Option<&JSONValue>) -> JSONValue {
+    let mut op = HashMap::new();
+    op.insert("op".to_string(), JSONValue::String(name.to_string()));
+    op.insert("path".to_string(), JSONValue::String(path.to_string()));
+    if let Some(v) = value {
+        op.insert("value".to_string(), v.clone());
+    }
+    JSONValue::Object(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn apply_operations() {
+        let mut doc = json(r#"{ "a": { "b": [1, 2] }, "c": "x" }"#);
+        let patch = json(
+            r#"[
+            { "op": "add", "path": "/a/b/1", "value": 9 },
+            { "op": "add", "path": "/a/b/-", "value": 3 },
+            { "op": "remove", "path": "/c" },
+            { "op": "replace", "path": "/a/b/0", "value": "one" },
+            { "op": "copy", "from": "/a/b", "path": "/d" },
+            { "op": "move", "from": "/a", "path": "/e" },
+            { "op": "test", "path": "/d/1", "value": 9 }
+        ]"#,
+        );
+        apply_patch(&mut doc, &patch).unwrap();
+        assert_eq!(
+            doc,
+            json(r#"{ "d": ["one", 9, 2, 3], "e": { "b": ["one", 9, 2, 3] } }"#)
+        );
+    }
+
+    #[test]
+    fn apply_is_atomic() {
+        let mut doc = json(r#"{ "a": 1 }"#);
+        let patch = json(
+            r#"[
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "test", "path": "/a", "value": 3 }
+        ]"#,
+        );
+        let err = apply_patch(&mut doc, &patch).unwrap_err();
+        assert_eq!(err.operation(), 1);
+        assert_eq!(doc, json(r#"{ "a": 1 }"#));
+
+        let bad = [
+            r#"[{ "op": "remove", "path": "/nope" }]"#,
+            r#"[{ "op": "add", "path": "/a/b", "value": 1 }]"#,
+            r#"[{ "op": "move", "from": "", "path": "/a" }]"#,
+            r#"[{ "op": "jump", "path": "/a" }]"#,
+            r#"[{ "op": "replace", "path": "/a" }]"#,
+        ];
+        for patch in bad.iter() {
+            assert!(apply_patch(&mut doc, &json(patch)).is_err(), "{}", patch);
+        }
+    }
+
+    #[test]
+    fn diff_roundtrip() {
+        let cases = [
+            (
+                r#"{ "a": 1, "b": [1, 2, 3] }"#,
+                r#"{ "a": 2, "b": [0, 1, 2, 3], "c/d": null }"#,
+            ),
+            (r#"[1, 2, 3, 4]"#, r#"[1, 4]"#),
+            (r#"[{ "x": 1 }, 5]"#, r#"[{ "x": 2 }, 5, 6, 7]"#),
+            (r#"{ "a": [1] }"#, r#""scalar""#),
+        ];
+        for (a, b) in cases.iter() {
+            let (a, b) = (json(a), json(b));
+            let patch = diff(&a, &b);
+            let mut doc = a.clone();
+            apply_patch(&mut doc, &patch).unwrap();
+            assert_eq!(doc, b);
+        }
+
+        let patch = diff(&json("[1, 2, 3]"), &json("[0, 1, 2, 3]"));
+        assert_eq!(
+            patch,
+            json(r#"[{ "op": "add", "path": "/0", "value": 0 }]"#)
+        );
+        assert_eq!(diff(&json("[1]"), &json("[1]")), json("[]"));
+    }
+}
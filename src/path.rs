@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::pointer::{escape_token, parse_index, parse_pointer};
+use crate::value::{JSONError, JSONValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+// displays as a JSON Pointer token, so a path renders by concatenation
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathSegment::Key(k) => write!(f, "/{}", escape_token(k)),
+            PathSegment::Index(i) => write!(f, "/{}", i),
+        }
+    }
+}
+
+// Parses either a JSON Pointer (`""`, `/a/b/2`) or a dotted path such as
+// `a.b[2].c`, where bracketed quotes hold keys with special characters:
+// `a["x.y"]`.
+pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, JSONError> {
+    if path.is_empty() || path.starts_with('/') {
+        return Ok(parse_pointer(path)?
+            .into_iter()
+            .map(PathSegment::Key)
+            .collect());
+    }
+
+    let err =
+        |msg: &str, at: usize| JSONError::new(format!("{} in path `{}`", msg, path), 0, at + 1);
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '[' => {
+                i += 1;
+                match chars.get(i) {
+                    Some(&quote) if quote == '"' || quote == '\'' => {
+                        let mut key = String::new();
+                        i += 1;
+                        loop {
+                            match chars.get(i) {
+                                Some('\\') if i + 1 < chars.len() => {
+                                    key.push(chars[i + 1]);
+                                    i += 2;
+                                }
+                                Some(&c) if c == quote => break,
+                                Some(&c) => {
+                                    key.push(c);
+                                    i += 1;
+                                }
+                                None => return Err(err("Unterminated key", i)),
+                            }
+                        }
+                        i += 1;
+                        segments.push(PathSegment::Key(key));
+                    }
+                    _ => {
+                        let start = i;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                        let digits: String = chars[start..i].iter().collect();
+                        match digits.parse() {
+                            Ok(n) => segments.push(PathSegment::Index(n)),
+                            Err(_) => return Err(err("Expected an index", start)),
+                        }
+                    }
+                }
+                if chars.get(i) != Some(&']') {
+                    return Err(err("Expected `]`", i));
+                }
+                i += 1;
+            }
+            '.' if i == 0 || i + 1 == chars.len() => return Err(err("Unexpected `.`", i)),
+            '.' => {
+                i += 1;
+                if chars[i] == '.' {
+                    return Err(err("Unexpected `.`", i));
+                }
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                segments.push(PathSegment::Key(chars[start..i].iter().collect()));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+impl JSONValue {
+    pub fn has_key(&self, key: &str) -> bool {
+        self.as_object().is_some_and(|hm| hm.contains_key(key))
+    }
+
+    // true when the path leads to a value, even a `null` one
+    pub fn contains(&self, path: &str) -> bool {
+        self.get_path(path).is_some()
+    }
+
+    pub fn get_path(&self, path: &str) -> Option<&JSONValue> {
+        let mut target = self;
+        for segment in parse_path(path).ok()? {
+            target = match (target, segment) {
+                (JSONValue::Object(hm), PathSegment::Key(k)) => hm.get(&k)?,
+                (JSONValue::Array(vc), PathSegment::Index(i)) => vc.get(i)?,
+                (JSONValue::Array(vc), PathSegment::Key(k)) => vc.get(parse_index(&k)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut JSONValue> {
+        let mut target = self;
+        for segment in parse_path(path).ok()? {
+            target = match (target, segment) {
+                (JSONValue::Object(hm), PathSegment::Key(k)) => hm.get_mut(&k)?,
+                (JSONValue::Array(vc), PathSegment::Index(i)) => vc.get_mut(i)?,
+                (JSONValue::Array(vc), PathSegment::Key(k)) => vc.get_mut(parse_index(&k)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    // Sets the value at `path` and returns the one it replaced. Missing
+    // members are created along the way, as arrays when the next segment is
+    // an index; an index equal to the length, or the token `-`, appends. On
+    // error `self` is left untouched.
+    pub fn set_path(
+        &mut self,
+        path: &str,
+        value: JSONValue,
+    ) -> Result<Option<JSONValue>, JSONError> {
+        let segments = parse_path(path)?;
+        let existed = self.get_path(path).is_some();
+        let mut doc = self.clone();
+        let mut target = &mut doc;
+        for (i, segment) in segments.iter().enumerate() {
+            if target.is_null() {
+                *target = match segment {
+                    PathSegment::Index(_) => JSONValue::Array(vec![]),
+                    PathSegment::Key(_) => JSONValue::Object(HashMap::new()),
+                };
+            }
+            let err = |msg: String| {
+                let at: String = segments[..i].iter().map(|s| s.to_string()).collect();
+                JSONError::new(format!("{} at `{}`", msg, at), 0, 0)
+            };
+            target = match (target, segment) {
+                (JSONValue::Object(hm), PathSegment::Key(k)) => {
+                    hm.entry(k.clone()).or_insert(JSONValue::Null)
+                }
+                (JSONValue::Array(vc), segment) => {
+                    let index = match segment {
+                        PathSegment::Index(n) => Some(*n),
+                        PathSegment::Key(k) if k == "-" => Some(vc.len()),
+                        PathSegment::Key(k) => parse_index(k),
+                    };
+                    let index = index.ok_or_else(|| err(format!("Invalid index `{}`", segment)))?;
+                    if index == vc.len() {
+                        vc.push(JSONValue::Null);
+                    }
+                    let len = vc.len();
+                    vc.get_mut(index).ok_or_else(|| {
+                        err(format!("Index {} out of bounds for length {}", index, len))
+                    })?
+                }
+                (v, segment) => {
+                    return Err(err(format!(
+                        "Cannot set `{}` in {}",
+                        segment,
+                        v.type_name()
+                    )))
+                }
+            };
+        }
+        let old = std::mem::replace(target, value);
+        *self = doc;
+        Ok(if existed { Some(old) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn parsing() {
+        use PathSegment::{Index, Key};
+        let key = |k: &str| Key(k.to_string());
+        assert_eq!(
+            parse_path("a.b[2].c").unwrap(),
+            vec![key("a"), key("b"), Index(2), key("c")]
+        );
+        assert_eq!(
+            parse_path(r#"[0]["x.y"]['q\'s']"#).unwrap(),
+            vec![Index(0), key("x.y"), key("q's")]
+        );
+        assert_eq!(parse_path("/a~1b/0").unwrap(), vec![key("a/b"), key("0")]);
+        assert_eq!(parse_path("").unwrap(), vec![]);
+        for bad in [".a", "a.", "a..b", "a[x]", "a[1", "a[\"x]"].iter() {
+            assert!(parse_path(bad).is_err(), "{}", bad);
+        }
+        let segments = parse_path("a/b[3]").unwrap();
+        let pointer: String = segments.iter().map(|s| s.to_string()).collect();
+        assert_eq!(pointer, "/a~1b/3");
+    }
+
+    #[test]
+    fn presence() {
+        let mut v = json!({
+            "flags": { "beta": { "users": ["ann", "bob"], "rollout": null } },
+            "x.y": 1
+        });
+        assert!(v.has_key("flags") && !v.has_key("beta"));
+        assert!(v.contains("flags.beta.rollout"));
+        assert!(v.contains("/flags/beta/users/1"));
+        assert!(v.contains(r#"["x.y"]"#));
+        assert!(!v.contains("flags.beta.users[2]"));
+        assert!(!v.contains("flags.alpha"));
+        assert!(!v.contains("flags.beta.users.name"));
+        assert_eq!(v.get_path("flags.beta.users[1]"), Some(&json!("bob")));
+        assert_eq!(v.get_path("flags.beta.users.0"), Some(&json!("ann")));
+
+        *v.get_path_mut("flags.beta.rollout").unwrap() = json!(0.5);
+        assert_eq!(v["flags"]["beta"]["rollout"], json!(0.5));
+    }
+
+    #[test]
+    fn setting() {
+        let mut v = json!({ "a": { "b": 1 }, "list": [1] });
+        assert_eq!(v.set_path("a.b", json!(2)).unwrap(), Some(json!(1)));
+        assert_eq!(v.set_path("/a/c/d", json!(true)).unwrap(), None);
+        assert_eq!(v.set_path("list[1]", json!(2)).unwrap(), None);
+        assert_eq!(v.set_path("/list/-", json!(3)).unwrap(), None);
+        assert_eq!(v.set_path("new[0].x", json!("y")).unwrap(), None);
+        assert_eq!(
+            v,
+            json!({
+                "a": { "b": 2, "c": { "d": true } },
+                "list": [1, 2, 3],
+                "new": [{ "x": "y" }]
+            })
+        );
+
+        let before = v.clone();
+        let err = v.set_path("/a/b/c/d", json!(1)).unwrap_err();
+        assert_eq!(err.message(), "Cannot set `/c` in number at `/a/b`");
+        assert!(v.set_path("list[5]", json!(1)).is_err());
+        assert!(v.set_path("/new/x", json!(1)).is_err());
+        assert_eq!(v, before);
+
+        assert_eq!(v.set_path("", json!(1)).unwrap(), Some(before));
+        assert_eq!(v, json!(1));
+    }
+}
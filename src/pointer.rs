@@ -0,0 +1,124 @@
+use crate::value::{JSONError, JSONValue};
+
+// splits an RFC 6901 pointer such as `/a/b~1c/0` into unescaped tokens
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, JSONError> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    if !pointer.starts_with('/') {
+        return Err(JSONError::new(
+            format!("Pointer `{}` must start with `/`", pointer),
+            0,
+            0,
+        ));
+    }
+    let mut tokens = vec![];
+    for raw in pointer[1..].split('/') {
+        let mut token = "".to_string();
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            if c != '~' {
+                token.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('0') => token.push('~'),
+                Some('1') => token.push('/'),
+                _ => {
+                    return Err(JSONError::new(
+                        format!("Invalid escape in pointer `{}`", pointer),
+                        0,
+                        0,
+                    ))
+                }
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+pub fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+pub fn to_pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|t| format!("/{}", escape_token(t)))
+        .collect()
+}
+
+// array index tokens are plain decimals without leading zeros
+pub fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    token.parse::<usize>().ok()
+}
+
+impl JSONValue {
+    pub fn pointer(&self, pointer: &str) -> Option<&JSONValue> {
+        let tokens = parse_pointer(pointer).ok()?;
+        let mut target = self;
+        for token in &tokens {
+            target = match target {
+                JSONValue::Object(hm) => hm.get(token)?,
+                JSONValue::Array(vc) => vc.get(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JSONValue> {
+        let tokens = parse_pointer(pointer).ok()?;
+        let mut target = self;
+        for token in &tokens {
+            target = match target {
+                JSONValue::Object(hm) => hm.get_mut(token)?,
+                JSONValue::Array(vc) => vc.get_mut(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    #[test]
+    fn pointer_tokens() {
+        assert_eq!(parse_pointer("").unwrap(), Vec::<String>::new());
+        assert_eq!(parse_pointer("/a~1b/~0c/").unwrap(), vec!["a/b", "~c", ""]);
+        assert!(parse_pointer("a").is_err());
+        assert!(parse_pointer("/a~2").is_err());
+        assert_eq!(
+            to_pointer(&["a/b".to_string(), "~c".to_string()]),
+            "/a~1b/~0c"
+        );
+        assert_eq!(parse_index("01"), None);
+        assert_eq!(parse_index("10"), Some(10));
+    }
+
+    #[test]
+    fn pointer_lookup() {
+        let mut doc = JSONDocument::new();
+        let mut v = doc
+            .parse_string(r#"{ "a": { "b/c": [1, 2] } }"#.to_string())
+            .unwrap();
+        assert_eq!(v.pointer("/a/b~1c/1"), Some(&JSONValue::Number(2f64)));
+        assert_eq!(v.pointer("/a/b~1c/2"), None);
+        assert_eq!(v.pointer("/a/x"), None);
+        assert_eq!(v.pointer(""), Some(&v.clone()));
+
+        *v.pointer_mut("/a/b~1c/0").unwrap() = JSONValue::Null;
+        assert_eq!(v.pointer("/a/b~1c/0"), Some(&JSONValue::Null));
+    }
+}
@@ -2,6 +2,17 @@ use std::fs::File;
 
 use crate::buffer::{LineBuffer, StringIterator};
 
+// Relaxed grammars for hand-edited files. JSONC (VS Code style) adds comments
+// and trailing commas; JSON5 (https://json5.org) also adds single quotes,
+// identifier keys and more numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dialect {
+    #[default]
+    Json,
+    Jsonc,
+    Json5,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     Collon,
@@ -15,6 +26,8 @@ pub enum TokenKind {
     Null,
     False,
     True,
+    // bare words other than literals, JSON5 only
+    Identifier,
     Eof,
     Error,
 }
@@ -55,10 +68,13 @@ pub struct Scanner<'a> {
     pk: Option<char>,
     lin: usize,
     pos: usize,
+    dialect: Dialect,
+    // comments skipped since the last `take_comments`
+    comments: Vec<String>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn from_string(data: &'a String) -> Scanner<'a> {
+    pub fn from_string(data: &'a str) -> Scanner<'a> {
         Scanner {
             lines: LineBuffer::from_string(data),
             line: None,
@@ -66,6 +82,8 @@ impl<'a> Scanner<'a> {
             pk: None,
             lin: 0,
             pos: 0,
+            dialect: Dialect::Json,
+            comments: vec![],
         }
     }
 
@@ -77,19 +95,42 @@ impl<'a> Scanner<'a> {
             pk: None,
             lin: 0,
             pos: 0,
+            dialect: Dialect::Json,
+            comments: vec![],
         }
     }
 
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    // raw text of the comments before the current token
+    pub fn take_comments(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.comments)
+    }
+
     pub fn next_token(&mut self) -> Token {
         // first time: move to first line
-        if self.ch == None && self.pk == None {
+        if self.ch.is_none() && self.pk.is_none() {
             let _ = self.consume();
         }
 
+        let json5 = self.dialect == Dialect::Json5;
         while let Some(c) = self.ch {
-            if c.is_whitespace() {
+            if c.is_whitespace() || (json5 && c == '\u{feff}') {
                 self.consume();
                 continue;
+            } else if c == '/'
+                && self.dialect != Dialect::Json
+                && (self.pk == Some('/') || self.pk == Some('*'))
+            {
+                let (lin, pos) = (self.lin, self.pos);
+                match self.scan_comment() {
+                    Some(comment) => self.comments.push(comment),
+                    None => return Token::new(TokenKind::Error, "/*".to_string(), lin, pos),
+                }
+                continue;
             } else if c == ':' {
                 self.consume();
                 return Token::new(TokenKind::Collon, c.to_string(), self.lin, self.pos);
@@ -108,18 +149,26 @@ impl<'a> Scanner<'a> {
             } else if c == ']' {
                 self.consume();
                 return Token::new(TokenKind::RightBracket, c.to_string(), self.lin, self.pos);
-            } else if c == '"' {
+            } else if c == '"' || (json5 && c == '\'') {
                 let pos = self.pos;
-                let word = self.scan_str();
+                let word = self.scan_str(c);
                 return match word {
                     Some(w) => Token::new(TokenKind::String, w, self.lin, pos),
-                    None => Token::new(TokenKind::Error, "".to_string(), self.lin, pos),
+                    None => {
+                        let lin = self.lin;
+                        self.skip_str(c);
+                        Token::new(TokenKind::Error, "".to_string(), lin, pos)
+                    }
                 };
-            } else if c.is_digit(10) || c == '-' {
+            } else if json5 && (c.is_ascii_digit() || "+-.".contains(c)) {
+                let pos = self.pos;
+                let num = self.scan_num5();
+                return Token::new(TokenKind::Number, num, self.lin, pos);
+            } else if c.is_ascii_digit() || c == '-' {
                 let pos = self.pos;
                 let num = self.scan_num();
                 return Token::new(TokenKind::Number, num, self.lin, pos);
-            } else if c.is_alphabetic() {
+            } else if c.is_alphabetic() || (json5 && (c == '_' || c == '$')) {
                 let pos = self.pos;
                 let word = self.scan_word();
                 return match word {
@@ -127,6 +176,10 @@ impl<'a> Scanner<'a> {
                         "null" => Token::new(TokenKind::Null, w, self.lin, pos),
                         "true" => Token::new(TokenKind::True, w, self.lin, pos),
                         "false" => Token::new(TokenKind::False, w, self.lin, pos),
+                        "Infinity" | "NaN" if json5 => {
+                            Token::new(TokenKind::Number, w, self.lin, pos)
+                        }
+                        _ if json5 => Token::new(TokenKind::Identifier, w, self.lin, pos),
                         _ => Token::new(TokenKind::Error, w, self.lin, self.pos),
                     },
                     None => Token::new(TokenKind::Error, "".to_string(), self.lin, self.pos),
@@ -163,7 +216,7 @@ impl<'a> Scanner<'a> {
         }
 
         match self.ch {
-            Some(c) if c == '\n' => {
+            Some('\n') => {
                 self.pos = 1;
                 self.lin += 1;
             }
@@ -175,10 +228,7 @@ impl<'a> Scanner<'a> {
             Some(ref mut chrs) => match chrs.next() {
                 Some(c) => Some(c),
                 None => {
-                    self.line = match self.lines.next() {
-                        Some(line) => Some(StringIterator::new(line)),
-                        None => None,
-                    };
+                    self.line = self.lines.next().map(StringIterator::new);
                     match self.line {
                         Some(ref mut chrs) => chrs.next(),
                         None => None,
@@ -190,34 +240,133 @@ impl<'a> Scanner<'a> {
         self.ch
     }
 
-    fn scan_str(&mut self) -> Option<String> {
+    // a `//` or `/* */` comment with its delimiters; None when a block is
+    // left open
+    fn scan_comment(&mut self) -> Option<String> {
+        let block = self.pk == Some('*');
+        let mut text = if block { "/*" } else { "//" }.to_string();
+        self.consume();
+        self.consume();
+        loop {
+            match self.ch {
+                None if block => return None,
+                None => return Some(text.trim_end().to_string()),
+                Some('\n') if !block => return Some(text.trim_end().to_string()),
+                Some('*') if block && self.pk == Some('/') => {
+                    self.consume();
+                    self.consume();
+                    text.push_str("*/");
+                    return Some(text);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.consume();
+                }
+            }
+        }
+    }
+
+    // decodes escapes; raw control characters, unknown escapes and unpaired
+    // surrogates make the string invalid. JSON5 adds `\'`, `\v`, `\0`, `\x`,
+    // escaped line breaks and identity escapes.
+    fn scan_str(&mut self, quote: char) -> Option<String> {
+        let json5 = self.dialect == Dialect::Json5;
         let mut word = "".to_string();
-        let c = self.consume(); // consume opening "
-        if c == Some('"') {
-            // check if empty string
+        let mut c = self.consume(); // consume opening quote
+        loop {
+            match c? {
+                q if q == quote => {
+                    self.consume(); // consume closing quote
+                    return Some(word);
+                }
+                '\\' => {
+                    let e = self.consume()?;
+                    match e {
+                        '"' | '\\' | '/' => word.push(e),
+                        'b' => word.push('\u{8}'),
+                        'f' => word.push('\u{c}'),
+                        'n' => word.push('\n'),
+                        'r' => word.push('\r'),
+                        't' => word.push('\t'),
+                        'u' => {
+                            let hi = self.scan_hex(4)?;
+                            let code = if (0xD800..0xDC00).contains(&hi) {
+                                if self.consume()? != '\\' || self.consume()? != 'u' {
+                                    return None;
+                                }
+                                let lo = self.scan_hex(4)?;
+                                if !(0xDC00..0xE000).contains(&lo) {
+                                    return None;
+                                }
+                                0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+                            } else {
+                                hi
+                            };
+                            word.push(std::char::from_u32(code)?);
+                        }
+                        'v' if json5 => word.push('\u{b}'),
+                        'x' if json5 => word.push(std::char::from_u32(self.scan_hex(2)?)?),
+                        '0' if json5 && !self.pk.is_some_and(|p| p.is_ascii_digit()) => {
+                            word.push('\0')
+                        }
+                        '\n' | '\u{2028}' | '\u{2029}' if json5 => (),
+                        e if json5 && !e.is_ascii_digit() => word.push(e),
+                        _ => return None,
+                    }
+                }
+                ch if (ch as u32) < 0x20 => return None,
+                ch => word.push(ch),
+            }
+            c = self.consume();
+        }
+    }
+
+    // moves past the rest of an invalid string, up to its closing quote or
+    // the end of the line
+    fn skip_str(&mut self, quote: char) {
+        while let Some(c) = self.ch {
+            if c == '\n' {
+                return;
+            }
             self.consume();
-            return Some(word);
+            if c == quote {
+                return;
+            }
         }
+    }
 
-        while let Some(p) = self.pk {
-            let c = self.ch.unwrap();
-            if p == '"' && c != '\\' {
-                self.consume();
-                word.push(c);
-                self.consume(); // consume closing "
-                return Some(word);
+    fn scan_hex(&mut self, len: usize) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..len {
+            code = code * 16 + self.consume()?.to_digit(16)?;
+        }
+        Some(code)
+    }
+
+    // JSON5 numbers are validated by the parser: `+1`, `.5`, `5.`, `0x1F`,
+    // `-Infinity`, `NaN`
+    fn scan_num5(&mut self) -> String {
+        let mut num = "".to_string();
+        while let Some(c) = self.ch {
+            let hex = num.trim_start_matches(['+', '-']).starts_with("0x")
+                || num.trim_start_matches(['+', '-']).starts_with("0X");
+            let sign = (c == '+' || c == '-')
+                && (num.is_empty() || (!hex && (num.ends_with('e') || num.ends_with('E'))));
+            if !(c.is_ascii_alphanumeric() || c == '.' || sign) {
+                break;
             }
-            word.push(c);
+            num.push(c);
             self.consume();
         }
-        None
+        num
     }
 
     fn scan_word(&mut self) -> Option<String> {
+        let json5 = self.dialect == Dialect::Json5;
         let mut word = "".to_string();
         while let Some(p) = self.pk {
             let c = self.ch.unwrap();
-            if !p.is_alphabetic() {
+            if !(p.is_alphabetic() || (json5 && (p.is_alphanumeric() || p == '_' || p == '$'))) {
                 self.consume();
                 word.push(c);
                 return Some(word);
@@ -233,12 +382,14 @@ impl<'a> Scanner<'a> {
         let mut num = "".to_string();
 
         //scan num part
+        let mut fraction = false;
         while let Some(p) = self.pk {
             let c = self.ch.unwrap();
-            if p.is_digit(10) {
+            if p.is_ascii_digit() {
                 num.push(c);
                 self.consume();
-            } else if p == '.' {
+            } else if p == '.' || p == 'e' || p == 'E' {
+                fraction = p == '.';
                 num.push(c);
                 self.consume();
                 break;
@@ -250,19 +401,21 @@ impl<'a> Scanner<'a> {
         }
 
         //scan decimal part
-        while let Some(p) = self.pk {
-            let c = self.ch.unwrap();
-            if p.is_digit(10) {
-                num.push(c);
-                self.consume();
-            } else if p == 'e' {
-                num.push(c);
-                self.consume();
-                break;
-            } else {
-                num.push(c);
-                self.consume();
-                return num;
+        if fraction {
+            while let Some(p) = self.pk {
+                let c = self.ch.unwrap();
+                if p.is_ascii_digit() {
+                    num.push(c);
+                    self.consume();
+                } else if p == 'e' || p == 'E' {
+                    num.push(c);
+                    self.consume();
+                    break;
+                } else {
+                    num.push(c);
+                    self.consume();
+                    return num;
+                }
             }
         }
 
@@ -279,7 +432,7 @@ impl<'a> Scanner<'a> {
         };
         while let Some(p) = self.pk {
             let c = self.ch.unwrap();
-            if p.is_digit(10) {
+            if p.is_ascii_digit() {
                 num.push(c);
                 self.consume();
             } else {
@@ -0,0 +1,207 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::schema::regex::Regex;
+
+// Returns None for formats we do not know, which are only annotations.
+pub fn check_format(format: &str, s: &str) -> Option<bool> {
+    let valid = match format {
+        "date-time" => is_date_time(s),
+        "date" => is_date(s),
+        "time" => is_time(s),
+        "email" => is_email(s),
+        "hostname" => is_hostname(s),
+        "uuid" => is_uuid(s),
+        "ipv4" => s.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<Ipv6Addr>().is_ok(),
+        "uri" => is_uri(s),
+        "regex" => Regex::new(s).is_ok(),
+        _ => return None,
+    };
+    Some(valid)
+}
+
+fn digits(s: &str, len: usize) -> Option<u32> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// RFC 3339 full-date: 2020-02-29
+fn is_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 3 {
+        return false;
+    }
+    let (year, month, day) = match (
+        digits(parts[0], 4),
+        digits(parts[1], 2),
+        digits(parts[2], 2),
+    ) {
+        (Some(y), Some(m), Some(d)) => (y, m, d),
+        _ => return false,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    day >= 1 && day <= days
+}
+
+// RFC 3339 full-time: 23:59:60.25+01:00, the offset is mandatory
+fn is_time(s: &str) -> bool {
+    let upper = s.to_ascii_uppercase();
+    let (partial, offset) = match upper.find(['Z', '+', '-']) {
+        Some(i) => upper.split_at(i),
+        None => return false,
+    };
+    if offset != "Z" {
+        let sign_free = &offset[1..];
+        let hm: Vec<&str> = sign_free.split(':').collect();
+        match (
+            hm.len(),
+            hm.first().and_then(|h| digits(h, 2)),
+            hm.get(1).and_then(|m| digits(m, 2)),
+        ) {
+            (2, Some(h), Some(m)) if h < 24 && m < 60 => (),
+            _ => return false,
+        }
+    }
+    let (hms, fraction) = match partial.find('.') {
+        Some(i) => (&partial[..i], Some(&partial[i + 1..])),
+        None => (partial, None),
+    };
+    if let Some(f) = fraction {
+        if f.is_empty() || !f.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+    }
+    let parts: Vec<&str> = hms.split(':').collect();
+    if parts.len() != 3 {
+        return false;
+    }
+    match (
+        digits(parts[0], 2),
+        digits(parts[1], 2),
+        digits(parts[2], 2),
+    ) {
+        (Some(h), Some(m), Some(s)) => h < 24 && m < 60 && s <= 60,
+        _ => false,
+    }
+}
+
+fn is_date_time(s: &str) -> bool {
+    match s.find(['T', 't']) {
+        Some(i) => is_date(&s[..i]) && is_time(&s[i + 1..]),
+        None => false,
+    }
+}
+
+fn is_hostname(s: &str) -> bool {
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+fn is_email(s: &str) -> bool {
+    let (local, domain) = match s.rfind('@') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => return false,
+    };
+    let local_ok = !local.is_empty()
+        && local.len() <= 64
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+    let domain_ok = match domain.strip_prefix('[').and_then(|d| d.strip_suffix(']')) {
+        Some(literal) => match literal.strip_prefix("IPv6:") {
+            Some(v6) => v6.parse::<Ipv6Addr>().is_ok(),
+            None => literal.parse::<Ipv4Addr>().is_ok(),
+        },
+        None => is_hostname(domain),
+    };
+    local_ok && domain_ok
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip(lengths.iter())
+            .all(|(g, l)| g.len() == *l && g.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+// absolute URI: a scheme followed by `:` and no whitespace
+fn is_uri(s: &str) -> bool {
+    let scheme = match s.find(':') {
+        Some(i) => &s[..i],
+        None => return false,
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        && !s.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let valid = [
+            ("date-time", "2020-02-29T23:59:60.123Z"),
+            ("date-time", "1990-12-31t15:59:59-08:00"),
+            ("date", "2024-02-29"),
+            ("time", "08:30:00+02:00"),
+            ("email", "jhon.doe+tag@example.com"),
+            ("email", "a@[127.0.0.1]"),
+            ("uuid", "123e4567-e89b-12d3-a456-426614174000"),
+            ("ipv4", "192.168.0.1"),
+            ("ipv6", "::1"),
+            ("ipv6", "2001:db8::8a2e:370:7334"),
+            ("hostname", "api.example.com"),
+            ("uri", "https://example.com/a?b=c"),
+        ];
+        for (format, s) in valid.iter() {
+            assert_eq!(check_format(format, s), Some(true), "{} {}", format, s);
+        }
+
+        let invalid = [
+            ("date-time", "2021-02-29T00:00:00Z"),
+            ("date-time", "2020-01-01 00:00:00Z"),
+            ("date", "2020-13-01"),
+            ("time", "08:30:00"),
+            ("email", "jhon@"),
+            ("email", "jhon..doe@example.com"),
+            ("uuid", "123e4567e89b12d3a456426614174000"),
+            ("ipv4", "256.1.1.1"),
+            ("ipv4", "01.1.1.1"),
+            ("ipv6", "12345::"),
+            ("hostname", "-bad.example"),
+            ("uri", "no scheme"),
+        ];
+        for (format, s) in invalid.iter() {
+            assert_eq!(check_format(format, s), Some(false), "{} {}", format, s);
+        }
+        assert_eq!(check_format("color", "red"), None);
+    }
+}
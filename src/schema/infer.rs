@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::schema::format::check_format;
+use crate::value::JSONValue;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+const FORMATS: [&str; 6] = ["date-time", "date", "time", "uuid", "email", "ipv4"];
+
+#[derive(Debug, Clone)]
+pub struct InferOptions {
+    // strings become an `enum` when they take at most this many distinct
+    // values and at least one of them repeats
+    pub enum_threshold: usize,
+    // share of objects a property must appear in to be `required`
+    pub required_ratio: f64,
+    pub formats: bool,
+}
+
+impl InferOptions {
+    pub fn new() -> Self {
+        InferOptions {
+            enum_threshold: 5,
+            required_ratio: 1f64,
+            formats: true,
+        }
+    }
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        InferOptions::new()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    nulls: usize,
+    booleans: usize,
+    numbers: Option<Numbers>,
+    strings: Option<Strings>,
+    arrays: Option<Box<Shape>>,
+    objects: Option<Objects>,
+}
+
+#[derive(Debug, Clone)]
+struct Numbers {
+    min: f64,
+    max: f64,
+    integer: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Strings {
+    count: usize,
+    // dropped once there are too many distinct values to be an enum
+    values: Option<BTreeSet<String>>,
+    formats: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Objects {
+    count: usize,
+    properties: BTreeMap<String, (usize, Shape)>,
+}
+
+// Accumulates samples one at a time, so large NDJSON inputs need not be
+// held in memory.
+#[derive(Debug, Clone)]
+pub struct SchemaInferrer {
+    options: InferOptions,
+    shape: Shape,
+}
+
+impl SchemaInferrer {
+    pub fn new(options: InferOptions) -> Self {
+        SchemaInferrer {
+            options,
+            shape: Shape::default(),
+        }
+    }
+
+    pub fn add(&mut self, sample: &JSONValue) {
+        self.shape.add(sample, &self.options);
+    }
+
+    pub fn schema(&self) -> JSONValue {
+        let mut schema = self.shape.schema(&self.options);
+        if let JSONValue::Object(hm) = &mut schema {
+            hm.insert("$schema".to_string(), JSONValue::String(DRAFT.to_string()));
+        }
+        schema
+    }
+}
+
+pub fn infer_schema(samples: &[JSONValue]) -> JSONValue {
+    let mut inferrer = SchemaInferrer::new(InferOptions::new());
+    for sample in samples {
+        inferrer.add(sample);
+    }
+    inferrer.schema()
+}
+
+impl Shape {
+    fn add(&mut self, v: &JSONValue, options: &InferOptions) {
+        match v {
+            JSONValue::Null => self.nulls += 1,
+            JSONValue::Boolean(_) => self.booleans += 1,
+            JSONValue::Number(n) => {
+                let n = *n;
+                let numbers = self.numbers.get_or_insert(Numbers {
+                    min: n,
+                    max: n,
+                    integer: true,
+                });
+                numbers.min = numbers.min.min(n);
+                numbers.max = numbers.max.max(n);
+                numbers.integer &= n.fract() == 0f64;
+            }
+            JSONValue::String(s) => {
+                let strings = self.strings.get_or_insert_with(|| Strings {
+                    count: 0,
+                    values: Some(BTreeSet::new()),
+                    formats: if options.formats {
+                        FORMATS.to_vec()
+                    } else {
+                        vec![]
+                    },
+                });
+                strings.count += 1;
+                if let Some(values) = &mut strings.values {
+                    values.insert(s.clone());
+                    if values.len() > options.enum_threshold {
+                        strings.values = None;
+                    }
+                }
+                strings.formats.retain(|f| check_format(f, s) == Some(true));
+            }
+            JSONValue::Array(vc) => {
+                let items = self.arrays.get_or_insert_with(Box::default);
+                for item in vc {
+                    items.add(item, options);
+                }
+            }
+            JSONValue::Object(hm) => {
+                let objects = self.objects.get_or_insert_with(Objects::default);
+                objects.count += 1;
+                for (k, v) in hm {
+                    let entry = objects.properties.entry(k.clone()).or_default();
+                    entry.0 += 1;
+                    entry.1.add(v, options);
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.nulls == 0
+            && self.booleans == 0
+            && self.numbers.is_none()
+            && self.strings.is_none()
+            && self.arrays.is_none()
+            && self.objects.is_none()
+    }
+
+    // all seen types share one schema object: the keywords of each type
+    // only apply to instances of that type, so they cannot conflict. `enum`
+    // applies to every instance and is only used when all of them are strings
+    fn schema(&self, options: &InferOptions) -> JSONValue {
+        let only_strings = self.nulls == 0
+            && self.booleans == 0
+            && self.numbers.is_none()
+            && self.arrays.is_none()
+            && self.objects.is_none();
+        let mut types = vec![];
+        let mut hm = HashMap::new();
+        if self.nulls > 0 {
+            types.push("null");
+        }
+        if self.booleans > 0 {
+            types.push("boolean");
+        }
+        if let Some(numbers) = &self.numbers {
+            types.push(if numbers.integer { "integer" } else { "number" });
+            hm.insert("minimum".to_string(), JSONValue::Number(numbers.min));
+            hm.insert("maximum".to_string(), JSONValue::Number(numbers.max));
+        }
+        if let Some(strings) = &self.strings {
+            types.push("string");
+            match &strings.values {
+                Some(values) if only_strings && strings.count > values.len() => {
+                    let values = values.iter().cloned().map(JSONValue::String).collect();
+                    hm.insert("enum".to_string(), JSONValue::Array(values));
+                }
+                _ => {
+                    if let Some(format) = strings.formats.first() {
+                        hm.insert("format".to_string(), JSONValue::String(format.to_string()));
+                    }
+                }
+            }
+        }
+        if let Some(items) = &self.arrays {
+            types.push("array");
+            if !items.is_empty() {
+                hm.insert("items".to_string(), items.schema(options));
+            }
+        }
+        if let Some(objects) = &self.objects {
+            types.push("object");
+            let mut properties = HashMap::new();
+            let mut required = vec![];
+            for (k, (count, shape)) in &objects.properties {
+                properties.insert(k.clone(), shape.schema(options));
+                if *count as f64 >= objects.count as f64 * options.required_ratio {
+                    required.push(JSONValue::String(k.clone()));
+                }
+            }
+            hm.insert("properties".to_string(), JSONValue::Object(properties));
+            if !required.is_empty() {
+                hm.insert("required".to_string(), JSONValue::Array(required));
+            }
+        }
+
+        // an enum already pins the type down
+        if hm.contains_key("enum") && types.len() == 1 {
+            return JSONValue::Object(hm);
+        }
+        match types.len() {
+            0 => (),
+            1 => {
+                hm.insert("type".to_string(), JSONValue::String(types[0].to_string()));
+            }
+            _ => {
+                let types = types
+                    .iter()
+                    .map(|t| JSONValue::String(t.to_string()))
+                    .collect();
+                hm.insert("type".to_string(), JSONValue::Array(types));
+            }
+        }
+        JSONValue::Object(hm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::JSONSchema;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn infer_from_samples() {
+        let samples: Vec<JSONValue> = [
+            r#"{ "id": 1, "level": "info", "at": "2024-01-01T10:00:00Z", "tags": ["a"], "user": { "name": "x" } }"#,
+            r#"{ "id": 2, "level": "warn", "at": "2024-01-01T10:00:01Z", "tags": [], "user": null }"#,
+            r#"{ "id": 3.5, "level": "info", "at": "2024-01-01T10:00:02Z", "tags": ["b", 1], "extra": true }"#,
+        ]
+        .iter()
+        .map(|s| json(s))
+        .collect();
+        let schema = infer_schema(&samples);
+        let properties = schema.pointer("/properties").unwrap();
+
+        assert_eq!(schema.pointer("/type"), Some(&json(r#""object""#)));
+        assert_eq!(
+            properties.pointer("/id"),
+            Some(&json(
+                r#"{ "type": "number", "minimum": 1, "maximum": 3.5 }"#
+            ))
+        );
+        assert_eq!(
+            properties.pointer("/level"),
+            Some(&json(r#"{ "enum": ["info", "warn"] }"#))
+        );
+        assert_eq!(
+            properties.pointer("/at"),
+            Some(&json(r#"{ "type": "string", "format": "date-time" }"#))
+        );
+        assert_eq!(properties.pointer("/tags/type"), Some(&json(r#""array""#)));
+        assert_eq!(
+            properties.pointer("/tags/items/type"),
+            Some(&json(r#"["integer", "string"]"#))
+        );
+        assert_eq!(
+            properties.pointer("/user/type"),
+            Some(&json(r#"["null", "object"]"#))
+        );
+        assert_eq!(
+            schema.pointer("/required"),
+            Some(&json(r#"["at", "id", "level", "tags"]"#))
+        );
+
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        assert!(samples.iter().all(|s| compiled.is_valid(s)));
+        assert!(!compiled.is_valid(&json(
+            r#"{ "id": 0, "level": "info", "at": "x", "tags": [] }"#
+        )));
+    }
+
+    #[test]
+    fn infer_validates_samples() {
+        let sets = [
+            r#"["a", "a", "b", null, 3]"#,
+            r#"["x", "x", 1.5, "y", "y"]"#,
+            r#"[{ "k": "a" }, { "k": "a" }, { "k": null }, { "k": [1, "b", "b"] }]"#,
+            r#"[["a", "a", true], "2024-01-01", null]"#,
+        ];
+        for set in sets.iter() {
+            let samples = match json(set) {
+                JSONValue::Array(samples) => samples,
+                _ => unreachable!(),
+            };
+            let schema = infer_schema(&samples);
+            let compiled = JSONSchema::compile(&schema).unwrap();
+            for sample in &samples {
+                assert!(compiled.is_valid(sample), "{} against {}", sample, schema);
+            }
+        }
+    }
+
+    #[test]
+    fn infer_options() {
+        let mut options = InferOptions::new();
+        options.required_ratio = 0.5;
+        options.enum_threshold = 1;
+        options.formats = false;
+        let mut inferrer = SchemaInferrer::new(options);
+        for text in [
+            r#"{ "a": "x", "b": 1 }"#,
+            r#"{ "a": "y" }"#,
+            r#"{ "a": "y" }"#,
+        ]
+        .iter()
+        {
+            inferrer.add(&json(text));
+        }
+        let schema = inferrer.schema();
+        assert_eq!(schema.pointer("/required"), Some(&json(r#"["a"]"#)));
+        assert_eq!(
+            schema.pointer("/properties/a"),
+            Some(&json(r#"{ "type": "string" }"#))
+        );
+        assert_eq!(infer_schema(&[]).pointer("/type"), None);
+    }
+}
@@ -0,0 +1,838 @@
+mod format;
+mod infer;
+mod regex;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::pointer::{escape_token, parse_pointer};
+use crate::source_map::{SourceMap, Span};
+use crate::value::{JSONError, JSONValue};
+use format::check_format;
+pub use infer::{infer_schema, InferOptions, SchemaInferrer};
+use regex::Regex;
+
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub instance_path: String,
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    // where the failing value (or its closest parent) is in the source
+    pub fn span(&self, map: &SourceMap) -> Option<Span> {
+        map.locate(&self.instance_path)
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "/"
+        } else {
+            &self.instance_path
+        };
+        write!(
+            f,
+            "{}: {} (schema {})",
+            path, self.message, self.schema_path
+        )
+    }
+}
+
+// A JSON Schema (draft 2020-12) ready to validate instances. References are
+// resolved within the schema document only: `#`, `#/json/pointer` and
+// `#anchor` forms.
+#[derive(Debug, Clone)]
+pub struct JSONSchema {
+    root: JSONValue,
+    regexes: HashMap<String, Regex>,
+    anchors: HashMap<String, String>,
+}
+
+impl JSONSchema {
+    pub fn compile(schema: &JSONValue) -> Result<JSONSchema, JSONError> {
+        let mut compiled = JSONSchema {
+            root: schema.clone(),
+            regexes: HashMap::new(),
+            anchors: HashMap::new(),
+        };
+        let mut refs = vec![];
+        compiled.prepare(schema, "", &mut refs)?;
+        for (at, reference) in refs {
+            if compiled.resolve(&reference).is_none() {
+                return Err(JSONError::new(
+                    format!("Cannot resolve $ref `{}` at `{}`", reference, at),
+                    0,
+                    0,
+                ));
+            }
+        }
+        Ok(compiled)
+    }
+
+    pub fn validate(&self, instance: &JSONValue) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        self.check(&self.root, instance, "", "", 0, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &JSONValue) -> bool {
+        self.validate(instance).is_ok()
+    }
+
+    // compiles patterns, records anchors and collects refs to resolve
+    fn prepare(
+        &mut self,
+        schema: &JSONValue,
+        path: &str,
+        refs: &mut Vec<(String, String)>,
+    ) -> Result<(), JSONError> {
+        match schema {
+            JSONValue::Object(hm) => {
+                for (k, v) in hm {
+                    let child = format!("{}/{}", path, escape_token(k));
+                    match (k.as_str(), v) {
+                        ("pattern", JSONValue::String(p)) => self.compile_regex(p, &child)?,
+                        ("patternProperties", JSONValue::Object(patterns)) => {
+                            for p in patterns.keys() {
+                                self.compile_regex(p, &child)?;
+                            }
+                        }
+                        ("$anchor", JSONValue::String(a)) => {
+                            self.anchors.insert(a.clone(), path.to_string());
+                        }
+                        ("$ref", JSONValue::String(r)) => refs.push((path.to_string(), r.clone())),
+                        // enum and const hold instances, not subschemas
+                        ("enum", _) | ("const", _) => continue,
+                        _ => (),
+                    }
+                    self.prepare(v, &child, refs)?;
+                }
+            }
+            JSONValue::Array(vc) => {
+                for (i, v) in vc.iter().enumerate() {
+                    self.prepare(v, &format!("{}/{}", path, i), refs)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn compile_regex(&mut self, pattern: &str, path: &str) -> Result<(), JSONError> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            JSONError::new(format!("{} at `{}`", e.message(), path), e.line(), e.col())
+        })?;
+        self.regexes.insert(pattern.to_string(), regex);
+        Ok(())
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&JSONValue> {
+        let fragment = reference.strip_prefix('#')?;
+        if fragment.is_empty() || fragment.starts_with('/') {
+            let pointer = percent_decode(fragment)?;
+            parse_pointer(&pointer).ok()?;
+            return self.root.pointer(&pointer);
+        }
+        let path = self.anchors.get(fragment)?;
+        self.root.pointer(path)
+    }
+
+    fn matches(&self, pattern: &str, s: &str) -> Result<bool, JSONError> {
+        match self.regexes.get(pattern) {
+            Some(regex) => regex.is_match(s),
+            None => Regex::new(pattern).map_or(Ok(false), |r| r.is_match(s)),
+        }
+    }
+
+    fn valid(&self, schema: &JSONValue, instance: &JSONValue, depth: usize) -> bool {
+        let mut errors = vec![];
+        self.check(schema, instance, "", "", depth, &mut errors);
+        errors.is_empty()
+    }
+
+    fn check(
+        &self,
+        schema: &JSONValue,
+        instance: &JSONValue,
+        ipath: &str,
+        spath: &str,
+        depth: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let hm = match schema {
+            JSONValue::Boolean(true) => return,
+            JSONValue::Boolean(false) => {
+                errors.push(ValidationError {
+                    instance_path: ipath.to_string(),
+                    schema_path: spath.to_string(),
+                    message: "No value is allowed here".to_string(),
+                });
+                return;
+            }
+            JSONValue::Object(hm) => hm,
+            _ => return,
+        };
+        if depth > MAX_DEPTH {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: spath.to_string(),
+                message: "Maximum schema depth exceeded".to_string(),
+            });
+            return;
+        }
+
+        if let Some(JSONValue::String(reference)) = hm.get("$ref") {
+            if let Some(target) = self.resolve(reference) {
+                let child = format!("{}/$ref", spath);
+                self.check(target, instance, ipath, &child, depth + 1, errors);
+            }
+        }
+        let mut fail = |keyword: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: format!("{}/{}", spath, keyword),
+                message,
+            })
+        };
+
+        if let Some(t) = hm.get("type") {
+            let allowed: Vec<&str> = match t {
+                JSONValue::String(s) => vec![s.as_str()],
+                JSONValue::Array(vc) => vc
+                    .iter()
+                    .filter_map(|v| match v {
+                        JSONValue::String(s) => Some(s.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            if !allowed.iter().any(|t| has_type(instance, t)) {
+                fail(
+                    "type",
+                    format!(
+                        "{} is not of type {}",
+                        instance.type_name(),
+                        allowed.join(", ")
+                    ),
+                );
+            }
+        }
+        if let Some(JSONValue::Array(options)) = hm.get("enum") {
+            if !options.contains(instance) {
+                fail(
+                    "enum",
+                    format!("{} is not one of the allowed values", instance),
+                );
+            }
+        }
+        if let Some(expected) = hm.get("const") {
+            if expected != instance {
+                fail(
+                    "const",
+                    format!("{} is not equal to {}", instance, expected),
+                );
+            }
+        }
+
+        match instance {
+            JSONValue::Number(n) => check_number(hm, *n, &mut fail),
+            JSONValue::String(s) => {
+                let len = s.chars().count();
+                if let Some(min) = number(hm, "minLength") {
+                    if (len as f64) < min {
+                        fail(
+                            "minLength",
+                            format!("String is shorter than {} characters", min),
+                        );
+                    }
+                }
+                if let Some(max) = number(hm, "maxLength") {
+                    if (len as f64) > max {
+                        fail(
+                            "maxLength",
+                            format!("String is longer than {} characters", max),
+                        );
+                    }
+                }
+                if let Some(JSONValue::String(pattern)) = hm.get("pattern") {
+                    match self.matches(pattern, s) {
+                        Ok(true) => (),
+                        Ok(false) => {
+                            fail("pattern", format!("String does not match `{}`", pattern))
+                        }
+                        Err(e) => fail("pattern", format!("{}: `{}`", e.message(), pattern)),
+                    }
+                }
+                if let Some(JSONValue::String(format)) = hm.get("format") {
+                    if check_format(format, s) == Some(false) {
+                        fail("format", format!("String is not a valid {}", format));
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        if let JSONValue::Array(items) = instance {
+            self.check_array(hm, items, ipath, spath, depth, errors);
+        }
+        if let JSONValue::Object(members) = instance {
+            self.check_object(hm, members, ipath, spath, depth, errors);
+        }
+        self.check_combinators(hm, instance, ipath, spath, depth, errors);
+    }
+
+    fn check_array(
+        &self,
+        hm: &HashMap<String, JSONValue>,
+        items: &[JSONValue],
+        ipath: &str,
+        spath: &str,
+        depth: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let at = |i: usize| format!("{}/{}", ipath, i);
+        let keyword = |k: &str| format!("{}/{}", spath, k);
+        let fail = |errors: &mut Vec<ValidationError>, k: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: keyword(k),
+                message,
+            })
+        };
+
+        if let Some(min) = number(hm, "minItems") {
+            if (items.len() as f64) < min {
+                fail(
+                    errors,
+                    "minItems",
+                    format!("Array has fewer than {} items", min),
+                );
+            }
+        }
+        if let Some(max) = number(hm, "maxItems") {
+            if (items.len() as f64) > max {
+                fail(
+                    errors,
+                    "maxItems",
+                    format!("Array has more than {} items", max),
+                );
+            }
+        }
+        if let Some(JSONValue::Boolean(true)) = hm.get("uniqueItems") {
+            let duplicate = (0..items.len()).any(|i| items[i + 1..].contains(&items[i]));
+            if duplicate {
+                fail(
+                    errors,
+                    "uniqueItems",
+                    "Array items are not unique".to_string(),
+                );
+            }
+        }
+
+        let mut prefix = 0;
+        if let Some(JSONValue::Array(schemas)) = hm.get("prefixItems") {
+            prefix = schemas.len();
+            for (i, (item, schema)) in items.iter().zip(schemas.iter()).enumerate() {
+                let child = format!("{}/{}", keyword("prefixItems"), i);
+                self.check(schema, item, &at(i), &child, depth + 1, errors);
+            }
+        }
+        if let Some(schema) = hm.get("items") {
+            for (i, item) in items.iter().enumerate().skip(prefix) {
+                self.check(schema, item, &at(i), &keyword("items"), depth + 1, errors);
+            }
+        }
+
+        if let Some(schema) = hm.get("contains") {
+            let count = items
+                .iter()
+                .filter(|item| self.valid(schema, item, depth + 1))
+                .count() as f64;
+            let min = number(hm, "minContains").unwrap_or(1f64);
+            if count < min {
+                fail(
+                    errors,
+                    "contains",
+                    format!(
+                        "Array contains {} matching items, expected at least {}",
+                        count, min
+                    ),
+                );
+            }
+            if let Some(max) = number(hm, "maxContains") {
+                if count > max {
+                    fail(
+                        errors,
+                        "maxContains",
+                        format!(
+                            "Array contains {} matching items, expected at most {}",
+                            count, max
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        hm: &HashMap<String, JSONValue>,
+        members: &HashMap<String, JSONValue>,
+        ipath: &str,
+        spath: &str,
+        depth: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let at = |k: &str| format!("{}/{}", ipath, escape_token(k));
+        let keyword = |k: &str| format!("{}/{}", spath, k);
+        let fail = |errors: &mut Vec<ValidationError>, k: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: keyword(k),
+                message,
+            })
+        };
+
+        if let Some(min) = number(hm, "minProperties") {
+            if (members.len() as f64) < min {
+                fail(
+                    errors,
+                    "minProperties",
+                    format!("Object has fewer than {} properties", min),
+                );
+            }
+        }
+        if let Some(max) = number(hm, "maxProperties") {
+            if (members.len() as f64) > max {
+                fail(
+                    errors,
+                    "maxProperties",
+                    format!("Object has more than {} properties", max),
+                );
+            }
+        }
+        if let Some(JSONValue::Array(required)) = hm.get("required") {
+            for name in required {
+                if let JSONValue::String(name) = name {
+                    if !members.contains_key(name) {
+                        fail(
+                            errors,
+                            "required",
+                            format!("Missing required property `{}`", name),
+                        );
+                    }
+                }
+            }
+        }
+        if let Some(JSONValue::Object(dependents)) = hm.get("dependentRequired") {
+            for (name, required) in dependents {
+                if !members.contains_key(name) {
+                    continue;
+                }
+                if let JSONValue::Array(required) = required {
+                    for other in required {
+                        if let JSONValue::String(other) = other {
+                            if !members.contains_key(other) {
+                                fail(
+                                    errors,
+                                    "dependentRequired",
+                                    format!("Property `{}` requires property `{}`", name, other),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(JSONValue::Object(dependents)) = hm.get("dependentSchemas") {
+            for (name, schema) in dependents {
+                if members.contains_key(name) {
+                    let child = format!("{}/{}", keyword("dependentSchemas"), escape_token(name));
+                    self.check(
+                        schema,
+                        &JSONValue::Object(members.clone()),
+                        ipath,
+                        &child,
+                        depth + 1,
+                        errors,
+                    );
+                }
+            }
+        }
+        if let Some(schema) = hm.get("propertyNames") {
+            for name in members.keys() {
+                let value = JSONValue::String(name.clone());
+                self.check(
+                    schema,
+                    &value,
+                    &at(name),
+                    &keyword("propertyNames"),
+                    depth + 1,
+                    errors,
+                );
+            }
+        }
+
+        let properties = match hm.get("properties") {
+            Some(JSONValue::Object(p)) => Some(p),
+            _ => None,
+        };
+        let patterns = match hm.get("patternProperties") {
+            Some(JSONValue::Object(p)) => Some(p),
+            _ => None,
+        };
+        let mut names: Vec<&String> = members.keys().collect();
+        names.sort();
+        for name in names {
+            let value = &members[name];
+            let mut evaluated = false;
+            if let Some(schema) = properties.and_then(|p| p.get(name)) {
+                evaluated = true;
+                let child = format!("{}/{}", keyword("properties"), escape_token(name));
+                self.check(schema, value, &at(name), &child, depth + 1, errors);
+            }
+            for (pattern, schema) in patterns.into_iter().flatten() {
+                let matched = match self.matches(pattern, name) {
+                    Ok(matched) => matched,
+                    Err(e) => {
+                        let message = format!("{}: `{}`", e.message(), pattern);
+                        fail(errors, "patternProperties", message);
+                        continue;
+                    }
+                };
+                if matched {
+                    evaluated = true;
+                    let child =
+                        format!("{}/{}", keyword("patternProperties"), escape_token(pattern));
+                    self.check(schema, value, &at(name), &child, depth + 1, errors);
+                }
+            }
+            if !evaluated {
+                if let Some(schema) = hm.get("additionalProperties") {
+                    let before = errors.len();
+                    self.check(
+                        schema,
+                        value,
+                        &at(name),
+                        &keyword("additionalProperties"),
+                        depth + 1,
+                        errors,
+                    );
+                    if *schema == JSONValue::Boolean(false) && errors.len() > before {
+                        errors.truncate(before);
+                        fail(
+                            errors,
+                            "additionalProperties",
+                            format!("Additional property `{}` is not allowed", name),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_combinators(
+        &self,
+        hm: &HashMap<String, JSONValue>,
+        instance: &JSONValue,
+        ipath: &str,
+        spath: &str,
+        depth: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let keyword = |k: &str| format!("{}/{}", spath, k);
+        let fail = |errors: &mut Vec<ValidationError>, k: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: keyword(k),
+                message,
+            })
+        };
+
+        if let Some(JSONValue::Array(schemas)) = hm.get("allOf") {
+            for (i, schema) in schemas.iter().enumerate() {
+                let child = format!("{}/{}", keyword("allOf"), i);
+                self.check(schema, instance, ipath, &child, depth + 1, errors);
+            }
+        }
+        if let Some(JSONValue::Array(schemas)) = hm.get("anyOf") {
+            if !schemas.iter().any(|s| self.valid(s, instance, depth + 1)) {
+                fail(
+                    errors,
+                    "anyOf",
+                    "Value does not match any of the schemas".to_string(),
+                );
+            }
+        }
+        if let Some(JSONValue::Array(schemas)) = hm.get("oneOf") {
+            let count = schemas
+                .iter()
+                .filter(|s| self.valid(s, instance, depth + 1))
+                .count();
+            if count != 1 {
+                fail(
+                    errors,
+                    "oneOf",
+                    format!("Value matches {} schemas, expected exactly one", count),
+                );
+            }
+        }
+        if let Some(schema) = hm.get("not") {
+            if self.valid(schema, instance, depth + 1) {
+                fail(errors, "not", "Value must not match the schema".to_string());
+            }
+        }
+        if let Some(condition) = hm.get("if") {
+            let (branch, schema) = if self.valid(condition, instance, depth + 1) {
+                ("then", hm.get("then"))
+            } else {
+                ("else", hm.get("else"))
+            };
+            if let Some(schema) = schema {
+                self.check(schema, instance, ipath, &keyword(branch), depth + 1, errors);
+            }
+        }
+    }
+}
+
+fn check_number(hm: &HashMap<String, JSONValue>, n: f64, fail: &mut dyn FnMut(&str, String)) {
+    if let Some(m) = number(hm, "multipleOf") {
+        let q = n / m;
+        if m <= 0f64 || (q - q.round()).abs() > 1e-9 {
+            fail("multipleOf", format!("{} is not a multiple of {}", n, m));
+        }
+    }
+    if let Some(min) = number(hm, "minimum") {
+        if n < min {
+            fail(
+                "minimum",
+                format!("{} is less than the minimum of {}", n, min),
+            );
+        }
+    }
+    if let Some(max) = number(hm, "maximum") {
+        if n > max {
+            fail(
+                "maximum",
+                format!("{} is greater than the maximum of {}", n, max),
+            );
+        }
+    }
+    if let Some(min) = number(hm, "exclusiveMinimum") {
+        if n <= min {
+            fail(
+                "exclusiveMinimum",
+                format!(
+                    "{} is less than or equal to the exclusive minimum of {}",
+                    n, min
+                ),
+            );
+        }
+    }
+    if let Some(max) = number(hm, "exclusiveMaximum") {
+        if n >= max {
+            fail(
+                "exclusiveMaximum",
+                format!(
+                    "{} is greater than or equal to the exclusive maximum of {}",
+                    n, max
+                ),
+            );
+        }
+    }
+}
+
+fn number(hm: &HashMap<String, JSONValue>, keyword: &str) -> Option<f64> {
+    match hm.get(keyword) {
+        Some(JSONValue::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+fn has_type(instance: &JSONValue, t: &str) -> bool {
+    match (t, instance) {
+        ("integer", JSONValue::Number(n)) => n.fract() == 0f64,
+        (t, v) => v.type_name() == t,
+    }
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    fn errors(schema: &str, instance: &str) -> Vec<(String, String)> {
+        let schema = JSONSchema::compile(&json(schema)).unwrap();
+        match schema.validate(&json(instance)) {
+            Ok(()) => vec![],
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| (e.instance_path, e.schema_path))
+                .collect(),
+        }
+    }
+
+    fn paths(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(i, s)| (i.to_string(), s.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn object_validation() {
+        let schema = r#"{
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 2 },
+                "age": { "type": "integer", "minimum": 0, "exclusiveMaximum": 150 },
+                "email": { "type": "string", "format": "email" },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] }, "uniqueItems": true }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        }"#;
+        assert!(errors(schema, r#"{ "name": "jo", "age": 3, "tags": ["a", "b"] }"#).is_empty());
+        assert_eq!(
+            errors(
+                schema,
+                r#"{ "name": "j", "age": 3.5, "email": "nope", "tags": ["a", "c", "a"], "x": 1 }"#
+            ),
+            paths(&[
+                ("/age", "/properties/age/type"),
+                ("/email", "/properties/email/format"),
+                ("/name", "/properties/name/minLength"),
+                ("/tags", "/properties/tags/uniqueItems"),
+                ("/tags/1", "/properties/tags/items/enum"),
+                ("", "/additionalProperties"),
+            ])
+        );
+        assert_eq!(
+            errors(schema, r#"{ "age": 200 }"#),
+            paths(&[
+                ("", "/required"),
+                ("/age", "/properties/age/exclusiveMaximum"),
+            ])
+        );
+    }
+
+    #[test]
+    fn arrays_and_patterns() {
+        let schema = r#"{
+            "prefixItems": [{ "type": "string", "pattern": "^[A-Z]{2}$" }, { "type": "number" }],
+            "items": { "type": "boolean" },
+            "contains": { "const": true },
+            "maxItems": 4
+        }"#;
+        assert!(errors(schema, r#"["FR", 1, false, true]"#).is_empty());
+        assert_eq!(
+            errors(schema, r#"["fr", 1, false, false, 3]"#),
+            paths(&[
+                ("", "/maxItems"),
+                ("/0", "/prefixItems/0/pattern"),
+                ("/4", "/items/type"),
+                ("", "/contains"),
+            ])
+        );
+        let patterned = r#"{ "patternProperties": { "^x-": { "type": "string" } }, "additionalProperties": { "type": "number" } }"#;
+        assert_eq!(
+            errors(patterned, r#"{ "x-a": 1, "b": 2, "c": "3" }"#),
+            paths(&[
+                ("/c", "/additionalProperties/type"),
+                ("/x-a", "/patternProperties/^x-/type"),
+            ])
+        );
+    }
+
+    #[test]
+    fn refs_and_combinators() {
+        let schema = r##"{
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": { "children": { "type": "array", "items": { "$ref": "#/$defs/node" } } },
+                    "required": ["id"]
+                },
+                "positive": { "$anchor": "pos", "type": "number", "minimum": 1 }
+            },
+            "type": "object",
+            "properties": {
+                "tree": { "$ref": "#/$defs/node" },
+                "count": { "$ref": "#pos" },
+                "kind": { "oneOf": [{ "const": "a" }, { "type": "string", "maxLength": 1 }] },
+                "value": { "anyOf": [{ "type": "null" }, { "type": "number" }], "not": { "const": 0 } },
+                "mode": {
+                    "if": { "const": "fast" },
+                    "then": true,
+                    "else": { "enum": ["slow", "safe"] }
+                }
+            }
+        }"##;
+        assert!(errors(
+            schema,
+            r#"{ "tree": { "id": 1, "children": [{ "id": 2 }] }, "count": 3, "kind": "b", "value": null, "mode": "fast" }"#
+        )
+        .is_empty());
+        assert_eq!(
+            errors(
+                schema,
+                r#"{ "tree": { "id": 1, "children": [{}] }, "count": 0, "kind": "a", "value": 0, "mode": "x" }"#
+            ),
+            paths(&[
+                ("/count", "/properties/count/$ref/minimum"),
+                ("/kind", "/properties/kind/oneOf"),
+                ("/mode", "/properties/mode/else/enum"),
+                (
+                    "/tree/children/0",
+                    "/properties/tree/$ref/properties/children/items/$ref/required"
+                ),
+                ("/value", "/properties/value/not"),
+            ])
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        assert!(JSONSchema::compile(&json(r##"{ "$ref": "#/$defs/missing" }"##)).is_err());
+        assert!(JSONSchema::compile(&json(r#"{ "$ref": "other.json" }"#)).is_err());
+        assert!(JSONSchema::compile(&json(r#"{ "pattern": "(" }"#)).is_err());
+        assert!(JSONSchema::compile(&json(r#"{ "enum": [{ "pattern": "(" }] }"#)).is_ok());
+
+        let looping = JSONSchema::compile(&json(r##"{ "$ref": "#" }"##)).unwrap();
+        assert!(!looping.is_valid(&json("1")));
+        assert!(JSONSchema::compile(&json("false"))
+            .unwrap()
+            .validate(&json("1"))
+            .is_err());
+    }
+}
@@ -0,0 +1,598 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use crate::value::JSONError;
+
+// upper bound on the work done by one `is_match`
+const MAX_STEPS: usize = 1_000_000;
+
+// A small backtracking matcher for the ECMA-262 subset commonly found in
+// schemas: classes, escapes, anchors, groups, alternation, greedy and lazy
+// quantifiers and lookaheads. Backreferences and unicode properties are
+// rejected at compile time.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    root: Node,
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    WordBoundary(bool),
+    Look(Box<Node>, bool),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat(Box<Node>, usize, Option<usize>, bool),
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, JSONError> {
+        let mut parser = RegexParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let root = parser.parse_alt()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("Unmatched `)`"));
+        }
+        Ok(Regex { root })
+    }
+
+    // unanchored search, as JSON Schema `pattern` requires. Fails instead of
+    // running for too long on patterns that backtrack catastrophically.
+    pub fn is_match(&self, text: &str) -> Result<bool, JSONError> {
+        let input: Vec<char> = text.chars().collect();
+        let matcher = Matcher {
+            input: &input,
+            steps: Cell::new(0),
+            nested: Cell::new(0),
+            failed: RefCell::new(HashMap::new()),
+        };
+        let last = if anchored(&self.root) { 0 } else { input.len() };
+        for start in 0..=last {
+            if matcher.node(&self.root, start, &mut |_| true) {
+                return Ok(true);
+            }
+            if matcher.exhausted() {
+                return Err(JSONError::new(
+                    "Pattern is too expensive to match".to_string(),
+                    0,
+                    0,
+                ));
+            }
+        }
+        Ok(false)
+    }
+}
+
+// whether every match has to start at the beginning of the input
+fn anchored(node: &Node) -> bool {
+    match node {
+        Node::Start => true,
+        Node::Concat(nodes) => nodes.first().is_some_and(anchored),
+        Node::Alt(alts) => alts.iter().all(anchored),
+        _ => false,
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn class_matches(items: &[ClassItem], c: char) -> bool {
+    items.iter().any(|item| match item {
+        ClassItem::Char(x) => *x == c,
+        ClassItem::Range(a, b) => *a <= c && c <= *b,
+        ClassItem::Digit(yes) => c.is_ascii_digit() == *yes,
+        ClassItem::Word(yes) => is_word(c) == *yes,
+        ClassItem::Space(yes) => c.is_whitespace() == *yes,
+    })
+}
+
+// a pending repetition state: try one more iteration from `More(pos, count)`
+// or continue after the repetition with `Stop(pos)`
+enum Step {
+    More(usize, usize),
+    Stop(usize),
+}
+
+// repetition states already explored
+#[derive(Default)]
+struct Visited {
+    more: HashSet<(usize, usize)>,
+    stops: HashSet<usize>,
+}
+
+struct Matcher<'a> {
+    input: &'a [char],
+    steps: Cell<usize>,
+    // how many repetitions or lookaheads enclose the current node
+    nested: Cell<usize>,
+    // states of outermost repetitions that failed, kept across start offsets
+    failed: RefCell<HashMap<*const Node, Visited>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn exhausted(&self) -> bool {
+        self.steps.get() > MAX_STEPS
+    }
+
+    fn enclosed<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.nested.set(self.nested.get() + 1);
+        let result = f();
+        self.nested.set(self.nested.get() - 1);
+        result
+    }
+
+    fn node(&self, node: &Node, pos: usize, k: &mut dyn FnMut(usize) -> bool) -> bool {
+        self.steps.set(self.steps.get() + 1);
+        if self.exhausted() {
+            return false;
+        }
+        let input = self.input;
+        let current = input.get(pos).copied();
+        match node {
+            Node::Char(c) => current == Some(*c) && k(pos + 1),
+            Node::Any => current.is_some_and(|c| c != '\n' && c != '\r') && k(pos + 1),
+            Node::Class(items, negated) => {
+                current.is_some_and(|c| class_matches(items, c) != *negated) && k(pos + 1)
+            }
+            Node::Start => pos == 0 && k(pos),
+            Node::End => pos == input.len() && k(pos),
+            Node::WordBoundary(yes) => {
+                let before = pos > 0 && is_word(input[pos - 1]);
+                let after = current.is_some_and(is_word);
+                ((before != after) == *yes) && k(pos)
+            }
+            Node::Look(inner, positive) => {
+                let found = self.enclosed(|| self.node(inner, pos, &mut |_| true));
+                found == *positive && k(pos)
+            }
+            Node::Concat(nodes) => self.seq(nodes, pos, k),
+            Node::Alt(alts) => alts.iter().any(|a| self.node(a, pos, k)),
+            Node::Repeat(inner, min, max, greedy) => {
+                self.repeat(inner, *min, *max, *greedy, pos, k)
+            }
+        }
+    }
+
+    fn seq(&self, nodes: &[Node], pos: usize, k: &mut dyn FnMut(usize) -> bool) -> bool {
+        match nodes.split_first() {
+            None => k(pos),
+            Some((first, rest)) => self.node(first, pos, &mut |p| self.seq(rest, p, k)),
+        }
+    }
+
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        pos: usize,
+        k: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        // an outermost repetition always continues the same way, so states
+        // that failed from one start offset fail from every other one
+        let outermost = self.nested.get() == 0;
+        let mut visited = if outermost {
+            let key = node as *const Node;
+            self.failed.borrow_mut().remove(&key).unwrap_or_default()
+        } else {
+            Visited::default()
+        };
+        let found = self.explore(node, min, max, greedy, pos, k, &mut visited);
+        if outermost && !found {
+            self.failed
+                .borrow_mut()
+                .insert(node as *const Node, visited);
+        }
+        found
+    }
+
+    // Iterations are driven by an explicit stack so that the call depth does
+    // not grow with the input. Each state collects every end of one more
+    // iteration, then backtracks through them in greedy or lazy order.
+    #[allow(clippy::too_many_arguments)]
+    fn explore(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        pos: usize,
+        k: &mut dyn FnMut(usize) -> bool,
+        visited: &mut Visited,
+    ) -> bool {
+        let mut stack = vec![Step::More(pos, 0)];
+        while let Some(step) = stack.pop() {
+            self.steps.set(self.steps.get() + 1);
+            if self.exhausted() {
+                return false;
+            }
+            let (pos, count) = match step {
+                Step::Stop(p) => {
+                    if visited.stops.insert(p) && k(p) {
+                        return true;
+                    }
+                    continue;
+                }
+                Step::More(p, count) => (p, count),
+            };
+            // past `min`, the count only matters against `max`
+            let key = if max.is_none() { count.min(min) } else { count };
+            if !visited.more.insert((pos, key)) {
+                continue;
+            }
+            let can_stop = count >= min;
+            let mut ends = vec![];
+            if max.is_none_or(|m| count < m) {
+                self.enclosed(|| {
+                    self.node(node, pos, &mut |p| {
+                        // an iteration that consumes nothing can only help reach `min`
+                        if p != pos || count < min {
+                            ends.push(p);
+                        }
+                        false
+                    })
+                });
+            }
+            if greedy && can_stop {
+                stack.push(Step::Stop(pos));
+            }
+            stack.extend(ends.into_iter().rev().map(|p| Step::More(p, count + 1)));
+            if !greedy && can_stop {
+                stack.push(Step::Stop(pos));
+            }
+        }
+        false
+    }
+}
+
+struct RegexParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl RegexParser {
+    fn error(&self, msg: &str) -> JSONError {
+        JSONError::new(format!("Invalid regex: {}", msg), 1, self.pos + 1)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, JSONError> {
+        let mut alts = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alts.push(self.parse_concat()?);
+        }
+        if alts.len() == 1 {
+            return Ok(alts.remove(0));
+        }
+        Ok(Node::Alt(alts))
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, JSONError> {
+        let mut nodes = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, JSONError> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_braces() {
+                Some(bounds) => {
+                    // parse_braces leaves us on the closing brace
+                    bounds
+                }
+                None => {
+                    self.pos = start;
+                    return Ok(atom);
+                }
+            },
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        if let Some(m) = max {
+            if m < min {
+                return Err(self.error("Quantifier range is out of order"));
+            }
+        }
+        if matches!(
+            atom,
+            Node::Start | Node::End | Node::WordBoundary(_) | Node::Look(_, _)
+        ) {
+            return Err(self.error("Nothing to repeat"));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat(Box::new(atom), min, max, greedy))
+    }
+
+    // `{n}`, `{n,}` or `{n,m}`; anything else is a literal brace
+    fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+        self.pos += 1;
+        let min = self.parse_number()?;
+        let max = if self.eat(',') {
+            if self.peek() == Some('}') {
+                None
+            } else {
+                Some(self.parse_number()?)
+            }
+        } else {
+            Some(min)
+        };
+        if self.peek() != Some('}') {
+            return None;
+        }
+        Some((min, max))
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, JSONError> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error("Unexpected end of pattern")),
+        };
+        self.pos += 1;
+        match c {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                let look = if self.eat('?') {
+                    if self.eat(':') {
+                        None
+                    } else if self.eat('=') {
+                        Some(true)
+                    } else if self.eat('!') {
+                        Some(false)
+                    } else if self.eat('<') {
+                        // named group, the name is irrelevant for matching
+                        while self.peek().is_some_and(|c| c != '>') {
+                            self.pos += 1;
+                        }
+                        if !self.eat('>') {
+                            return Err(self.error("Unterminated group name"));
+                        }
+                        None
+                    } else {
+                        return Err(self.error("Unsupported group"));
+                    }
+                } else {
+                    None
+                };
+                let inner = self.parse_alt()?;
+                if !self.eat(')') {
+                    return Err(self.error("Missing `)`"));
+                }
+                Ok(match look {
+                    Some(positive) => Node::Look(Box::new(inner), positive),
+                    None => inner,
+                })
+            }
+            '[' => self.parse_class(),
+            '\\' => self.parse_escape(false).map(|item| match item {
+                Escape::Item(ClassItem::Char(c)) => Node::Char(c),
+                Escape::Item(item) => Node::Class(vec![item], false),
+                Escape::Boundary(yes) => Node::WordBoundary(yes),
+            }),
+            '*' | '+' | '?' => Err(self.error("Nothing to repeat")),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Node, JSONError> {
+        let negated = self.eat('^');
+        let mut items = vec![];
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("Missing `]`")),
+            };
+            self.pos += 1;
+            if c == ']' {
+                break;
+            }
+            let item = if c == '\\' {
+                match self.parse_escape(true)? {
+                    Escape::Item(item) => item,
+                    Escape::Boundary(_) => ClassItem::Char('\u{8}'),
+                }
+            } else {
+                ClassItem::Char(c)
+            };
+            // a `-` between two single characters makes a range
+            if let ClassItem::Char(from) = item {
+                if self.peek() == Some('-')
+                    && self.chars.get(self.pos + 1).is_some_and(|c| *c != ']')
+                {
+                    self.pos += 1;
+                    let to = match self.peek() {
+                        Some('\\') => {
+                            self.pos += 1;
+                            match self.parse_escape(true)? {
+                                Escape::Item(ClassItem::Char(to)) => to,
+                                _ => return Err(self.error("Invalid class range")),
+                            }
+                        }
+                        Some(to) => {
+                            self.pos += 1;
+                            to
+                        }
+                        None => return Err(self.error("Missing `]`")),
+                    };
+                    if to < from {
+                        return Err(self.error("Class range is out of order"));
+                    }
+                    items.push(ClassItem::Range(from, to));
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+        Ok(Node::Class(items, negated))
+    }
+
+    fn parse_escape(&mut self, in_class: bool) -> Result<Escape, JSONError> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error("Trailing `\\`")),
+        };
+        self.pos += 1;
+        let item = match c {
+            'd' => ClassItem::Digit(true),
+            'D' => ClassItem::Digit(false),
+            'w' => ClassItem::Word(true),
+            'W' => ClassItem::Word(false),
+            's' => ClassItem::Space(true),
+            'S' => ClassItem::Space(false),
+            'b' if !in_class => return Ok(Escape::Boundary(true)),
+            'B' if !in_class => return Ok(Escape::Boundary(false)),
+            'b' => ClassItem::Char('\u{8}'),
+            'n' => ClassItem::Char('\n'),
+            'r' => ClassItem::Char('\r'),
+            't' => ClassItem::Char('\t'),
+            'f' => ClassItem::Char('\u{c}'),
+            'v' => ClassItem::Char('\u{b}'),
+            '0' => ClassItem::Char('\0'),
+            'u' => ClassItem::Char(self.parse_hex(4)?),
+            'x' => ClassItem::Char(self.parse_hex(2)?),
+            c if c.is_ascii_digit() => return Err(self.error("Backreferences are not supported")),
+            'p' | 'P' => return Err(self.error("Unicode properties are not supported")),
+            c => ClassItem::Char(c),
+        };
+        Ok(Escape::Item(item))
+    }
+
+    fn parse_hex(&mut self, len: usize) -> Result<char, JSONError> {
+        let hex: String = self.chars.iter().skip(self.pos).take(len).collect();
+        let c = u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(std::char::from_u32);
+        match c {
+            Some(c) if hex.len() == len => {
+                self.pos += len;
+                Ok(c)
+            }
+            _ => Err(self.error("Invalid hex escape")),
+        }
+    }
+}
+
+enum Escape {
+    Item(ClassItem),
+    Boundary(bool),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text).unwrap()
+    }
+
+    #[test]
+    fn regex_matching() {
+        assert!(matches("^[a-z]+$", "abc"));
+        assert!(!matches("^[a-z]+$", "abC"));
+        assert!(matches("b", "abc"));
+        assert!(matches(r"^\d{3}-\d{2,4}$", "123-4567"));
+        assert!(!matches(r"^\d{3}-\d{2,4}$", "123-45678"));
+        assert!(matches("^(foo|bar)+baz$", "foobarfoobaz"));
+        assert!(matches("^a.*?c$", "abbbc"));
+        assert!(matches(r"^\w+@\w+\.com$", "jhon@mail.com"));
+        assert!(matches("^[^0-9]*$", "no digits"));
+        assert!(matches("^(?:a|b){2}$", "ab"));
+        assert!(matches(r"^foo(?!bar)", "foobaz"));
+        assert!(!matches(r"^foo(?!bar)", "foobar"));
+        assert!(matches(r"\bcat\b", "a cat sat"));
+        assert!(matches("x{,2}", "x{,2}"));
+        assert!(matches("^(a*)*$", "aaaa"));
+        assert!(matches("^[\\u0041-\\u005A]$", "Q"));
+        assert!(matches("^é+$", "éé"));
+    }
+
+    #[test]
+    fn long_input() {
+        use crate::{json, JSONSchema};
+
+        let text = "a".repeat(100_000);
+        let schema = JSONSchema::compile(&json!({"pattern": "^[a-z]*$"})).unwrap();
+        assert!(schema.is_valid(&json!(text.clone())));
+        assert!(!schema.is_valid(&json!(format!("{}1", text))));
+        assert!(matches("(?:ab|a)+$", &text));
+        assert!(matches("^(a|aa)*$", &text));
+
+        // unanchored patterns do not redo failed work at every start offset
+        let schema = JSONSchema::compile(&json!({"pattern": "[a-z]*1"})).unwrap();
+        assert!(schema.is_valid(&json!(format!("{} 1", "a".repeat(3000)))));
+        assert!(schema.is_valid(&json!(format!("{} 1", text))));
+        assert!(!schema.is_valid(&json!(text.clone())));
+        assert!(matches("(?:a|b)+c", &format!("{}c", text)));
+        assert!(!matches("a{2}x", &text));
+
+        // catastrophic backtracking runs out of steps instead of hanging
+        let schema = JSONSchema::compile(&json!({"pattern": "^(a*)*b"})).unwrap();
+        let errors = schema.validate(&json!(text)).unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Pattern is too expensive to match: `^(a*)*b`"
+        );
+    }
+
+    #[test]
+    fn regex_errors() {
+        assert!(Regex::new("(a").is_err());
+        assert!(Regex::new("a)").is_err());
+        assert!(Regex::new("[a").is_err());
+        assert!(Regex::new("*a").is_err());
+        assert!(Regex::new("[z-a]").is_err());
+        assert!(Regex::new(r"(a)\1").is_err());
+        assert!(Regex::new(r"\p{L}").is_err());
+    }
+}
@@ -0,0 +1,424 @@
+use std::fmt::Display;
+use std::io;
+
+use serde::ser::{self, Serialize};
+
+use crate::serializer::{escape, format_number};
+use crate::value::{JSONError, JSONValue};
+
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, JSONError> {
+    let mut out = vec![];
+    to_writer(&mut out, value)?;
+    String::from_utf8(out).map_err(|e| JSONError::new(e.to_string(), 0, 0))
+}
+
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(
+    writer: W,
+    value: &T,
+) -> Result<(), JSONError> {
+    value.serialize(&mut Serializer { writer })
+}
+
+impl ser::Error for JSONError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JSONError::new(msg.to_string(), 0, 0)
+    }
+}
+
+impl Serialize for JSONValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::{SerializeMap, SerializeSeq};
+        match self {
+            JSONValue::Null => serializer.serialize_unit(),
+            JSONValue::Boolean(b) => serializer.serialize_bool(*b),
+            JSONValue::Number(n) => serializer.serialize_f64(*n),
+            JSONValue::String(s) => serializer.serialize_str(s),
+            JSONValue::Array(vc) => {
+                let mut seq = serializer.serialize_seq(Some(vc.len()))?;
+                for v in vc {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            JSONValue::Object(hm) => {
+                // sorted so that output is stable between runs
+                let mut keys: Vec<&String> = hm.keys().collect();
+                keys.sort();
+                let mut map = serializer.serialize_map(Some(hm.len()))?;
+                for k in keys {
+                    map.serialize_entry(k, &hm[k])?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+fn io_error(e: io::Error) -> JSONError {
+    JSONError::new(e.to_string(), 0, 0)
+}
+
+fn write_escaped<W: io::Write>(w: &mut W, s: &str) -> Result<(), JSONError> {
+    w.write_all(escape(s).as_bytes()).map_err(io_error)
+}
+
+// Writes compact JSON; struct fields keep their declaration order.
+pub struct Serializer<W> {
+    writer: W,
+}
+
+impl<W: io::Write> Serializer<W> {
+    fn write(&mut self, s: &str) -> Result<(), JSONError> {
+        self.writer.write_all(s.as_bytes()).map_err(io_error)
+    }
+}
+
+pub struct Compound<'a, W> {
+    ser: &'a mut Serializer<W>,
+    first: bool,
+    // variants are wrapped in an extra object naming them
+    variant: bool,
+}
+
+impl<'a, W: io::Write> Compound<'a, W> {
+    fn separate(&mut self) -> Result<(), JSONError> {
+        if !self.first {
+            self.ser.write(",")?;
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    fn close(self, bracket: &str) -> Result<(), JSONError> {
+        self.ser.write(bracket)?;
+        if self.variant {
+            self.ser.write("}")?;
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), JSONError> {
+        self.separate()?;
+        write_escaped(&mut self.ser.writer, key)?;
+        self.ser.write(":")
+    }
+}
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = JSONError;
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), JSONError> {
+        self.write(if v { "true" } else { "false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), JSONError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), JSONError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), JSONError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), JSONError> {
+        self.write(&v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), JSONError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), JSONError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), JSONError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), JSONError> {
+        self.write(&v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), JSONError> {
+        self.serialize_f64(v as f64)
+    }
+
+    // JSON has no representation for NaN and infinities
+    fn serialize_f64(self, v: f64) -> Result<(), JSONError> {
+        self.write(&format_number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), JSONError> {
+        write_escaped(&mut self.writer, &v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), JSONError> {
+        write_escaped(&mut self.writer, v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), JSONError> {
+        use ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for b in v {
+            seq.serialize_element(b)?;
+        }
+        seq.end()
+    }
+
+    fn serialize_none(self) -> Result<(), JSONError> {
+        self.write("null")
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), JSONError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), JSONError> {
+        self.write("null")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JSONError> {
+        self.write("null")
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), JSONError> {
+        write_escaped(&mut self.writer, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JSONError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), JSONError> {
+        self.write("{")?;
+        write_escaped(&mut self.writer, variant)?;
+        self.write(":")?;
+        value.serialize(&mut *self)?;
+        self.write("}")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a, W>, JSONError> {
+        self.write("[")?;
+        Ok(Compound {
+            ser: self,
+            first: true,
+            variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, W>, JSONError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>, JSONError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, W>, JSONError> {
+        self.write("{")?;
+        write_escaped(&mut self.writer, variant)?;
+        self.write(":[")?;
+        Ok(Compound {
+            ser: self,
+            first: true,
+            variant: true,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a, W>, JSONError> {
+        self.write("{")?;
+        Ok(Compound {
+            ser: self,
+            first: true,
+            variant: false,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>, JSONError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, W>, JSONError> {
+        self.write("{")?;
+        write_escaped(&mut self.writer, variant)?;
+        self.write(":{")?;
+        Ok(Compound {
+            ser: self,
+            first: true,
+            variant: true,
+        })
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeSeq for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JSONError> {
+        self.separate()?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("]")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTuple for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JSONError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("]")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTupleStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JSONError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("]")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTupleVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JSONError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("]")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeMap for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    // keys are rendered on their own first: strings are kept as is,
+    // numbers and booleans are quoted and anything else is rejected
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JSONError> {
+        let mut buf = vec![];
+        key.serialize(&mut Serializer { writer: &mut buf })?;
+        self.separate()?;
+        match buf.first() {
+            Some(b'"') => (),
+            Some(b'{') | Some(b'[') | Some(b'n') | None => {
+                return Err(JSONError::new(
+                    "Object keys must be strings".to_string(),
+                    0,
+                    0,
+                ))
+            }
+            _ => {
+                buf.insert(0, b'"');
+                buf.push(b'"');
+            }
+        }
+        self.ser.writer.write_all(&buf).map_err(io_error)?;
+        self.ser.write(":")
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JSONError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("}")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JSONError> {
+        self.key(key)?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("}")
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeStructVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = JSONError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JSONError> {
+        self.key(key)?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JSONError> {
+        self.close("}")
+    }
+}
@@ -0,0 +1,509 @@
+use std::fmt::{self, Write};
+
+use crate::parser::Comments;
+use crate::pointer::escape_token;
+use crate::value::{JSONError, JSONValue};
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    // one level of indentation; `None` writes everything on a single line
+    pub indent: Option<String>,
+    pub sort_keys: bool,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        FormatOptions {
+            indent: Some("  ".to_string()),
+            sort_keys: false,
+        }
+    }
+
+    pub fn compact() -> Self {
+        FormatOptions {
+            indent: None,
+            sort_keys: false,
+        }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions::new()
+    }
+}
+
+// quotes `s` as a JSON string literal
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// JSON has no NaN or infinities, they are written as `null`
+// very small and very large magnitudes use exponent notation, at the same
+// thresholds as ECMAScript
+pub fn format_number(n: f64) -> String {
+    if !n.is_finite() {
+        "null".to_string()
+    } else if n != 0.0 && (n.abs() < 1e-6 || n.abs() >= 1e21) {
+        format!("{:e}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+// JSON5 can write infinities and NaN as they are
+fn format_number5(n: f64) -> String {
+    match n {
+        n if n.is_nan() => "NaN".to_string(),
+        n if n.is_infinite() && n > 0.0 => "Infinity".to_string(),
+        n if n.is_infinite() => "-Infinity".to_string(),
+        n => format_number(n),
+    }
+}
+
+// JSON5 member names that can be written without quotes
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+pub fn write_value<W: Write>(
+    w: &mut W,
+    value: &JSONValue,
+    opts: &FormatOptions,
+    depth: usize,
+) -> fmt::Result {
+    write_node(w, value, opts, depth, &Style::default(), &mut String::new())
+}
+
+// like `write_value`, with unquoted identifier keys, non-finite numbers and
+// trailing commas when indenting
+pub fn write_json5<W: Write>(
+    w: &mut W,
+    value: &JSONValue,
+    opts: &FormatOptions,
+    depth: usize,
+) -> fmt::Result {
+    let style = Style {
+        json5: true,
+        comments: None,
+    };
+    write_node(w, value, opts, depth, &style, &mut String::new())
+}
+
+// like `write_value`, putting captured comments back before the members they
+// were attached to; without indentation line comments become block comments
+pub fn write_jsonc<W: Write>(
+    w: &mut W,
+    value: &JSONValue,
+    opts: &FormatOptions,
+    comments: &Comments,
+) -> fmt::Result {
+    let style = Style {
+        json5: false,
+        comments: Some(comments),
+    };
+    for text in style.leading("") {
+        write_comment(w, text, opts)?;
+        if opts.indent.is_some() {
+            w.write_char('\n')?;
+        }
+    }
+    write_node(w, value, opts, 0, &style, &mut String::new())?;
+    for text in &comments.end {
+        if opts.indent.is_some() {
+            w.write_char('\n')?;
+        }
+        write_comment(w, text, opts)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Style<'a> {
+    json5: bool,
+    comments: Option<&'a Comments>,
+}
+
+impl<'a> Style<'a> {
+    fn leading(&self, pointer: &str) -> &'a [String] {
+        self.comments
+            .and_then(|c| c.leading.get(pointer))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    fn trailing(&self, pointer: &str) -> &'a [String] {
+        self.comments
+            .and_then(|c| c.trailing.get(pointer))
+            .map_or(&[], |v| v.as_slice())
+    }
+}
+
+// compact output turns line comments into block comments, except when the
+// text would end the block early; those keep their own line
+fn write_comment<W: Write>(w: &mut W, text: &str, opts: &FormatOptions) -> fmt::Result {
+    match text.strip_prefix("//") {
+        Some(line) if opts.indent.is_none() && !line.contains("*/") => {
+            write!(w, "/*{} */", line)
+        }
+        Some(_) if opts.indent.is_none() => writeln!(w, "{}", text),
+        _ => w.write_str(text),
+    }
+}
+
+fn write_node<W: Write>(
+    w: &mut W,
+    value: &JSONValue,
+    opts: &FormatOptions,
+    depth: usize,
+    style: &Style,
+    pointer: &mut String,
+) -> fmt::Result {
+    let newline = |w: &mut W, depth: usize| -> fmt::Result {
+        if let Some(indent) = &opts.indent {
+            w.write_char('\n')?;
+            for _ in 0..depth {
+                w.write_str(indent)?;
+            }
+        }
+        Ok(())
+    };
+    // comments go on their own lines before the member
+    let leading = |w: &mut W, pointer: &str| -> fmt::Result {
+        for text in style.leading(pointer) {
+            write_comment(w, text, opts)?;
+            newline(w, depth + 1)?;
+        }
+        Ok(())
+    };
+    let close = |w: &mut W, pointer: &str, bracket: char, empty: bool| -> fmt::Result {
+        if style.json5 && opts.indent.is_some() && !empty {
+            w.write_char(',')?;
+        }
+        for text in style.trailing(pointer) {
+            newline(w, depth + 1)?;
+            write_comment(w, text, opts)?;
+        }
+        newline(w, depth)?;
+        w.write_char(bracket)
+    };
+
+    match value {
+        JSONValue::Null => w.write_str("null"),
+        JSONValue::Boolean(b) => write!(w, "{}", b),
+        JSONValue::Number(n) if style.json5 => w.write_str(&format_number5(*n)),
+        JSONValue::Number(n) => w.write_str(&format_number(*n)),
+        JSONValue::String(s) => w.write_str(&escape(s)),
+        JSONValue::Object(hm) if hm.is_empty() && style.trailing(pointer).is_empty() => {
+            w.write_str("{}")
+        }
+        JSONValue::Object(hm) => {
+            let mut members: Vec<_> = hm.iter().collect();
+            if opts.sort_keys {
+                members.sort_by(|a, b| a.0.cmp(b.0));
+            }
+            w.write_char('{')?;
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                newline(w, depth + 1)?;
+                let len = pointer.len();
+                if style.comments.is_some() {
+                    pointer.push('/');
+                    pointer.push_str(&escape_token(k));
+                    leading(w, pointer)?;
+                }
+                if style.json5 && is_identifier(k) {
+                    w.write_str(k)?;
+                } else {
+                    w.write_str(&escape(k))?;
+                }
+                w.write_str(if opts.indent.is_some() { ": " } else { ":" })?;
+                write_node(w, v, opts, depth + 1, style, pointer)?;
+                pointer.truncate(len);
+            }
+            close(w, pointer, '}', hm.is_empty())
+        }
+        JSONValue::Array(vc) if vc.is_empty() && style.trailing(pointer).is_empty() => {
+            w.write_str("[]")
+        }
+        JSONValue::Array(vc) => {
+            w.write_char('[')?;
+            for (i, v) in vc.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                newline(w, depth + 1)?;
+                let len = pointer.len();
+                if style.comments.is_some() {
+                    pointer.push_str(&format!("/{}", i));
+                    leading(w, pointer)?;
+                }
+                write_node(w, v, opts, depth + 1, style, pointer)?;
+                pointer.truncate(len);
+            }
+            close(w, pointer, ']', vc.is_empty())
+        }
+    }
+}
+
+// ECMAScript Number::toString, as RFC 8785 requires: the shortest digits that
+// round-trip, in plain notation for exponents from -6 to 20
+fn format_number_es(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    if n < 0.0 {
+        return format!("-{}", format_number_es(-n));
+    }
+    let sci = format!("{:e}", n);
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // the decimal point sits after `n` digits
+    let n = exp.parse::<i32>().unwrap_or(0) + 1;
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", rest)
+        };
+        format!("{}{}e{}{}", first, rest, sign, (n - 1).abs())
+    }
+}
+
+fn write_canonical(
+    out: &mut String,
+    value: &JSONValue,
+    pointer: &mut String,
+) -> Result<(), JSONError> {
+    match value {
+        JSONValue::Number(n) if !n.is_finite() => {
+            let at = if pointer.is_empty() { "/" } else { pointer };
+            return Err(JSONError::new(
+                format!("Cannot canonicalize {} at `{}`", n, at),
+                0,
+                0,
+            ));
+        }
+        JSONValue::Number(n) => out.push_str(&format_number_es(*n)),
+        JSONValue::Object(hm) => {
+            let mut keys: Vec<&String> = hm.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&escape(k));
+                out.push(':');
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&escape_token(k));
+                write_canonical(out, &hm[k], pointer)?;
+                pointer.truncate(len);
+            }
+            out.push('}');
+        }
+        JSONValue::Array(vc) => {
+            out.push('[');
+            for (i, v) in vc.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let len = pointer.len();
+                pointer.push_str(&format!("/{}", i));
+                write_canonical(out, v, pointer)?;
+                pointer.truncate(len);
+            }
+            out.push(']');
+        }
+        // strings are escaped as JCS asks: only `"`, `\\` and control characters
+        _ => {
+            let _ = write_value(out, value, &FormatOptions::compact(), 0);
+        }
+    }
+    Ok(())
+}
+
+impl JSONValue {
+    // RFC 8785 (JCS) canonical form for hashing and signing: keys sorted by
+    // UTF-16 code units, ECMAScript numbers, no whitespace. NaN and infinities
+    // are errors.
+    pub fn to_canonical_string(&self) -> Result<String, JSONError> {
+        let mut out = String::new();
+        write_canonical(&mut out, self, &mut String::new())?;
+        Ok(out)
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        self.to_string_with(&FormatOptions::new())
+    }
+
+    pub fn to_string_with(&self, opts: &FormatOptions) -> String {
+        let mut out = String::new();
+        let _ = write_value(&mut out, self, opts, 0);
+        out
+    }
+
+    pub fn to_json5_string(&self, opts: &FormatOptions) -> String {
+        let mut out = String::new();
+        let _ = write_json5(&mut out, self, opts, 0);
+        out
+    }
+
+    pub fn to_jsonc_string(&self, opts: &FormatOptions, comments: &Comments) -> String {
+        let mut out = String::new();
+        let _ = write_jsonc(&mut out, self, opts, comments);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn formatting() {
+        let v = json!({ "b": [1, 2.5, {}], "a": { "x": null, "y": [] } });
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::new()
+        };
+        assert_eq!(
+            v.to_string_with(&opts),
+            "{\n  \"a\": {\n    \"x\": null,\n    \"y\": []\n  },\n  \"b\": [\n    1,\n    2.5,\n    {}\n  ]\n}"
+        );
+        let opts = FormatOptions {
+            indent: Some("\t".to_string()),
+            sort_keys: true,
+        };
+        assert_eq!(
+            json!({ "a": [1] }).to_string_with(&opts),
+            "{\n\t\"a\": [\n\t\t1\n\t]\n}"
+        );
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::compact()
+        };
+        assert_eq!(
+            v.to_string_with(&opts),
+            r#"{"a":{"x":null,"y":[]},"b":[1,2.5,{}]}"#
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("a\"b\\c\n\u{1}é"), r#""a\"b\\c\n\u0001é""#);
+        assert_eq!(JSONValue::Number(f64::NAN).to_string(), "null");
+        let v = json!({ "q\"k": "line\nbreak" });
+        assert_eq!(v.to_string(), r#"{"q\"k":"line\nbreak"}"#);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(format_number(6.626e-34), "6.626e-34");
+        assert_eq!(format_number(-1.5e300), "-1.5e300");
+        assert_eq!(format_number(1e21), "1e21");
+        assert_eq!(format_number(1e20), "100000000000000000000");
+        assert_eq!(format_number(0.000001), "0.000001");
+        assert_eq!(format_number(-0.0), "-0");
+        assert_eq!(json!([6.626e-34, 0.5]).to_string(), "[6.626e-34,0.5]");
+    }
+
+    #[test]
+    fn json5() {
+        let v = json!({ "name": "x", "two words": [1, 2], "$id": 7 });
+        let opts = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::new()
+        };
+        assert_eq!(
+            v.to_json5_string(&opts),
+            "{\n  $id: 7,\n  name: \"x\",\n  \"two words\": [\n    1,\n    2,\n  ],\n}"
+        );
+        let v = json!([f64::INFINITY, f64::NEG_INFINITY, f64::NAN, {}]);
+        assert_eq!(
+            v.to_json5_string(&FormatOptions::compact()),
+            "[Infinity,-Infinity,NaN,{}]"
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        // RFC 8785, section 3.2.2
+        let v = crate::JSONDocument::new()
+            .parse_string(
+                r#"{
+                  "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                  "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                  "literals": [null, true, false]
+                }"#
+                .to_string(),
+            )
+            .unwrap();
+        assert_eq!(
+            v.to_canonical_string().unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+
+        // RFC 8785, section 3.2.3: UTF-16 order puts 😀 before U+FB33
+        let v =
+            json!({ "\u{20ac}": 1, "\r": 2, "\u{fb33}": 3, "1": 4, "😀": 5, "\u{80}": 6, "ö": 7 });
+        assert_eq!(
+            v.to_canonical_string().unwrap(),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}"
+        );
+
+        let cases = [
+            (-0.0, "0"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (5e-324, "5e-324"),
+            (-1.7976931348623157e308, "-1.7976931348623157e+308"),
+            (0.000001, "0.000001"),
+            (1.5e-7, "1.5e-7"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+        ];
+        for (n, text) in cases.iter() {
+            assert_eq!(&JSONValue::Number(*n).to_canonical_string().unwrap(), text);
+        }
+        let err = json!({ "a": [f64::NAN] })
+            .to_canonical_string()
+            .unwrap_err();
+        assert_eq!(err.message(), "Cannot canonicalize NaN at `/a/0`");
+    }
+}
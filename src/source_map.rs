@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// 1-based line and column (in characters) with the byte offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub col: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn start() -> Position {
+        Position {
+            line: 1,
+            col: 1,
+            offset: 0,
+        }
+    }
+
+    // moves past `text`
+    pub(crate) fn advance(&mut self, text: &str) {
+        for c in text.chars() {
+            self.offset += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+// `end` is just past the last character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+// Where each value and member key of a document was read from, keyed by JSON
+// Pointer. See `CSTDocument::source_map`.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    values: HashMap<String, Span>,
+    keys: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    pub fn value(&self, pointer: &str) -> Option<Span> {
+        self.values.get(pointer).copied()
+    }
+
+    pub fn key(&self, pointer: &str) -> Option<Span> {
+        self.keys.get(pointer).copied()
+    }
+
+    // the span of `pointer`, or of its closest ancestor when it is missing,
+    // e.g. for a required member
+    pub fn locate(&self, pointer: &str) -> Option<Span> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(span) = self.value(pointer) {
+                return Some(span);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    pub(crate) fn insert_value(&mut self, pointer: &str, span: Span) {
+        self.values.insert(pointer.to_string(), span);
+    }
+
+    pub(crate) fn insert_key(&mut self, pointer: &str, span: Span) {
+        self.keys.insert(pointer.to_string(), span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CSTDocument, JSONDocument, JSONSchema};
+
+    #[test]
+    fn spans() {
+        let text = "{\n  \"name\": \"é\",\n  \"server\": {\n    \"port\": 70000,\n    \"tags\": [\"a\", 1]\n  }\n}\n";
+        let doc = CSTDocument::parse(text).unwrap();
+        let map = doc.source_map();
+
+        let port = map.value("/server/port").unwrap();
+        assert_eq!((port.start.line, port.start.col), (4, 13));
+        assert_eq!(&text[port.start.offset..port.end.offset], "70000");
+        let key = map.key("/server/port").unwrap();
+        assert_eq!(&text[key.start.offset..key.end.offset], "\"port\"");
+        let name = map.value("/name").unwrap();
+        assert_eq!((name.end.col, name.end.offset - name.start.offset), (14, 4));
+        let root = map.value("").unwrap();
+        assert_eq!((root.start.offset, root.end.line, root.end.col), (0, 7, 2));
+        assert_eq!(
+            map.value("/server/tags/1").unwrap().start.to_string(),
+            "5:19"
+        );
+        assert_eq!(map.locate("/server/missing/x"), map.value("/server"));
+        assert_eq!(map.value("/server/missing"), None);
+
+        let schema = JSONDocument::new()
+            .parse_string(
+                r#"{"properties": {"server": {"properties": {"port": {"maximum": 65535}}}}}"#
+                    .to_string(),
+            )
+            .unwrap();
+        let errors = JSONSchema::compile(&schema)
+            .unwrap()
+            .validate(&doc.value())
+            .unwrap_err();
+        assert_eq!(errors[0].span(&map).unwrap().start.to_string(), "4:13");
+    }
+}
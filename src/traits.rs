@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::pointer::escape_token;
+use crate::value::JSONValue;
+
+// A failed conversion; `path` is a JSON Pointer to the offending value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    path: String,
+    message: String,
+}
+
+impl ConversionError {
+    pub fn new(message: String) -> Self {
+        ConversionError {
+            path: String::new(),
+            message,
+        }
+    }
+
+    pub fn expected(what: &str, found: &JSONValue) -> Self {
+        ConversionError::new(format!("Expected {} but found {}", what, found.type_name()))
+    }
+
+    pub fn missing(field: &str) -> Self {
+        ConversionError::new(format!("Missing field `{}`", field)).at(field)
+    }
+
+    // prefixes the path with a parent key or index, innermost first
+    pub fn at(mut self, segment: &str) -> Self {
+        self.path = format!("/{}{}", escape_token(segment), self.path);
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "ConversionError: {} - @ {}", self.message, path)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+pub trait ToJSON {
+    fn to_json(&self) -> JSONValue;
+}
+
+pub trait FromJSON: Sized {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError>;
+
+    // value to use when an object member is absent; only `Option` has one
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+impl ToJSON for JSONValue {
+    fn to_json(&self) -> JSONValue {
+        self.clone()
+    }
+}
+
+impl FromJSON for JSONValue {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        Ok(value.clone())
+    }
+}
+
+impl ToJSON for () {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::Null
+    }
+}
+
+impl FromJSON for () {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::Null => Ok(()),
+            v => Err(ConversionError::expected("null", v)),
+        }
+    }
+}
+
+impl ToJSON for bool {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::Boolean(*self)
+    }
+}
+
+impl FromJSON for bool {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::Boolean(b) => Ok(*b),
+            v => Err(ConversionError::expected("boolean", v)),
+        }
+    }
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {$(
+        impl ToJSON for $t {
+            fn to_json(&self) -> JSONValue {
+                JSONValue::Number(*self as f64)
+            }
+        }
+
+        impl FromJSON for $t {
+            fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+                match value {
+                    // MAX as f64 rounds up to a power of two for the 64-bit
+                    // types, so the bound is exclusive
+                    JSONValue::Number(n)
+                        if n.fract() == 0f64
+                            && *n >= <$t>::MIN as f64
+                            && *n < <$t>::MAX as f64 + 1f64 =>
+                    {
+                        Ok(*n as $t)
+                    }
+                    JSONValue::Number(n) => Err(ConversionError::new(format!(
+                        "{} is not a valid {}",
+                        n,
+                        stringify!($t)
+                    ))),
+                    v => Err(ConversionError::expected("number", v)),
+                }
+            }
+        }
+    )*};
+}
+
+integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! float {
+    ($($t:ty),*) => {$(
+        impl ToJSON for $t {
+            fn to_json(&self) -> JSONValue {
+                JSONValue::Number(*self as f64)
+            }
+        }
+
+        impl FromJSON for $t {
+            fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+                match value {
+                    JSONValue::Number(n) => Ok(*n as $t),
+                    v => Err(ConversionError::expected("number", v)),
+                }
+            }
+        }
+    )*};
+}
+
+float!(f32, f64);
+
+impl ToJSON for String {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::String(self.clone())
+    }
+}
+
+impl FromJSON for String {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::String(s) => Ok(s.clone()),
+            v => Err(ConversionError::expected("string", v)),
+        }
+    }
+}
+
+impl ToJSON for str {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::String(self.to_string())
+    }
+}
+
+impl ToJSON for char {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::String(self.to_string())
+    }
+}
+
+impl FromJSON for char {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::String(s) if s.chars().count() == 1 => Ok(s.chars().next().unwrap()),
+            v => Err(ConversionError::expected("single character string", v)),
+        }
+    }
+}
+
+impl<T: ToJSON + ?Sized> ToJSON for &T {
+    fn to_json(&self) -> JSONValue {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJSON + ?Sized> ToJSON for Box<T> {
+    fn to_json(&self) -> JSONValue {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJSON> FromJSON for Box<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+impl<T: ToJSON> ToJSON for Option<T> {
+    fn to_json(&self) -> JSONValue {
+        match self {
+            Some(v) => v.to_json(),
+            None => JSONValue::Null,
+        }
+    }
+}
+
+impl<T: FromJSON> FromJSON for Option<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::Null => Ok(None),
+            v => T::from_json(v).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: ToJSON> ToJSON for [T] {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::Array(self.iter().map(ToJSON::to_json).collect())
+    }
+}
+
+impl<T: ToJSON> ToJSON for Vec<T> {
+    fn to_json(&self) -> JSONValue {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJSON> FromJSON for Vec<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        match value {
+            JSONValue::Array(vc) => vc
+                .iter()
+                .enumerate()
+                .map(|(i, v)| T::from_json(v).map_err(|e| e.at(&i.to_string())))
+                .collect(),
+            v => Err(ConversionError::expected("array", v)),
+        }
+    }
+}
+
+fn object_from_json<T: FromJSON, M: FromIterator<(String, T)>>(
+    value: &JSONValue,
+) -> Result<M, ConversionError> {
+    match value {
+        JSONValue::Object(hm) => hm
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_json(v).map_err(|e| e.at(k))?)))
+            .collect(),
+        v => Err(ConversionError::expected("object", v)),
+    }
+}
+
+impl<T: ToJSON> ToJSON for HashMap<String, T> {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<T: FromJSON> FromJSON for HashMap<String, T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        object_from_json(value)
+    }
+}
+
+impl<T: ToJSON> ToJSON for BTreeMap<String, T> {
+    fn to_json(&self) -> JSONValue {
+        JSONValue::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<T: FromJSON> FromJSON for BTreeMap<String, T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+        object_from_json(value)
+    }
+}
+
+macro_rules! tuple {
+    ($len:expr => $($t:ident $i:tt),+) => {
+        impl<$($t: ToJSON),+> ToJSON for ($($t,)+) {
+            fn to_json(&self) -> JSONValue {
+                JSONValue::Array(vec![$(self.$i.to_json()),+])
+            }
+        }
+
+        impl<$($t: FromJSON),+> FromJSON for ($($t,)+) {
+            fn from_json(value: &JSONValue) -> Result<Self, ConversionError> {
+                match value {
+                    JSONValue::Array(vc) if vc.len() == $len => Ok(($(
+                        $t::from_json(&vc[$i]).map_err(|e| e.at(stringify!($i)))?,
+                    )+)),
+                    v => Err(ConversionError::expected(concat!("array of ", $len, " items"), v)),
+                }
+            }
+        }
+    };
+}
+
+tuple!(1 => A 0);
+tuple!(2 => A 0, B 1);
+tuple!(3 => A 0, B 1, C 2);
+tuple!(4 => A 0, B 1, C 2, D 3);
+tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JSONDocument;
+
+    fn json(text: &str) -> JSONValue {
+        JSONDocument::new().parse_string(text.to_string()).unwrap()
+    }
+
+    type Rows = Vec<(u8, String, bool)>;
+
+    #[test]
+    fn roundtrip() {
+        let v = json(r#"{ "a": [[1, "x", true], [2, "y", false]], "b": null }"#);
+        let typed: BTreeMap<String, Option<Rows>> = FromJSON::from_json(&v).unwrap();
+        assert_eq!(typed["a"].as_ref().unwrap()[1], (2, "y".to_string(), false));
+        assert_eq!(typed["b"], None);
+        assert_eq!(typed.to_json(), v);
+
+        assert_eq!(Box::new(1.5f32).to_json(), JSONValue::Number(1.5));
+        assert_eq!(char::from_json(&json(r#""z""#)), Ok('z'));
+        assert_eq!(<Option<i32>>::from_missing(), Some(None));
+        assert_eq!(i32::from_missing(), None);
+    }
+
+    #[test]
+    fn error_paths() {
+        let v = json(r#"{ "a/b": [1, 2, 300] }"#);
+        let err = HashMap::<String, Vec<u8>>::from_json(&v).unwrap_err();
+        assert_eq!(err.path(), "/a~1b/2");
+        assert_eq!(err.message(), "300 is not a valid u8");
+        assert_eq!(
+            err.to_string(),
+            "ConversionError: 300 is not a valid u8 - @ /a~1b/2"
+        );
+
+        let err = <(i64, String)>::from_json(&json(r#"[1, 2]"#)).unwrap_err();
+        assert_eq!(err.path(), "/1");
+        assert_eq!(err.message(), "Expected string but found number");
+        assert!(u32::from_json(&json("-1")).is_err());
+        assert!(u32::from_json(&json("1.5")).is_err());
+    }
+
+    #[test]
+    fn integer_bounds() {
+        let n = JSONValue::Number;
+        assert_eq!(u8::from_json(&n(255.0)), Ok(255));
+        assert!(u8::from_json(&n(256.0)).is_err());
+        assert_eq!(i32::from_json(&n(-2147483648.0)), Ok(i32::MIN));
+        assert!(i32::from_json(&n(2147483648.0)).is_err());
+        assert_eq!(i64::from_json(&n(-9223372036854775808.0)), Ok(i64::MIN));
+        assert!(i64::from_json(&n(9223372036854775808.0)).is_err());
+        assert_eq!(
+            i64::from_json(&n(9223372036854774784.0)),
+            Ok(9223372036854774784)
+        );
+        assert!(u64::from_json(&n(18446744073709551616.0)).is_err());
+        assert!(usize::from_json(&n(usize::MAX as f64)).is_err());
+        assert!(isize::from_json(&n(isize::MAX as f64)).is_err());
+    }
+}
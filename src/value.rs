@@ -1,6 +1,10 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use crate::pointer::parse_pointer;
+use crate::serializer::{write_value, FormatOptions};
+use crate::traits::ConversionError;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum JSONValue {
     Null,
@@ -11,150 +15,196 @@ pub enum JSONValue {
     Array(Vec<JSONValue>),
 }
 
+pub type Map = HashMap<String, JSONValue>;
+
 impl JSONValue {
-    pub fn as_str(&self) -> String {
+    pub fn as_str(&self) -> Option<&str> {
         match self {
-            JSONValue::String(s) => s.clone(),
-            _ => "".to_string(),
+            JSONValue::String(s) => Some(s),
+            _ => None,
         }
     }
 
-    pub fn as_i64(&self) -> i64 {
+    // only whole numbers within range convert to integers
+    pub fn as_i64(&self) -> Option<i64> {
         match self {
-            JSONValue::Number(n) => *n as i64,
-            _ => 0,
+            JSONValue::Number(n)
+                if n.fract() == 0f64 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                Some(*n as i64)
+            }
+            _ => None,
         }
     }
 
-    pub fn as_f64(&self) -> f64 {
+    pub fn as_u64(&self) -> Option<u64> {
         match self {
-            JSONValue::Number(n) => *n,
-            _ => 0f64,
+            JSONValue::Number(n) if n.fract() == 0f64 && *n >= 0f64 && *n < u64::MAX as f64 => {
+                Some(*n as u64)
+            }
+            _ => None,
         }
     }
 
-    pub fn as_bool(&self) -> bool {
+    pub fn as_f64(&self) -> Option<f64> {
         match self {
-            JSONValue::Boolean(b) => *b,
-            _ => false,
+            JSONValue::Number(n) => Some(*n),
+            _ => None,
         }
     }
 
-    pub fn as_array(&self) -> Vec<JSONValue> {
+    pub fn as_bool(&self) -> Option<bool> {
         match self {
-            JSONValue::Array(vc) => vc.clone(),
-            _ => Vec::new(),
+            JSONValue::Boolean(b) => Some(*b),
+            _ => None,
         }
     }
 
-    pub fn as_map(&self) -> HashMap<String, JSONValue> {
+    pub fn as_array(&self) -> Option<&[JSONValue]> {
         match self {
-            JSONValue::Object(hm) => hm.clone(),
-            _ => HashMap::new(),
+            JSONValue::Array(vc) => Some(vc),
+            _ => None,
         }
     }
 
-    pub fn get(&self, k: &str) -> Option<JSONValue> {
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JSONValue>> {
         match self {
-            JSONValue::Object(hm) => match hm.get(k) {
-                Some(v) => Some(v.clone()),
-                None => None,
-            },
+            JSONValue::Array(vc) => Some(vc),
             _ => None,
         }
     }
 
-    pub fn exists() -> bool {
-        false
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            JSONValue::Object(hm) => Some(hm),
+            _ => None,
+        }
     }
 
-    pub fn is_number(&self) -> bool {
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
         match self {
-            JSONValue::Number(_) => true,
-            _ => false,
+            JSONValue::Object(hm) => Some(hm),
+            _ => None,
         }
     }
 
-    pub fn is_string(&self) -> bool {
-        match self {
-            JSONValue::String(_) => true,
-            _ => false,
+    pub fn get(&self, k: &str) -> Option<&JSONValue> {
+        self.as_object().and_then(|hm| hm.get(k))
+    }
+
+    pub fn get_mut(&mut self, k: &str) -> Option<&mut JSONValue> {
+        self.as_object_mut().and_then(|hm| hm.get_mut(k))
+    }
+
+    // The `expect_*` accessors resolve a JSON Pointer (`""` for the value
+    // itself) and fail with the pointer and the type actually found.
+    pub fn expect_str(&self, pointer: &str) -> Result<&str, ConversionError> {
+        self.expect(pointer, "string", JSONValue::as_str)
+    }
+
+    pub fn expect_i64(&self, pointer: &str) -> Result<i64, ConversionError> {
+        self.expect(pointer, "integer", JSONValue::as_i64)
+    }
+
+    pub fn expect_u64(&self, pointer: &str) -> Result<u64, ConversionError> {
+        self.expect(pointer, "unsigned integer", JSONValue::as_u64)
+    }
+
+    pub fn expect_f64(&self, pointer: &str) -> Result<f64, ConversionError> {
+        self.expect(pointer, "number", JSONValue::as_f64)
+    }
+
+    pub fn expect_bool(&self, pointer: &str) -> Result<bool, ConversionError> {
+        self.expect(pointer, "boolean", JSONValue::as_bool)
+    }
+
+    pub fn expect_array(&self, pointer: &str) -> Result<&[JSONValue], ConversionError> {
+        self.expect(pointer, "array", JSONValue::as_array)
+    }
+
+    pub fn expect_object(&self, pointer: &str) -> Result<&Map, ConversionError> {
+        self.expect(pointer, "object", JSONValue::as_object)
+    }
+
+    fn expect<'a, T>(
+        &'a self,
+        pointer: &str,
+        what: &str,
+        accessor: fn(&'a JSONValue) -> Option<T>,
+    ) -> Result<T, ConversionError> {
+        let tokens =
+            parse_pointer(pointer).map_err(|e| ConversionError::new(e.message().to_string()))?;
+        let at = |err: ConversionError| tokens.iter().rev().fold(err, |e, t| e.at(t));
+        match self.pointer(pointer) {
+            Some(v) => accessor(v).ok_or_else(|| at(ConversionError::expected(what, v))),
+            None => Err(at(ConversionError::new(format!(
+                "Expected {} but found nothing",
+                what
+            )))),
         }
     }
 
-    pub fn is_bool(&self) -> bool {
+    pub fn type_name(&self) -> &'static str {
         match self {
-            JSONValue::Boolean(_) => true,
-            _ => false,
+            JSONValue::Null => "null",
+            JSONValue::Boolean(_) => "boolean",
+            JSONValue::Number(_) => "number",
+            JSONValue::String(_) => "string",
+            JSONValue::Object(_) => "object",
+            JSONValue::Array(_) => "array",
         }
     }
 
+    pub fn is_number(&self) -> bool {
+        matches!(self, JSONValue::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, JSONValue::String(_))
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, JSONValue::Boolean(_))
+    }
+
     pub fn is_object(&self) -> bool {
-        match self {
-            JSONValue::Object(_) => true,
-            _ => false,
-        }
+        matches!(self, JSONValue::Object(_))
     }
 
     pub fn is_array(&self) -> bool {
-        match self {
-            JSONValue::Array(_) => true,
-            _ => false,
-        }
+        matches!(self, JSONValue::Array(_))
     }
 
     pub fn is_null(&self) -> bool {
-        match self {
-            JSONValue::Null => true,
-            _ => false,
-        }
+        matches!(self, JSONValue::Null)
     }
 }
 
 impl fmt::Display for JSONValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            JSONValue::Null => write!(f, "null"),
-            JSONValue::Boolean(b) => write!(f, "{}", b),
-            JSONValue::Number(n) => write!(f, "{}", n),
-            JSONValue::String(s) => write!(f, "\"{}\"", s),
-            JSONValue::Object(hm) => {
-                let mut ctr = 0;
-                write!(f, "{{")?;
-                for (k, v) in hm {
-                    if ctr < hm.len() - 1 {
-                        write!(f, "\"{}\":{},", k, v)?
-                    } else {
-                        write!(f, "\"{}\":{}", k, v)?
-                    };
-                    ctr += 1;
-                }
-                write!(f, "}}")
-            }
-            JSONValue::Array(vc) => {
-                let mut ctr = 0;
-                write!(f, "[")?;
-                for v in vc {
-                    if ctr < vc.len() - 1 {
-                        write!(f, "{},", v)?
-                    } else {
-                        write!(f, "{}", v)?
-                    };
-                    ctr += 1;
-                }
-                write!(f, "]")
-            }
-        }
+        write_value(f, self, &FormatOptions::compact(), 0)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct JSONError(String, usize, usize);
 
 impl JSONError {
     pub fn new(err: String, lin: usize, col: usize) -> Self {
         JSONError(err, lin, col)
     }
+
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+
+    pub fn line(&self) -> usize {
+        self.1
+    }
+
+    pub fn col(&self) -> usize {
+        self.2
+    }
 }
 
 impl fmt::Display for JSONError {
@@ -162,3 +212,53 @@ impl fmt::Display for JSONError {
         write!(f, "JSONError: {} - @ ({}, {})", self.0, self.1, self.2)
     }
 }
+
+impl std::error::Error for JSONError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn accessors() {
+        let mut v = json!({ "name": "jo", "age": 43, "ratio": 0.5, "ok": true, "primes": [2, 3] });
+        assert_eq!(v.get("name").and_then(JSONValue::as_str), Some("jo"));
+        assert_eq!(v.get("age").and_then(JSONValue::as_i64), Some(43));
+        assert_eq!(v.get("ratio").and_then(JSONValue::as_i64), None);
+        assert_eq!(v.get("ratio").and_then(JSONValue::as_f64), Some(0.5));
+        assert_eq!(v.get("ok").and_then(JSONValue::as_bool), Some(true));
+        assert_eq!(v.get("name").and_then(JSONValue::as_bool), None);
+        assert_eq!(
+            v.get("primes")
+                .and_then(JSONValue::as_array)
+                .map(|a| a.len()),
+            Some(2)
+        );
+        assert!(v.as_object().is_some() && v.as_array().is_none());
+
+        v.get_mut("primes")
+            .and_then(JSONValue::as_array_mut)
+            .unwrap()
+            .push(json!(5));
+        v.as_object_mut().unwrap().remove("ok");
+        assert_eq!(v.get("primes"), Some(&json!([2, 3, 5])));
+        assert_eq!(v.get("ok"), None);
+    }
+
+    #[test]
+    fn expect_accessors() {
+        let v = json!({ "user": { "name": "jo", "tags": ["a", 1] } });
+        assert_eq!(v.expect_str("/user/name"), Ok("jo"));
+        assert_eq!(v.expect_array("/user/tags").map(|a| a.len()), Ok(2));
+        assert!(v.expect_object("").is_ok());
+
+        let err = v.expect_i64("/user/tags/0").unwrap_err();
+        assert_eq!(err.path(), "/user/tags/0");
+        assert_eq!(err.message(), "Expected integer but found string");
+        let err = v.expect_bool("/user/admin").unwrap_err();
+        assert_eq!(err.path(), "/user/admin");
+        assert_eq!(err.message(), "Expected boolean but found nothing");
+        assert!(v.expect_str("user").is_err());
+    }
+}
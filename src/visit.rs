@@ -0,0 +1,280 @@
+use crate::path::PathSegment;
+use crate::value::JSONValue;
+
+// Called for every value in pre-order, objects in sorted key order; returning
+// false skips the children of `value`.
+pub trait Visitor {
+    fn visit(&mut self, path: &[PathSegment], value: &JSONValue) -> bool;
+}
+
+// Like `Visitor`, but may rewrite each value before its children are visited.
+pub trait VisitorMut {
+    fn visit_mut(&mut self, path: &[PathSegment], value: &mut JSONValue) -> bool;
+}
+
+impl<F: FnMut(&[PathSegment], &JSONValue) -> bool> Visitor for F {
+    fn visit(&mut self, path: &[PathSegment], value: &JSONValue) -> bool {
+        self(path, value)
+    }
+}
+
+impl<F: FnMut(&[PathSegment], &mut JSONValue) -> bool> VisitorMut for F {
+    fn visit_mut(&mut self, path: &[PathSegment], value: &mut JSONValue) -> bool {
+        self(path, value)
+    }
+}
+
+fn sorted_keys(value: &JSONValue) -> Vec<String> {
+    let mut keys: Vec<String> = match value {
+        JSONValue::Object(hm) => hm.keys().cloned().collect(),
+        _ => vec![],
+    };
+    keys.sort();
+    keys
+}
+
+fn walk<V: Visitor + ?Sized>(value: &JSONValue, path: &mut Vec<PathSegment>, visitor: &mut V) {
+    if !visitor.visit(path, value) {
+        return;
+    }
+    match value {
+        JSONValue::Object(hm) => {
+            for k in sorted_keys(value) {
+                let child = &hm[&k];
+                path.push(PathSegment::Key(k));
+                walk(child, path, visitor);
+                path.pop();
+            }
+        }
+        JSONValue::Array(vc) => {
+            for (i, child) in vc.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                walk(child, path, visitor);
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn walk_mut<V: VisitorMut + ?Sized>(
+    value: &mut JSONValue,
+    path: &mut Vec<PathSegment>,
+    visitor: &mut V,
+) {
+    if !visitor.visit_mut(path, value) {
+        return;
+    }
+    let keys = sorted_keys(value);
+    match value {
+        JSONValue::Object(hm) => {
+            for k in keys {
+                let child = hm.get_mut(&k).unwrap();
+                path.push(PathSegment::Key(k));
+                walk_mut(child, path, visitor);
+                path.pop();
+            }
+        }
+        JSONValue::Array(vc) => {
+            for (i, child) in vc.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                walk_mut(child, path, visitor);
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn retain<F: FnMut(&[PathSegment], &JSONValue) -> bool>(
+    value: &mut JSONValue,
+    path: &mut Vec<PathSegment>,
+    keep: &mut F,
+) {
+    let keys = sorted_keys(value);
+    match value {
+        JSONValue::Object(hm) => {
+            for k in keys {
+                path.push(PathSegment::Key(k.clone()));
+                if keep(path, &hm[&k]) {
+                    retain(hm.get_mut(&k).unwrap(), path, keep);
+                } else {
+                    hm.remove(&k);
+                }
+                path.pop();
+            }
+        }
+        JSONValue::Array(vc) => {
+            let mut kept = Vec::with_capacity(vc.len());
+            for (i, mut child) in vc.drain(..).enumerate() {
+                path.push(PathSegment::Index(i));
+                if keep(path, &child) {
+                    retain(&mut child, path, keep);
+                    kept.push(child);
+                }
+                path.pop();
+            }
+            *vc = kept;
+        }
+        _ => (),
+    }
+}
+
+fn map_values<F: FnMut(&[PathSegment], &mut JSONValue)>(
+    value: &mut JSONValue,
+    path: &mut Vec<PathSegment>,
+    f: &mut F,
+) {
+    let keys = sorted_keys(value);
+    match value {
+        JSONValue::Object(hm) => {
+            for k in keys {
+                let child = hm.get_mut(&k).unwrap();
+                path.push(PathSegment::Key(k));
+                map_values(child, path, f);
+                path.pop();
+            }
+        }
+        JSONValue::Array(vc) => {
+            for (i, child) in vc.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                map_values(child, path, f);
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+    f(path, value);
+}
+
+fn map_keys<F: FnMut(&[PathSegment], &str) -> String>(
+    value: &mut JSONValue,
+    path: &mut Vec<PathSegment>,
+    f: &mut F,
+) {
+    let keys = sorted_keys(value);
+    match value {
+        JSONValue::Object(hm) => {
+            let mut renamed = std::collections::HashMap::with_capacity(hm.len());
+            for k in keys {
+                let mut child = hm.remove(&k).unwrap();
+                path.push(PathSegment::Key(k));
+                map_keys(&mut child, path, f);
+                let key = match path.last() {
+                    Some(PathSegment::Key(k)) => f(path, k),
+                    _ => unreachable!(),
+                };
+                path.pop();
+                renamed.insert(key, child);
+            }
+            *hm = renamed;
+        }
+        JSONValue::Array(vc) => {
+            for (i, child) in vc.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                map_keys(child, path, f);
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+impl JSONValue {
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        walk(self, &mut vec![], visitor);
+    }
+
+    pub fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        walk_mut(self, &mut vec![], visitor);
+    }
+
+    // drops every nested member or element for which `keep` is false; kept
+    // containers are then filtered in turn
+    pub fn retain<F: FnMut(&[PathSegment], &JSONValue) -> bool>(&mut self, mut keep: F) {
+        retain(self, &mut vec![], &mut keep);
+    }
+
+    // calls `f` on every value, children before their parent
+    pub fn map_values<F: FnMut(&[PathSegment], &mut JSONValue)>(&mut self, mut f: F) {
+        map_values(self, &mut vec![], &mut f);
+    }
+
+    // renames every object key; `f` gets the member's path and current key,
+    // and when two keys map to the same name the last in sorted order wins
+    pub fn map_keys<F: FnMut(&[PathSegment], &str) -> String>(&mut self, mut f: F) {
+        map_keys(self, &mut vec![], &mut f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn pointer(path: &[PathSegment]) -> String {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn walking() {
+        let v = json!({ "b": [1, { "c": null }], "a": true });
+        let mut seen = vec![];
+        v.walk(&mut |path: &[PathSegment], _: &JSONValue| {
+            seen.push(pointer(path));
+            path.len() < 2
+        });
+        assert_eq!(seen, vec!["", "/a", "/b", "/b/0", "/b/1"]);
+
+        struct Counter(usize);
+        impl Visitor for Counter {
+            fn visit(&mut self, _: &[PathSegment], value: &JSONValue) -> bool {
+                if value.is_number() {
+                    self.0 += 1;
+                }
+                true
+            }
+        }
+        let mut counter = Counter(0);
+        json!([1, [2, { "x": 3 }], "4"]).walk(&mut counter);
+        assert_eq!(counter.0, 3);
+
+        let mut v = json!({ "user": { "password": "hunter2", "name": "jo" } });
+        v.walk_mut(&mut |path: &[PathSegment], value: &mut JSONValue| {
+            if path.last() == Some(&PathSegment::Key("password".to_string())) {
+                *value = json!("***");
+            }
+            true
+        });
+        assert_eq!(v, json!({ "user": { "password": "***", "name": "jo" } }));
+    }
+
+    #[test]
+    fn transforms() {
+        let mut v = json!({ "a": null, "b": [1, null, { "c": null, "d": 2 }] });
+        v.retain(|_, value| !value.is_null());
+        assert_eq!(v, json!({ "b": [1, { "d": 2 }] }));
+
+        let mut v = json!({ "secret": { "password": { "hash": "x" } }, "n": [1, 2] });
+        v.map_values(|path, value| {
+            if let Some(PathSegment::Key(k)) = path.last() {
+                if k == "password" {
+                    *value = json!("***");
+                }
+            }
+            if let JSONValue::Number(n) = value {
+                *n *= 10f64;
+            }
+        });
+        assert_eq!(v, json!({ "secret": { "password": "***" }, "n": [10, 20] }));
+
+        let mut v = json!({ "Name": "jo", "Tags": [{ "ID": 1 }] });
+        let mut paths = vec![];
+        v.map_keys(|path, key| {
+            paths.push(pointer(path));
+            key.to_lowercase()
+        });
+        assert_eq!(v, json!({ "name": "jo", "tags": [{ "id": 1 }] }));
+        assert_eq!(paths, vec!["/Name", "/Tags/0/ID", "/Tags"]);
+    }
+}